@@ -0,0 +1,39 @@
+// Per-scan cost of listing processes: a fresh `System::new_all()` with a full
+// refresh (what every scan used to do) against the shared `ProcessMonitor`,
+// which keeps one `System` and refreshes only exe, name, parent and cmd.
+//
+// Run with `cargo bench --bench process_scan`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use not_enough_accountability_lib::ProcessMonitor;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+fn fresh_system_scan() -> usize {
+    let mut sys = System::new_all();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::everything(),
+    );
+    sys.processes().len()
+}
+
+fn process_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("process_scan");
+
+    group.bench_function("system_new_all", |b| b.iter(fresh_system_scan));
+
+    let monitor = ProcessMonitor::new();
+    // The first scan fills exe/cmd for every process; steady state is what matters
+    monitor.process_details();
+    group.bench_function("shared_monitor", |b| b.iter(|| monitor.process_details().len()));
+
+    group.bench_function("shared_monitor_running_apps", |b| {
+        b.iter(|| monitor.running_apps().len())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, process_scan);
+criterion_main!(benches);
@@ -0,0 +1,6 @@
+// Privileged helper for NEU. Runs as root (see `neu-helper --unit`) and makes
+// the system changes the app asks for over a Unix socket.
+
+fn main() {
+    not_enough_accountability_lib::run_helper()
+}
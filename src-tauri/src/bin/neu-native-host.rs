@@ -0,0 +1,6 @@
+// Native messaging host for the NEU browser extension. Talks to the browser
+// over stdin/stdout, so unlike the app it must stay a console program.
+
+fn main() {
+    not_enough_accountability_lib::run_native_host()
+}
@@ -0,0 +1,330 @@
+use serde::Serialize;
+use std::io;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::dns_sinkhole::SinkholePolicy;
+use crate::loopback_service::{self, HostThrottle, LoopbackServer, Refresh};
+use crate::rules::{AllowlistRule, RuleKind, RuleTiming, WebsiteBlockRule};
+use crate::store::{Store, StoreData};
+
+// How often the server is started, stopped or moved to match the settings
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+// Clients get this long to send their request head
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Visits to the same host are logged at most this often; a page load brings
+// requests for its scripts, images and retries along
+const LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+// Request heads larger than this are cut off; only the request line and the
+// Host header are needed
+const MAX_REQUEST_HEAD: usize = 8192;
+
+// A request for a blocked host, as far as the block page cares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRequest {
+    pub path: String,
+    // Lowercased, without port or trailing dot
+    pub host: String,
+}
+
+// Request line and Host header from a raw request head. `None` if the head
+// isn't HTTP or has no Host.
+pub fn parse_request(head: &str) -> Option<PageRequest> {
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let (_method, path, version) = (request_line.next()?, request_line.next()?, request_line.next()?);
+    if !version.starts_with("HTTP/") {
+        return None;
+    }
+
+    let host = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("host"))
+        .map(|(_, value)| value.trim())?;
+    // Drop the port, keeping bracketed IPv6 literals intact
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if !name.ends_with(':') && port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+
+    Some(PageRequest {
+        path: path.to_string(),
+        host: host.trim_end_matches('.').to_lowercase(),
+    })
+}
+
+// What keeps a host blocked
+#[derive(Debug, Clone)]
+pub enum BlockedBy {
+    Rule(WebsiteBlockRule),
+    // The host isn't on the active allowlist
+    Allowlist(AllowlistRule),
+}
+
+impl BlockedBy {
+    fn timing(&self) -> &RuleTiming {
+        match self {
+            BlockedBy::Rule(rule) => &rule.timing,
+            BlockedBy::Allowlist(rule) => &rule.timing,
+        }
+    }
+
+    // For event messages: "rule: reddit.com", "allowlist: Work"
+    pub fn describe(&self) -> String {
+        match self {
+            BlockedBy::Rule(rule) => format!("rule: {}", rule.domain),
+            BlockedBy::Allowlist(rule) => format!("allowlist: {}", rule.name),
+        }
+    }
+}
+
+// "2h 5m", "45m", "less than a minute"
+pub fn format_remaining(remaining: chrono::Duration) -> String {
+    let minutes = remaining.num_minutes();
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => "less than a minute".to_string(),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub fn render_page(host: &str, blocked_by: &BlockedBy, now: &chrono::DateTime<chrono::Local>) -> String {
+    let timing = blocked_by.timing();
+    let until = match timing.ends_at(now) {
+        Some(end) => format!(
+            "Blocked for another {} (until {}).",
+            format_remaining(end - *now),
+            end.format("%H:%M")
+        ),
+        None => "Blocked until the rule is removed.".to_string(),
+    };
+    let kind = match timing.kind {
+        RuleKind::Timer => "Timer",
+        RuleKind::Schedule => "Schedule",
+        RuleKind::Permanent => "Permanent",
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Blocked by NEU</title>
+<style>
+  body {{ font-family: system-ui, sans-serif; background: #0f172a; color: #e2e8f0; display: flex; align-items: center; justify-content: center; min-height: 100vh; margin: 0; }}
+  main {{ max-width: 32rem; padding: 2.5rem; border-radius: 1rem; background: #1e293b; box-shadow: 0 10px 40px rgba(0, 0, 0, 0.4); }}
+  h1 {{ margin-top: 0; font-size: 1.75rem; }}
+  .host {{ font-family: ui-monospace, monospace; color: #f87171; }}
+  .muted {{ color: #94a3b8; font-size: 0.9rem; }}
+</style>
+</head>
+<body>
+<main>
+  <h1>This site is blocked</h1>
+  <p><span class="host">{host}</span> is {reason} ({kind}).</p>
+  <p>{until}</p>
+  <p class="muted">This visit has been recorded by Not Enough Accountability.</p>
+</main>
+</body>
+</html>
+"#,
+        host = escape_html(host),
+        reason = match blocked_by {
+            BlockedBy::Rule(rule) => format!("blocked by the rule for <strong>{}</strong>", escape_html(&rule.domain)),
+            BlockedBy::Allowlist(rule) => format!("not on the allowlist <strong>{}</strong>", escape_html(&rule.name)),
+        },
+        kind = kind,
+        until = escape_html(&until),
+    )
+}
+
+fn response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+// The active website or allowlist rule that blocks `host`, decided exactly as
+// the DNS sinkhole does
+pub fn blocking_rule(app: &AppHandle, host: &str, now: &chrono::DateTime<chrono::Local>) -> Option<BlockedBy> {
+    app.state::<Store>().read(|data| blocking_rule_in(data, host, now))
+}
+
+pub fn blocking_rule_in(data: &StoreData, host: &str, now: &chrono::DateTime<chrono::Local>) -> Option<BlockedBy> {
+    let rule_id = SinkholePolicy::at(data, now).blocking_rule(host)?.to_string();
+    let rule = data.website_rules.iter().find(|rule| rule.id == rule_id);
+    match rule {
+        Some(rule) => Some(BlockedBy::Rule(rule.clone())),
+        None => data
+            .allowlist_rules
+            .iter()
+            .find(|rule| rule.id == rule_id)
+            .map(|rule| BlockedBy::Allowlist(rule.clone())),
+    }
+}
+
+async fn read_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_HEAD {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    Ok(String::from_utf8_lossy(&head).to_string())
+}
+
+async fn serve_client(app: AppHandle, page: BlockPage, mut stream: TcpStream) {
+    let Ok(Ok(head)) = tokio::time::timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await else {
+        return;
+    };
+    let Some(request) = parse_request(&head) else {
+        let _ = stream
+            .write_all(response("400 Bad Request", "text/plain", "Bad request").as_bytes())
+            .await;
+        return;
+    };
+
+    let now = chrono::Local::now();
+    let reply = match blocking_rule(&app, &request.host, &now) {
+        // Browsers ask for it on every blocked page; not a visit of its own
+        Some(_) if request.path == "/favicon.ico" => response("204 No Content", "text/plain", ""),
+        Some(blocked_by) => {
+            page.log_visit(&app, &request, &blocked_by);
+            response(
+                "403 Forbidden",
+                "text/html; charset=utf-8",
+                &render_page(&request.host, &blocked_by, &now),
+            )
+        }
+        None => response("404 Not Found", "text/plain", "Not blocked by NEU"),
+    };
+    let _ = stream.write_all(reply.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn serve(app: AppHandle, page: BlockPage, listener: TcpListener) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        tokio::spawn(serve_client(app.clone(), page.clone(), stream));
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockPageStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub listen: String,
+    // Why the server isn't running (bad address, port in use, ...)
+    pub error: Option<String>,
+}
+
+// HTTP server on loopback that blocked domains resolve to (0.0.0.0 in the
+// hosts file and from the sinkhole, which Linux and macOS connect to
+// loopback; 127.0.0.1 on Windows). Visits get a page explaining the block
+// instead of a connection error, and are logged. Only plain
+// HTTP lands here: an HTTPS visit still fails, as no valid certificate can be
+// shown for the blocked site.
+#[derive(Clone)]
+pub struct BlockPage {
+    server: LoopbackServer<()>,
+    visits: HostThrottle,
+}
+
+impl BlockPage {
+    pub fn new() -> Self {
+        Self {
+            server: LoopbackServer::new("Block page"),
+            visits: HostThrottle::new(LOG_INTERVAL),
+        }
+    }
+
+    // Spawn the refresh loop. Called once from the `setup` hook.
+    pub fn start(&self, app: AppHandle) {
+        loopback_service::start(self, app, REFRESH_INTERVAL);
+    }
+
+    fn log_visit(&self, app: &AppHandle, request: &PageRequest, blocked_by: &BlockedBy) {
+        let Some(held_back) = self.visits.hit(&request.host) else {
+            return;
+        };
+        let message = format!(
+            "Blocked visit to {}{} ({}){}",
+            request.host,
+            request.path,
+            blocked_by.describe(),
+            loopback_service::held_back_note(held_back)
+        );
+        if let Err(e) = app.state::<Store>().log_event("violation", &request.host, message) {
+            eprintln!("Failed to log block page visit: {}", e);
+        }
+    }
+}
+
+impl Refresh for BlockPage {
+    // Start, stop or move the server to match the settings
+    async fn refresh(&self, app: &AppHandle) {
+        let settings = app.state::<Store>().read(|data| data.settings.clone());
+        let desired = if settings.block_page_enabled {
+            loopback_service::parse_listen(&settings.block_page_listen).map(|listen| Some((listen, ())))
+        } else {
+            Ok(None)
+        };
+        self.server
+            .reconcile(desired, |listen, ()| async move {
+                let listener = loopback_service::bind_tcp(listen).await?;
+                Ok(tauri::async_runtime::spawn(serve(app.clone(), self.clone(), listener)))
+            })
+            .await;
+    }
+}
+
+impl Default for BlockPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[tauri::command]
+pub async fn get_block_page_status(
+    app: AppHandle,
+    page: State<'_, BlockPage>,
+    store: State<'_, Store>,
+) -> Result<BlockPageStatus, String> {
+    // Apply settings saved since the last refresh before reporting
+    page.refresh(&app).await;
+
+    let settings = store.read(|data| data.settings.clone());
+    Ok(BlockPageStatus {
+        enabled: settings.block_page_enabled,
+        running: page.server.is_running().await,
+        listen: settings.block_page_listen,
+        error: page.server.error(),
+    })
+}
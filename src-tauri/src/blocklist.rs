@@ -0,0 +1,337 @@
+// Importer for third-party blocklists. Each line is one of:
+//
+//     0.0.0.0 ads.example.com tracker.example.com    hosts format
+//     ads.example.com                                one domain per line
+//     ||ads.example.com^                             AdBlock network rule
+//
+// `#` and `!` start comments. AdBlock rules that only apply conditionally
+// (`$third-party`, paths, exceptions, cosmetic filters) can't be expressed as
+// a website rule and are counted as unsupported.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use crate::domain;
+use crate::rules::{RuleTiming, WebsiteBlockRule};
+use crate::store::Store;
+
+// Names hosts files map to themselves, not blocks
+const HOSTS_SELF_ENTRIES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+    "0.0.0.0",
+];
+
+// Invalid entries echoed back in the summary, so a bad file is recognisable
+const MAX_INVALID_EXAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub hosts_entries: usize,
+    pub plain_entries: usize,
+    pub adblock_entries: usize,
+    // Listed more than once in the file
+    pub duplicates: usize,
+    pub invalid: usize,
+    pub invalid_examples: Vec<String>,
+    pub unsupported: usize,
+    // Already covered by an existing website rule
+    pub already_blocked: usize,
+    pub imported: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParsedBlocklist {
+    // Unique, in file order
+    pub domains: Vec<String>,
+    pub summary: ImportSummary,
+}
+
+// Timing and options shared by every rule created from one list
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleTemplate {
+    #[serde(flatten)]
+    pub timing: RuleTiming,
+    pub include_subdomains: bool,
+}
+
+enum Entry<'a> {
+    Domains(Vec<&'a str>),
+    Unsupported,
+}
+
+pub fn parse(content: &str) -> ParsedBlocklist {
+    let mut parsed = ParsedBlocklist::default();
+    let mut seen = HashSet::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') || line.starts_with('[') {
+            continue;
+        }
+
+        let summary = &mut parsed.summary;
+        let entry = if line.starts_with("||") || line.starts_with("@@") || line.contains("##") {
+            summary.adblock_entries += 1;
+            parse_adblock(line)
+        } else {
+            // Inline comments only exist in hosts and plain lists
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace().peekable();
+            if tokens.peek().is_some_and(|first| first.parse::<IpAddr>().is_ok()) {
+                summary.hosts_entries += 1;
+                tokens.next();
+                let names: Vec<&str> = tokens
+                    .filter(|name| !HOSTS_SELF_ENTRIES.contains(&name.to_lowercase().as_str()))
+                    .collect();
+                if names.is_empty() {
+                    Entry::Unsupported
+                } else {
+                    Entry::Domains(names)
+                }
+            } else {
+                summary.plain_entries += 1;
+                // Several words without an address isn't a format we know; let
+                // validation reject it as a whole
+                Entry::Domains(vec![line.trim()])
+            }
+        };
+
+        let names = match entry {
+            Entry::Domains(names) => names,
+            Entry::Unsupported => {
+                summary.unsupported += 1;
+                continue;
+            }
+        };
+        for name in names {
+            let Some(domain) = normalize(name) else {
+                summary.invalid += 1;
+                if summary.invalid_examples.len() < MAX_INVALID_EXAMPLES {
+                    summary.invalid_examples.push(name.to_string());
+                }
+                continue;
+            };
+            if seen.insert(domain.clone()) {
+                parsed.domains.push(domain);
+            } else {
+                summary.duplicates += 1;
+            }
+        }
+    }
+
+    parsed
+}
+
+// `||example.com^` blocks the whole domain. Anything narrower is unsupported.
+fn parse_adblock(line: &str) -> Entry<'_> {
+    let Some(rest) = line.strip_prefix("||") else {
+        return Entry::Unsupported;
+    };
+    let (domain, tail) = match rest.find(['^', '/', '$', '*', '|']) {
+        Some(end) => rest.split_at(end),
+        None => (rest, ""),
+    };
+    if matches!(tail, "" | "^" | "^|") {
+        Entry::Domains(vec![domain])
+    } else {
+        Entry::Unsupported
+    }
+}
+
+// Normalized host name without `www.`, as website rules store it. `None` if
+// it isn't a valid domain.
+pub fn normalize(name: &str) -> Option<String> {
+    let domain = domain::normalize(name).ok()?;
+    match domain.strip_prefix("www.") {
+        Some(bare) if bare.contains('.') => Some(bare.to_string()),
+        _ => Some(domain),
+    }
+}
+
+// Add one website rule named `name` that blocks every domain in `parsed` not
+// blocked by another rule yet. Nothing is added if all of them are.
+pub fn import(
+    store: &Store,
+    parsed: ParsedBlocklist,
+    name: &str,
+    template: &RuleTemplate,
+) -> Result<ImportSummary, String> {
+    use rand_core::RngCore;
+
+    let mut summary = parsed.summary;
+    store.update(|data| {
+        let existing: HashSet<String> = data
+            .website_rules
+            .iter()
+            .flat_map(|rule| rule.blocked_domains())
+            .map(|domain| domain.to_lowercase())
+            .collect();
+        let (already_blocked, domains): (Vec<String>, Vec<String>) =
+            parsed.domains.into_iter().partition(|domain| existing.contains(domain));
+        summary.already_blocked = already_blocked.len();
+        summary.imported = domains.len();
+        if domains.is_empty() {
+            return;
+        }
+
+        let created_at = chrono::Utc::now().timestamp_millis();
+        data.website_rules.push(WebsiteBlockRule {
+            id: format!("{}-{:x}", created_at, rand_core::OsRng.next_u64()),
+            domain: name.to_string(),
+            is_active: true,
+            created_at,
+            timing: template.timing.clone(),
+            include_subdomains: template.include_subdomains,
+            domains,
+        });
+    })?;
+    Ok(summary)
+}
+
+pub fn import_file(store: &Store, path: &Path, template: &RuleTemplate) -> Result<ImportSummary, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let name = path
+        .file_name()
+        .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().to_string());
+    import(store, parse(&String::from_utf8_lossy(&bytes)), &name, template)
+}
+
+// Let the user pick a blocklist file. `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn browse_for_blocklist(app: AppHandle) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Blocklists", &["txt", "hosts", "list", "conf"])
+        .set_title("Select a blocklist to import")
+        .pick_file(move |file_path| {
+            let path = file_path.and_then(|fp| fp.as_path().map(|p| p.to_string_lossy().to_string()));
+            let _ = tx.send(path);
+        });
+    Ok(rx.await.unwrap_or(None))
+}
+
+#[tauri::command]
+pub async fn import_blocklist(path: String, template: RuleTemplate, app: AppHandle) -> Result<ImportSummary, String> {
+    // Lists run to hundreds of thousands of lines
+    tauri::async_runtime::spawn_blocking(move || import_file(&app.state::<Store>(), Path::new(&path), &template))
+        .await
+        .map_err(|e| format!("Failed to import the blocklist: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_lines_with_several_names() {
+        let parsed = parse("0.0.0.0 ads.example.com tracker.example.com # trackers\n127.0.0.1 a.example.net\n");
+        assert_eq!(parsed.domains, ["ads.example.com", "tracker.example.com", "a.example.net"]);
+        assert_eq!(parsed.summary.hosts_entries, 2);
+        assert_eq!(parsed.summary.invalid, 0);
+    }
+
+    #[test]
+    fn hosts_self_entries_are_skipped() {
+        let content = "127.0.0.1 localhost\n\
+                       ::1 localhost ip6-localhost ip6-loopback\n\
+                       255.255.255.255 broadcasthost\n\
+                       0.0.0.0 0.0.0.0\n\
+                       127.0.0.1 LocalHost ads.example.com\n";
+        let parsed = parse(content);
+        assert_eq!(parsed.domains, ["ads.example.com"]);
+        assert_eq!(parsed.summary.hosts_entries, 5);
+        assert_eq!(parsed.summary.unsupported, 4);
+        assert_eq!(parsed.summary.invalid, 0);
+    }
+
+    #[test]
+    fn adblock_rules_block_only_whole_domains() {
+        let content = "||ads.example.com^\n\
+                       ||tracker.example.com^$third-party\n\
+                       ||cdn.example.com/ads.js\n\
+                       @@||good.example.com^\n\
+                       example.com##.banner\n\
+                       ||pixel.example.com\n";
+        let parsed = parse(content);
+        assert_eq!(parsed.domains, ["ads.example.com", "pixel.example.com"]);
+        assert_eq!(parsed.summary.adblock_entries, 6);
+        assert_eq!(parsed.summary.unsupported, 4);
+    }
+
+    #[test]
+    fn www_is_folded_into_the_domain() {
+        let parsed = parse("www.example.com\nexample.com\n0.0.0.0 WWW.Example.org\n||www.example.net^\n");
+        assert_eq!(parsed.domains, ["example.com", "example.org", "example.net"]);
+        assert_eq!(parsed.summary.duplicates, 1);
+        // A bare `www.` label has nothing left to fold into
+        assert_eq!(normalize("www.com").as_deref(), Some("www.com"));
+    }
+
+    #[test]
+    fn duplicates_and_invalid_entries_are_counted() {
+        let content = "# comment\n\
+                       ! adblock comment\n\
+                       [Adblock Plus 2.0]\n\
+                       example.com\n\
+                       example.com\n\
+                       0.0.0.0 example.com other.example.com\n\
+                       not a domain\n\
+                       0.0.0.0 bad_name!\n";
+        let parsed = parse(content);
+        assert_eq!(parsed.domains, ["example.com", "other.example.com"]);
+        assert_eq!(parsed.summary.plain_entries, 3);
+        assert_eq!(parsed.summary.hosts_entries, 2);
+        assert_eq!(parsed.summary.duplicates, 2);
+        assert_eq!(parsed.summary.invalid, 2);
+        assert_eq!(parsed.summary.invalid_examples, ["not a domain", "bad_name!"]);
+    }
+
+    #[test]
+    fn a_list_is_imported_as_one_rule() {
+        let dir = std::env::temp_dir().join(format!("neu-blocklist-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = Store::load(dir.join(crate::store::STORE_FILE_NAME)).unwrap();
+        let template = RuleTemplate {
+            timing: crate::rules::RuleTiming::permanent(),
+            include_subdomains: true,
+        };
+        let existing: WebsiteBlockRule = serde_json::from_value(serde_json::json!({
+            "id": "r", "domain": "example.com", "isActive": true, "createdAt": 0, "type": "permanent"
+        }))
+        .unwrap();
+        store.update(|data| data.website_rules.push(existing)).unwrap();
+
+        let summary = import(&store, parse("a.example\nexample.com\nb.example\n"), "ads.txt", &template).unwrap();
+        assert_eq!((summary.imported, summary.already_blocked), (2, 1));
+        let rules = store.read(|data| data.website_rules.clone());
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].domain, "ads.txt");
+        assert_eq!(rules[1].blocked_domains(), ["a.example", "b.example"]);
+
+        // Importing it again adds nothing
+        let summary = import(&store, parse("a.example\nb.example\n"), "ads.txt", &template).unwrap();
+        assert_eq!((summary.imported, summary.already_blocked), (0, 2));
+        assert_eq!(store.read(|data| data.website_rules.len()), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
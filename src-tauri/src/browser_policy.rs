@@ -0,0 +1,1001 @@
+// Enterprise policies that make the browsers themselves refuse blocked sites
+// and paths: Firefox's `WebsiteFilter` and the `URLBlocklist` of Chromium-based
+// browsers. This gives URL rules path-level enforcement without the extension,
+// and the same policies force-install the companion extension when its ids
+// are set. Other policies in the same files are left alone, and our entries
+// come back out once their rules stop being active. Browsers read the files when they
+// start (Chromium also every few hours), so changes show up after a restart.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::domain;
+use crate::helper::HelperClient;
+use crate::rules;
+use crate::store::{Store, StoreData};
+use crate::url_pattern;
+
+// How often the active rules are compared with what was last written
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+// Chromium managed-policy file we own inside a policy directory; holds both
+// `URLBlocklist` and `ExtensionInstallForcelist`
+const CHROMIUM_POLICY_FILE: &str = "neu-url-blocklist.json";
+
+// Next to Firefox's policies.json: the `WebsiteFilter` entries we added, so
+// an administrator's own entries survive their removal
+const FIREFOX_OWNED_FILE: &str = "neu-website-filter.json";
+
+// Next to Firefox's policies.json: the add-on ids we added to
+// `ExtensionSettings`
+const FIREFOX_OWNED_EXTENSIONS_FILE: &str = "neu-extension-settings.json";
+
+// Registry key listing, by name, the Chromium list policy keys we wrote
+const REGISTRY_OWNED_KEY: &str = "HKLM\\SOFTWARE\\NotEnoughAccountability\\UrlPolicies";
+
+// Where Chromium-based browsers update Chrome Web Store extensions from
+const CHROME_WEB_STORE_UPDATE_URL: &str = "https://clients2.google.com/service/update2/crx";
+
+// Where a browser reads its enterprise policies from. Each platform uses only
+// some of these.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub enum PolicyTarget {
+    // Firefox's policies.json; other policies in it are kept
+    FirefoxJson(&'static str),
+    // Directory Chromium-based browsers read every JSON policy file from
+    ChromiumDir(&'static str),
+    // Windows registry key of a Chromium-based browser's policies
+    ChromiumRegistry(&'static str),
+    // Only configuration profiles can set it (Chromium on macOS)
+    Unsupported,
+}
+
+pub struct Browser {
+    pub name: &'static str,
+    // Any of these existing means the browser is installed
+    pub markers: &'static [&'static str],
+    pub target: PolicyTarget,
+}
+
+#[cfg(target_os = "linux")]
+pub const BROWSERS: &[Browser] = &[
+    Browser {
+        name: "Firefox",
+        markers: &["/usr/lib/firefox", "/usr/lib64/firefox", "/opt/firefox", "/snap/bin/firefox", "/usr/bin/firefox"],
+        target: PolicyTarget::FirefoxJson("/etc/firefox/policies/policies.json"),
+    },
+    Browser {
+        name: "Google Chrome",
+        markers: &["/opt/google/chrome"],
+        target: PolicyTarget::ChromiumDir("/etc/opt/chrome/policies/managed"),
+    },
+    Browser {
+        name: "Chromium",
+        markers: &["/usr/lib/chromium", "/usr/lib/chromium-browser", "/snap/bin/chromium", "/usr/bin/chromium"],
+        target: PolicyTarget::ChromiumDir("/etc/chromium/policies/managed"),
+    },
+    Browser {
+        name: "Microsoft Edge",
+        markers: &["/opt/microsoft/msedge"],
+        target: PolicyTarget::ChromiumDir("/etc/opt/edge/policies/managed"),
+    },
+    Browser {
+        name: "Brave",
+        markers: &["/opt/brave.com/brave"],
+        target: PolicyTarget::ChromiumDir("/etc/brave/policies/managed"),
+    },
+];
+
+#[cfg(target_os = "macos")]
+pub const BROWSERS: &[Browser] = &[
+    Browser {
+        name: "Firefox",
+        markers: &["/Applications/Firefox.app"],
+        target: PolicyTarget::FirefoxJson("/Applications/Firefox.app/Contents/Resources/distribution/policies.json"),
+    },
+    Browser {
+        name: "Google Chrome",
+        markers: &["/Applications/Google Chrome.app"],
+        target: PolicyTarget::Unsupported,
+    },
+    Browser {
+        name: "Microsoft Edge",
+        markers: &["/Applications/Microsoft Edge.app"],
+        target: PolicyTarget::Unsupported,
+    },
+    Browser {
+        name: "Brave",
+        markers: &["/Applications/Brave Browser.app"],
+        target: PolicyTarget::Unsupported,
+    },
+];
+
+#[cfg(target_os = "windows")]
+pub const BROWSERS: &[Browser] = &[
+    Browser {
+        name: "Firefox",
+        markers: &["C:\\Program Files\\Mozilla Firefox"],
+        target: PolicyTarget::FirefoxJson("C:\\Program Files\\Mozilla Firefox\\distribution\\policies.json"),
+    },
+    Browser {
+        name: "Google Chrome",
+        markers: &["C:\\Program Files\\Google\\Chrome", "C:\\Program Files (x86)\\Google\\Chrome"],
+        target: PolicyTarget::ChromiumRegistry("HKLM\\SOFTWARE\\Policies\\Google\\Chrome"),
+    },
+    Browser {
+        name: "Microsoft Edge",
+        markers: &["C:\\Program Files (x86)\\Microsoft\\Edge", "C:\\Program Files\\Microsoft\\Edge"],
+        target: PolicyTarget::ChromiumRegistry("HKLM\\SOFTWARE\\Policies\\Microsoft\\Edge"),
+    },
+    Browser {
+        name: "Brave",
+        markers: &["C:\\Program Files\\BraveSoftware\\Brave-Browser"],
+        target: PolicyTarget::ChromiumRegistry("HKLM\\SOFTWARE\\Policies\\BraveSoftware\\Brave"),
+    },
+];
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub const BROWSERS: &[Browser] = &[];
+
+// What the active rules ask the browsers to refuse, in each policy's format
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlBlocklist {
+    // Chromium `URLBlocklist` filters
+    pub chromium: Vec<String>,
+    // Firefox `WebsiteFilter` match patterns
+    pub firefox: Vec<String>,
+    // URL rule patterns Chromium's filter format can't express
+    pub chromium_unsupported: Vec<String>,
+    // `ExtensionInstallForcelist` entries for the companion extension
+    pub chromium_extensions: Vec<String>,
+    // Firefox add-on ids of the companion extension to force-install
+    pub firefox_extensions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowserUrlPolicy {
+    pub browser: String,
+    pub installed: bool,
+    // A URL blocklist policy can be written on this platform
+    pub supported: bool,
+    // NEU entries currently in the browser's policy
+    pub entries: usize,
+    // Another policy file sets `URLBlocklist` too; the browser uses only one
+    pub conflict: bool,
+    // Our policy force-installs the companion extension
+    pub extension_forced: bool,
+    // Policy file or directory
+    pub location: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowserPolicyStatus {
+    pub enabled: bool,
+    pub browsers: Vec<BrowserUrlPolicy>,
+    pub chromium_unsupported: Vec<String>,
+    // Why the last write failed (usually missing root rights)
+    pub error: Option<String>,
+}
+
+pub fn is_installed(browser: &Browser) -> bool {
+    browser.markers.iter().any(|marker| Path::new(marker).exists())
+}
+
+pub fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+// Entries for the website and URL rules active at `now`; empty when the
+// policies or website blocking are switched off
+pub fn blocklist(data: &StoreData, now: &chrono::DateTime<chrono::Local>) -> UrlBlocklist {
+    let mut list = UrlBlocklist::default();
+    if !data.settings.browser_policies_enabled {
+        return list;
+    }
+    // The companion extension stays installed while website blocking is off,
+    // so URL rules are enforced again as soon as it is back on
+    let settings = &data.settings;
+    list.chromium_extensions = settings
+        .chromium_extension_id
+        .iter()
+        .filter(|id| is_chromium_extension_id(id))
+        .map(|id| format!("{};{}", id, CHROME_WEB_STORE_UPDATE_URL))
+        .collect();
+    list.firefox_extensions = settings
+        .firefox_extension_id
+        .iter()
+        .filter(|id| is_firefox_extension_id(id))
+        .cloned()
+        .collect();
+    if !settings.website_blocking_enabled {
+        return list;
+    }
+
+    for rule in rules::active_at(&data.website_rules, now) {
+        for blocked in rule.blocked_domains() {
+            let Ok(domain) = domain::normalize(blocked) else {
+                continue;
+            };
+            if rule.include_subdomains {
+                list.chromium.push(domain.clone());
+                list.firefox.push(format!("*://*.{}/*", domain));
+                continue;
+            }
+            // The domain and its `www.` variant, like the hosts file
+            let mut names = vec![domain.clone()];
+            if !domain.starts_with("www.") {
+                names.push(format!("www.{}", domain));
+            }
+            for name in names {
+                // A leading dot keeps a Chromium filter off the subdomains
+                list.chromium.push(format!(".{}", name));
+                list.firefox.push(format!("*://{}/*", name));
+            }
+        }
+    }
+
+    for rule in rules::active_at(&data.url_rules, now) {
+        let Ok((host, path)) = url_pattern::split_pattern(&rule.pattern) else {
+            continue;
+        };
+        match chromium_filter(&host, path) {
+            Some(filter) => list.chromium.push(filter),
+            None => list.chromium_unsupported.push(rule.pattern.clone()),
+        }
+        list.firefox.extend(firefox_patterns(&host, path));
+    }
+
+    dedup(&mut list.chromium);
+    dedup(&mut list.firefox);
+    list
+}
+
+// Chromium filters cover the host and its subdomains and take the path as a
+// plain prefix, so `*` only works at the end
+fn chromium_filter(host: &str, path: &str) -> Option<String> {
+    let path = path.strip_suffix('*').unwrap_or(path);
+    if path.contains('*') {
+        return None;
+    }
+    Some(format!("{}{}", host, path))
+}
+
+// Firefox match patterns cover the whole path and query; `*.host` includes
+// the host itself
+fn firefox_patterns(host: &str, path: &str) -> Vec<String> {
+    let base = format!("*://*.{}", host);
+    match path {
+        "" | "/" | "/*" => vec![format!("{}/*", base)],
+        path if path.ends_with('*') => vec![format!("{}{}", base, path)],
+        path if path.ends_with('/') => vec![format!("{}{}*", base, path)],
+        // Same segment boundary as the extension: the path itself, anything
+        // below it, or it with a query
+        path => vec![
+            format!("{}{}", base, path),
+            format!("{}{}/*", base, path),
+            format!("{}{}?*", base, path),
+        ],
+    }
+}
+
+// Chromium extension ids are 32 letters from `a` to `p`
+fn is_chromium_extension_id(id: &str) -> bool {
+    id.len() == 32 && id.bytes().all(|b| (b'a'..=b'p').contains(&b))
+}
+
+// Firefox add-on ids are `name@domain` or a `{GUID}`. They end up in policy
+// keys and a URL, so nothing else gets through.
+fn is_firefox_extension_id(id: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "-._@{}".contains(c);
+    id.len() <= 80
+        && id.chars().all(allowed)
+        && (id.contains('@') || (id.len() > 2 && id.starts_with('{') && id.ends_with('}')))
+}
+
+// Latest version of an add-on on addons.mozilla.org
+fn firefox_install_url(id: &str) -> String {
+    format!(
+        "https://addons.mozilla.org/firefox/downloads/latest/{}/latest.xpi",
+        id.replace('{', "%7B").replace('}', "%7D")
+    )
+}
+
+fn dedup(entries: &mut Vec<String>) {
+    let mut seen = HashSet::new();
+    entries.retain(|entry| seen.insert(entry.clone()));
+}
+
+// Policy state of every browser this platform knows about
+pub fn policies() -> Vec<BrowserUrlPolicy> {
+    BROWSERS.iter().map(|browser| inspect(browser, None)).collect()
+}
+
+// Put `list` into the policies of every installed browser, replacing what we
+// wrote before; entries come out of every browser's policy. Needs root or
+// administrator rights.
+pub fn write_blocklist(list: &UrlBlocklist) -> Vec<BrowserUrlPolicy> {
+    let nothing = UrlBlocklist::default();
+    BROWSERS
+        .iter()
+        .map(|browser| {
+            let list = if is_installed(browser) { list } else { &nothing };
+            let written = match browser.target {
+                PolicyTarget::FirefoxJson(path) => {
+                    let filter = write_firefox_filter(Path::new(path), &list.firefox);
+                    let extensions = write_firefox_extensions(Path::new(path), &list.firefox_extensions);
+                    filter.and(extensions)
+                }
+                PolicyTarget::ChromiumDir(dir) => {
+                    write_chromium_policies(Path::new(dir), &list.chromium, &list.chromium_extensions)
+                }
+                PolicyTarget::ChromiumRegistry(key) => {
+                    let blocklist = write_registry_list(key, "URLBlocklist", &list.chromium);
+                    let extensions = write_registry_list(key, "ExtensionInstallForcelist", &list.chromium_extensions);
+                    blocklist.and(extensions)
+                }
+                PolicyTarget::Unsupported => Ok(()),
+            };
+            inspect(browser, written.err())
+        })
+        .collect()
+}
+
+// Write through the privileged helper when one is running
+pub fn apply(list: &UrlBlocklist) -> Result<Vec<BrowserUrlPolicy>, String> {
+    match HelperClient::connect() {
+        Some(helper) => helper.set_url_blocklist(list),
+        None => Ok(write_blocklist(list)),
+    }
+}
+
+fn inspect(browser: &Browser, error: Option<String>) -> BrowserUrlPolicy {
+    let (location, entries, conflict, extension_forced) = match browser.target {
+        PolicyTarget::FirefoxJson(path) => {
+            let path = Path::new(path);
+            let owned = firefox_owned(&path.with_file_name(FIREFOX_OWNED_FILE));
+            let extensions = firefox_owned(&path.with_file_name(FIREFOX_OWNED_EXTENSIONS_FILE));
+            (Some(path.display().to_string()), owned.len(), false, !extensions.is_empty())
+        }
+        PolicyTarget::ChromiumDir(dir) => {
+            let (ours, others) = chromium_blocklists(Path::new(dir));
+            let extension_forced = read_json(&Path::new(dir).join(CHROMIUM_POLICY_FILE))
+                .is_some_and(|policies| policies["ExtensionInstallForcelist"].is_array());
+            (Some(dir.to_string()), ours, others, extension_forced)
+        }
+        PolicyTarget::ChromiumRegistry(key) => {
+            let (ours, others) = registry_owned_list(key, "URLBlocklist");
+            let (extensions, _) = registry_owned_list(key, "ExtensionInstallForcelist");
+            (Some(key.to_string()), ours, others, extensions > 0)
+        }
+        PolicyTarget::Unsupported => (None, 0, false, false),
+    };
+    BrowserUrlPolicy {
+        browser: browser.name.to_string(),
+        installed: is_installed(browser),
+        supported: !matches!(browser.target, PolicyTarget::Unsupported),
+        entries,
+        conflict,
+        extension_forced,
+        location,
+        error,
+    }
+}
+
+// Firefox keeps every policy in one policies.json, which both the website
+// filter here and the DoH policy (`doh`) read, change and write back. One
+// writer at a time, or one would undo the other's change.
+pub static FIREFOX_POLICIES_LOCK: Mutex<()> = Mutex::new(());
+
+fn firefox_owned(path: &Path) -> Vec<String> {
+    read_json(path)
+        .and_then(|owned| serde_json::from_value(owned).ok())
+        .unwrap_or_default()
+}
+
+// policies.json as it is, or an empty one if there is none yet
+fn read_firefox_policies(path: &Path) -> Result<serde_json::Value, String> {
+    let existing = read_json(path);
+    if existing.is_none() && path.exists() {
+        return Err(format!("{} is not valid JSON; leaving it alone", path.display()));
+    }
+    let mut policies = existing.unwrap_or_else(|| serde_json::json!({ "policies": {} }));
+    if !policies["policies"].is_object() {
+        policies["policies"] = serde_json::json!({});
+    }
+    Ok(policies)
+}
+
+// Write `policies` if it changed from `before`, removing the file once
+// nothing is left in it, and record `added` in `owned_path` as ours
+fn save_firefox_policies(
+    path: &Path,
+    before: &serde_json::Value,
+    policies: &serde_json::Value,
+    owned_path: &Path,
+    owned: &[String],
+    added: &[String],
+) -> Result<(), String> {
+    if policies != before {
+        if *policies == serde_json::json!({ "policies": {} }) {
+            remove_file(path)?;
+        } else {
+            write_json(path, policies)?;
+        }
+    }
+    if added.is_empty() {
+        remove_file(owned_path)
+    } else if added != owned {
+        write_json(owned_path, &serde_json::json!(added))
+    } else {
+        Ok(())
+    }
+}
+
+// Remove the policy `name` once nothing is left in it
+fn remove_if_empty(policies: &mut serde_json::Value, name: &str) {
+    if policies["policies"][name].as_object().is_none_or(|policy| policy.is_empty()) {
+        if let Some(policies) = policies["policies"].as_object_mut() {
+            policies.remove(name);
+        }
+    }
+}
+
+// `{"policies": {"WebsiteFilter": {"Block": [...]}}}`, with the entries we
+// added last time swapped for `entries`
+fn write_firefox_filter(path: &Path, entries: &[String]) -> Result<(), String> {
+    let _guard = FIREFOX_POLICIES_LOCK.lock().unwrap();
+    let owned_path = path.with_file_name(FIREFOX_OWNED_FILE);
+    let owned = firefox_owned(&owned_path);
+    let before = read_firefox_policies(path)?;
+    let mut policies = before.clone();
+
+    let filter = &mut policies["policies"]["WebsiteFilter"];
+    let mut block: Vec<String> = filter["Block"]
+        .as_array()
+        .map(|block| block.iter().filter_map(|entry| entry.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    block.retain(|entry| !owned.contains(entry));
+    // Entries an administrator already lists stay theirs
+    let added: Vec<String> = entries.iter().filter(|entry| !block.contains(entry)).cloned().collect();
+    block.extend(added.iter().cloned());
+
+    if block.is_empty() {
+        if let Some(filter) = filter.as_object_mut() {
+            filter.remove("Block");
+        }
+    } else {
+        filter["Block"] = serde_json::json!(block);
+    }
+    remove_if_empty(&mut policies, "WebsiteFilter");
+    save_firefox_policies(path, &before, &policies, &owned_path, &owned, &added)
+}
+
+// `{"policies": {"ExtensionSettings": {"<id>": {"installation_mode":
+// "force_installed", ...}}}}` for each of `ids`, replacing the ones we added
+// last time. Settings an administrator made for an add-on stay theirs.
+fn write_firefox_extensions(path: &Path, ids: &[String]) -> Result<(), String> {
+    let _guard = FIREFOX_POLICIES_LOCK.lock().unwrap();
+    let owned_path = path.with_file_name(FIREFOX_OWNED_EXTENSIONS_FILE);
+    let owned = firefox_owned(&owned_path);
+    let before = read_firefox_policies(path)?;
+    let mut policies = before.clone();
+
+    let settings = &mut policies["policies"]["ExtensionSettings"];
+    if let Some(settings) = settings.as_object_mut() {
+        settings.retain(|id, _| !owned.contains(id));
+    }
+    let added: Vec<String> = ids
+        .iter()
+        .filter(|id| settings.get(id.as_str()).is_none())
+        .cloned()
+        .collect();
+    for id in &added {
+        settings[id.as_str()] = serde_json::json!({
+            "installation_mode": "force_installed",
+            "install_url": firefox_install_url(id),
+        });
+    }
+    remove_if_empty(&mut policies, "ExtensionSettings");
+    save_firefox_policies(path, &before, &policies, &owned_path, &owned, &added)
+}
+
+// Our entries and whether any other file in the directory sets the policy
+fn chromium_blocklists(dir: &Path) -> (usize, bool) {
+    let Ok(files) = fs::read_dir(dir) else {
+        return (0, false);
+    };
+    let mut ours = 0;
+    let mut others = false;
+    for path in files.flatten().map(|file| file.path()) {
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Some(list) = read_json(&path).and_then(|policies| policies["URLBlocklist"].as_array().cloned()) else {
+            continue;
+        };
+        if path.file_name().is_some_and(|name| name == CHROMIUM_POLICY_FILE) {
+            ours = list.len();
+        } else {
+            others = true;
+        }
+    }
+    (ours, others)
+}
+
+// Our file holds only our policies, so it is rewritten as a whole
+fn write_chromium_policies(dir: &Path, blocklist: &[String], extensions: &[String]) -> Result<(), String> {
+    let path = dir.join(CHROMIUM_POLICY_FILE);
+    let mut policies = serde_json::Map::new();
+    if !blocklist.is_empty() {
+        policies.insert("URLBlocklist".to_string(), serde_json::json!(blocklist));
+    }
+    if !extensions.is_empty() {
+        policies.insert("ExtensionInstallForcelist".to_string(), serde_json::json!(extensions));
+    }
+    if policies.is_empty() {
+        return remove_file(&path);
+    }
+    let policies = serde_json::Value::Object(policies);
+    if read_json(&path).as_ref() == Some(&policies) {
+        return Ok(());
+    }
+    write_json(&path, &policies)
+}
+
+// `reg query` output ends with the value's data
+pub fn registry_value(key: &str, name: &str) -> Option<String> {
+    let output = std::process::Command::new("reg")
+        .args(["query", key, "/v", name])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .last()
+        .map(str::to_string)
+}
+
+pub fn reg(key: &str, args: &[&str]) -> Result<(), String> {
+    let output = std::process::Command::new("reg")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to update {} (requires admin): {}",
+            key,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+
+// `reg query` lines for a key's values: `    <name>    <type>    <data>`
+fn parse_reg_values(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim_start().splitn(3, "    ");
+            let name = fields.next()?;
+            let kind = fields.next()?;
+            kind.starts_with("REG_")
+                .then(|| (name.to_string(), fields.next().unwrap_or_default().to_string()))
+        })
+        .collect()
+}
+
+// Entries of a Chromium list policy in the registry: the values `1`, `2`,
+// ... of its key. `None` if the key doesn't exist.
+fn registry_list(key: &str) -> Option<Vec<String>> {
+    let output = std::process::Command::new("reg").args(["query", key]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut values: Vec<(u32, String)> = parse_reg_values(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter_map(|(name, data)| Some((name.parse().ok()?, data)))
+        .collect();
+    values.sort();
+    Some(values.into_iter().map(|(_, data)| data).collect())
+}
+
+// Our entries in the list policy `name` and whether someone else set it
+fn registry_owned_list(key: &str, name: &str) -> (usize, bool) {
+    let list_key = format!("{}\\{}", key, name);
+    let owned = registry_value(REGISTRY_OWNED_KEY, &list_key).is_some();
+    match registry_list(&list_key) {
+        Some(list) if owned => (list.len(), false),
+        Some(_) => (0, true),
+        None => (0, false),
+    }
+}
+
+// Replace the list policy `name` under `key` with `entries`. Chromium reads
+// one list per policy from the registry, so a list set by someone else is
+// left alone and reported instead.
+fn write_registry_list(key: &str, name: &str, entries: &[String]) -> Result<(), String> {
+    let list_key = format!("{}\\{}", key, name);
+    let owned = registry_value(REGISTRY_OWNED_KEY, &list_key).is_some();
+    let existing = registry_list(&list_key);
+    if existing.is_some() && !owned {
+        return if entries.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("{} is set by someone else; leaving it alone", list_key))
+        };
+    }
+    if entries.is_empty() {
+        if owned {
+            if existing.is_some() {
+                reg(&list_key, &["delete", &list_key, "/f"])?;
+            }
+            reg(REGISTRY_OWNED_KEY, &["delete", REGISTRY_OWNED_KEY, "/v", &list_key, "/f"])?;
+        }
+        return Ok(());
+    }
+    if existing.as_deref() == Some(entries) {
+        return Ok(());
+    }
+    // Claimed first, so a list left half written is still ours to clean up
+    reg(
+        REGISTRY_OWNED_KEY,
+        &["add", REGISTRY_OWNED_KEY, "/v", &list_key, "/t", "REG_SZ", "/d", name, "/f"],
+    )?;
+    import_reg_file(&list_key, &reg_file(&list_key, entries))
+}
+
+// .reg file replacing `key` with a list of `entries`. One `reg import`
+// instead of a `reg add` per entry, which would take minutes for long lists.
+fn reg_file(key: &str, entries: &[String]) -> String {
+    let key = key.replacen("HKLM\\", "HKEY_LOCAL_MACHINE\\", 1);
+    let mut content = format!("Windows Registry Editor Version 5.00\r\n\r\n[-{key}]\r\n\r\n[{key}]\r\n");
+    for (index, entry) in entries.iter().enumerate() {
+        let entry = entry.replace('\\', "\\\\").replace('"', "\\\"");
+        content.push_str(&format!("\"{}\"=\"{}\"\r\n", index + 1, entry));
+    }
+    content
+}
+
+fn import_reg_file(key: &str, content: &str) -> Result<(), String> {
+    // UTF-16 with a byte order mark, as regedit writes them
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(content.encode_utf16().flat_map(u16::to_le_bytes));
+    let path = std::env::temp_dir().join(format!("neu-policy-{}.reg", std::process::id()));
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    let imported = reg(key, &["import", &path.to_string_lossy()]);
+    let _ = fs::remove_file(&path);
+    imported
+}
+
+// Replace `path` without a browser ever reading a partial file: write a temp
+// file next to it, fsync it, then rename it over (as `hosts::write_atomically`
+// does). The temp name doesn't end in `.json`, so Chromium skips it.
+pub fn write_json(path: &Path, value: &serde_json::Value) -> Result<(), String> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| format!("{} has no parent directory", path.display()))?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+
+    let tmp_path = dir.join(format!(".{}.neu-{}.tmp", name, std::process::id()));
+    let written = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        if let Ok(original) = fs::metadata(path) {
+            fs::set_permissions(&tmp_path, original.permissions())?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    written.map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Failed to write {} (requires admin): {}", path.display(), e)
+    })
+}
+
+pub fn remove_file(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {} (requires admin): {}", path.display(), e)),
+    }
+}
+
+// Keeps the browsers' policies in line with the active rules. Writes only
+// when the entries change, and retries failed writes on the next refresh.
+#[derive(Clone)]
+pub struct BrowserPolicies {
+    // Last blocklist written without errors
+    written: Arc<tokio::sync::Mutex<Option<UrlBlocklist>>>,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl BrowserPolicies {
+    pub fn new() -> Self {
+        Self {
+            written: Arc::new(tokio::sync::Mutex::new(None)),
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Spawn the refresh loop. Called once from the `setup` hook.
+    pub fn start(&self, app: AppHandle) {
+        let policies = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut ticker = tokio::time::interval(REFRESH_INTERVAL);
+            loop {
+                ticker.tick().await;
+                policies.refresh(&app).await;
+            }
+        });
+    }
+
+    // Rewrite the policies if the active rules or settings changed
+    async fn refresh(&self, app: &AppHandle) {
+        let now = chrono::Local::now();
+        let desired = app.state::<Store>().read(|data| blocklist(data, &now));
+
+        let mut written = self.written.lock().await;
+        if written.as_ref() == Some(&desired) {
+            return;
+        }
+        let list = desired.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || apply(&list))
+            .await
+            .map_err(|e| format!("Browser policy task failed: {}", e))
+            .and_then(|result| result);
+
+        let error = match result {
+            Ok(browsers) => {
+                let errors: Vec<String> = browsers
+                    .iter()
+                    .filter_map(|browser| {
+                        browser.error.as_ref().map(|e| format!("{}: {}", browser.browser, e))
+                    })
+                    .collect();
+                (!errors.is_empty()).then(|| errors.join("; "))
+            }
+            Err(e) => Some(e),
+        };
+        if error.is_none() {
+            *written = Some(desired);
+        }
+        self.set_error(error);
+    }
+
+    fn set_error(&self, error: Option<String>) {
+        let mut current = self.error.lock().unwrap();
+        if error.is_some() && *current != error {
+            eprintln!("Browser policies: {}", error.as_deref().unwrap_or_default());
+        }
+        *current = error;
+    }
+}
+
+impl Default for BrowserPolicies {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[tauri::command]
+pub async fn get_browser_policy_status(
+    app: AppHandle,
+    policies: State<'_, BrowserPolicies>,
+    store: State<'_, Store>,
+) -> Result<BrowserPolicyStatus, String> {
+    // Apply rules and settings saved since the last refresh before reporting
+    policies.refresh(&app).await;
+
+    let now = chrono::Local::now();
+    let (enabled, list) = store.read(|data| {
+        (data.settings.browser_policies_enabled, blocklist(data, &now))
+    });
+    let browsers = tauri::async_runtime::spawn_blocking(self::policies)
+        .await
+        .map_err(|e| format!("Browser policy task failed: {}", e))?;
+    Ok(BrowserPolicyStatus {
+        enabled,
+        browsers,
+        chromium_unsupported: list.chromium_unsupported,
+        error: policies.error.lock().unwrap().clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("neu-browser-policy-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn firefox_filter_is_merged_and_taken_back_out() {
+        let dir = temp_dir("firefox-filter");
+        let path = dir.join("policies.json");
+        let admin = json!({
+            "policies": {
+                "DisableTelemetry": true,
+                "WebsiteFilter": { "Block": ["*://admin.example/*"], "Exceptions": ["*://admin.example/ok"] }
+            }
+        });
+        write_json(&path, &admin).unwrap();
+
+        write_firefox_filter(&path, &strings(&["*://a.example/*", "*://admin.example/*"])).unwrap();
+        let policies = read_json(&path).unwrap();
+        assert_eq!(policies["policies"]["DisableTelemetry"], true);
+        assert_eq!(
+            policies["policies"]["WebsiteFilter"]["Block"],
+            json!(["*://admin.example/*", "*://a.example/*"])
+        );
+        // The administrator's entry stays theirs
+        assert_eq!(firefox_owned(&dir.join(FIREFOX_OWNED_FILE)), ["*://a.example/*"]);
+
+        write_firefox_filter(&path, &[]).unwrap();
+        assert_eq!(read_json(&path).unwrap(), admin);
+        assert!(!dir.join(FIREFOX_OWNED_FILE).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn firefox_policies_we_created_are_removed_again() {
+        let dir = temp_dir("firefox-new");
+        let path = dir.join("policies.json");
+
+        write_firefox_filter(&path, &strings(&["*://a.example/*"])).unwrap();
+        write_firefox_extensions(&path, &strings(&["neu@example.org"])).unwrap();
+        let policies = read_json(&path).unwrap();
+        assert_eq!(policies["policies"]["WebsiteFilter"]["Block"], json!(["*://a.example/*"]));
+        assert_eq!(
+            policies["policies"]["ExtensionSettings"]["neu@example.org"]["installation_mode"],
+            "force_installed"
+        );
+
+        write_firefox_filter(&path, &[]).unwrap();
+        write_firefox_extensions(&path, &[]).unwrap();
+        assert!(!path.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn firefox_extension_settings_of_others_are_kept() {
+        let dir = temp_dir("firefox-extensions");
+        let path = dir.join("policies.json");
+        let admin = json!({
+            "policies": {
+                "ExtensionSettings": {
+                    "*": { "installation_mode": "blocked" },
+                    "admin@example.org": { "installation_mode": "allowed" }
+                }
+            }
+        });
+        write_json(&path, &admin).unwrap();
+
+        write_firefox_extensions(&path, &strings(&["neu@example.org", "admin@example.org"])).unwrap();
+        let policies = read_json(&path).unwrap();
+        let settings = &policies["policies"]["ExtensionSettings"];
+        assert_eq!(settings["*"], admin["policies"]["ExtensionSettings"]["*"]);
+        assert_eq!(settings["admin@example.org"]["installation_mode"], "allowed");
+        assert_eq!(
+            settings["neu@example.org"]["install_url"],
+            "https://addons.mozilla.org/firefox/downloads/latest/neu@example.org/latest.xpi"
+        );
+
+        write_firefox_extensions(&path, &[]).unwrap();
+        assert_eq!(read_json(&path).unwrap(), admin);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_firefox_policies_are_left_alone() {
+        let dir = temp_dir("firefox-invalid");
+        let path = dir.join("policies.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        assert!(write_firefox_filter(&path, &strings(&["*://a.example/*"])).is_err());
+        assert!(write_firefox_extensions(&path, &strings(&["neu@example.org"])).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chromium_policies_go_in_their_own_file() {
+        let dir = temp_dir("chromium");
+        let admin = json!({ "URLBlocklist": ["admin.example"], "HomepageLocation": "https://example.org" });
+        write_json(&dir.join("admin.json"), &admin).unwrap();
+
+        let extension = format!("{};{}", "a".repeat(32), CHROME_WEB_STORE_UPDATE_URL);
+        write_chromium_policies(&dir, &strings(&["a.example", ".b.example"]), std::slice::from_ref(&extension)).unwrap();
+        assert_eq!(
+            read_json(&dir.join(CHROMIUM_POLICY_FILE)).unwrap(),
+            json!({ "URLBlocklist": ["a.example", ".b.example"], "ExtensionInstallForcelist": [extension] })
+        );
+        // Chromium uses one of the two lists; the other file is reported
+        assert_eq!(chromium_blocklists(&dir), (2, true));
+
+        write_chromium_policies(&dir, &[], &[]).unwrap();
+        assert!(!dir.join(CHROMIUM_POLICY_FILE).exists());
+        assert_eq!(read_json(&dir.join("admin.json")).unwrap(), admin);
+        assert_eq!(chromium_blocklists(&dir), (0, true));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn companion_extension_is_forced_only_with_valid_ids() {
+        let mut data = StoreData::default();
+        data.settings.browser_policies_enabled = true;
+        data.settings.website_blocking_enabled = false;
+        data.settings.chromium_extension_id = Some("abcdefghijklmnopabcdefghijklmnop".to_string());
+        data.settings.firefox_extension_id = Some("{0b3d4c5e-1111-4222-8333-944455556666}".to_string());
+        let list = blocklist(&data, &chrono::Local::now());
+        assert_eq!(
+            list.chromium_extensions,
+            [format!("abcdefghijklmnopabcdefghijklmnop;{}", CHROME_WEB_STORE_UPDATE_URL)]
+        );
+        assert_eq!(list.firefox_extensions, ["{0b3d4c5e-1111-4222-8333-944455556666}"]);
+        assert!(firefox_install_url(&list.firefox_extensions[0]).contains("/%7B0b3d4c5e-"));
+
+        data.settings.chromium_extension_id = Some("not-an-id;https://evil.example".to_string());
+        data.settings.firefox_extension_id = Some("\"}, \"evil\": {".to_string());
+        let list = blocklist(&data, &chrono::Local::now());
+        assert!(list.chromium_extensions.is_empty());
+        assert!(list.firefox_extensions.is_empty());
+
+        data.settings.browser_policies_enabled = false;
+        data.settings.firefox_extension_id = Some("neu@example.org".to_string());
+        assert_eq!(blocklist(&data, &chrono::Local::now()), UrlBlocklist::default());
+    }
+
+    #[test]
+    fn registry_lists_are_read_and_written() {
+        let output = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Policies\\Google\\Chrome\\URLBlocklist\r\n    \
+                      2    REG_SZ    b.example\r\n    1    REG_SZ    a.example/some path\r\n\r\n";
+        assert_eq!(
+            parse_reg_values(output),
+            [
+                ("2".to_string(), "b.example".to_string()),
+                ("1".to_string(), "a.example/some path".to_string())
+            ]
+        );
+
+        let content = reg_file("HKLM\\SOFTWARE\\Policies\\Google\\Chrome\\URLBlocklist", &strings(&["a.example", "q\"\\"]));
+        assert_eq!(
+            content,
+            "Windows Registry Editor Version 5.00\r\n\r\n\
+             [-HKEY_LOCAL_MACHINE\\SOFTWARE\\Policies\\Google\\Chrome\\URLBlocklist]\r\n\r\n\
+             [HKEY_LOCAL_MACHINE\\SOFTWARE\\Policies\\Google\\Chrome\\URLBlocklist]\r\n\
+             \"1\"=\"a.example\"\r\n\
+             \"2\"=\"q\\\"\\\\\"\r\n"
+        );
+    }
+}
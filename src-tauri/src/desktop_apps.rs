@@ -0,0 +1,494 @@
+use base64::Engine;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::AppInfo;
+
+// Icons larger than this are left out rather than inlined into the app list
+const MAX_ICON_BYTES: u64 = 256 * 1024;
+
+// Preferred icon sizes, best first. Flatpak and most distros ship hicolor.
+const ICON_SIZES: &[&str] = &["48x48", "64x64", "128x128", "scalable", "256x256", "32x32"];
+
+// Installed applications from XDG desktop entries
+pub fn installed_apps() -> Vec<AppInfo> {
+    let mut apps = Vec::new();
+    // Desktop file IDs already seen; an entry in an earlier directory shadows
+    // (or, if hidden, removes) the same ID in later ones
+    let mut seen = HashSet::new();
+
+    for dir in application_dirs() {
+        for (id, path) in desktop_files(&dir) {
+            if !seen.insert(id) {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let Some(entry) = DesktopEntry::parse(&content) else {
+                continue;
+            };
+            if let Some(app) = entry.to_app_info() {
+                apps.push(app);
+            }
+        }
+    }
+
+    apps
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+fn data_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".local/share")))
+}
+
+// XDG data directories in precedence order, with the Flatpak and Snap export
+// dirs appended in case the session doesn't list them in XDG_DATA_DIRS
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = data_home().into_iter().collect();
+
+    let system = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(system.split(':').map(PathBuf::from).filter(|dir| dir.is_absolute()));
+
+    if let Some(data_home) = data_home() {
+        dirs.push(data_home.join("flatpak/exports/share"));
+    }
+    dirs.push(PathBuf::from("/var/lib/flatpak/exports/share"));
+    dirs.push(PathBuf::from("/var/lib/snapd/desktop"));
+
+    let mut seen = HashSet::new();
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
+
+fn application_dirs() -> Vec<PathBuf> {
+    data_dirs().into_iter().map(|dir| dir.join("applications")).collect()
+}
+
+// `.desktop` files under `dir` with their desktop file IDs
+// (`kde/konsole.desktop` has the ID `kde-konsole.desktop`)
+fn desktop_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    fn walk(dir: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                walk(&path, &format!("{}{}-", prefix, name), files);
+            } else if name.ends_with(".desktop") {
+                files.push((format!("{}{}", prefix, name), path));
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(dir, "", &mut files);
+    files.sort();
+    files
+}
+
+// The `[Desktop Entry]` group of a desktop file
+struct DesktopEntry {
+    values: HashMap<String, String>,
+}
+
+impl DesktopEntry {
+    fn parse(content: &str) -> Option<Self> {
+        let mut values = HashMap::new();
+        let mut in_entry = false;
+        let mut found = false;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                found |= in_entry;
+                continue;
+            }
+            if !in_entry {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                values
+                    .entry(key.trim().to_string())
+                    .or_insert_with(|| unescape_value(value.trim()));
+            }
+        }
+
+        found.then_some(Self { values })
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str).filter(|v| !v.is_empty())
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.get(key) == Some("true")
+    }
+
+    // Name in the user's language if the entry has one, e.g. `Name[de]`
+    fn localized_name(&self) -> Option<&str> {
+        let locale = std::env::var("LC_ALL")
+            .or_else(|_| std::env::var("LC_MESSAGES"))
+            .or_else(|_| std::env::var("LANG"))
+            .unwrap_or_default();
+        // `de_DE.UTF-8@euro` -> try `de_DE`, then `de`
+        let locale = locale.split(['.', '@']).next().unwrap_or("");
+        let language = locale.split('_').next().unwrap_or("");
+
+        let localized = [locale, language]
+            .into_iter()
+            .filter(|tag| !tag.is_empty() && *tag != "C" && *tag != "POSIX")
+            .find_map(|tag| self.get(&format!("Name[{}]", tag)));
+        localized.or_else(|| self.get("Name"))
+    }
+
+    fn to_app_info(&self) -> Option<AppInfo> {
+        if self.get("Type") != Some("Application") || self.flag("NoDisplay") || self.flag("Hidden") {
+            return None;
+        }
+
+        // Per the spec, an entry whose TryExec can't be found isn't installed
+        if let Some(try_exec) = self.get("TryExec") {
+            find_executable(try_exec)?;
+        }
+
+        let args = split_exec(self.get("Exec")?);
+        let path = resolve_exec(&args)?;
+
+        Some(AppInfo {
+            name: self.localized_name()?.to_string(),
+            path: path.to_string_lossy().to_string(),
+            pid: None,
+            icon: self.get("Icon").and_then(icon_data_url),
+        })
+    }
+}
+
+// Backslash escapes allowed in string values
+fn unescape_value(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                // Leave the Exec quoting escapes (\" \` \$) for split_exec
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+// Split an Exec value into arguments following the spec's quoting rules and
+// drop field codes (%f, %U, ...). `%%` is a literal `%`.
+fn split_exec(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    args.into_iter()
+        .filter(|arg| !(arg.len() == 2 && arg.starts_with('%') && arg != "%%"))
+        .map(|arg| arg.replace("%%", "%"))
+        .collect()
+}
+
+// The binary an Exec line actually runs, looking through `env`, `flatpak run`
+// and `/snap/bin` launchers
+fn resolve_exec(args: &[String]) -> Option<PathBuf> {
+    let mut args = args.iter().map(String::as_str).peekable();
+    let mut program = args.next()?;
+
+    // `env FOO=bar program ...`
+    if file_name(program) == "env" {
+        while args.peek().is_some_and(|arg| arg.contains('=') || arg.starts_with('-')) {
+            args.next();
+        }
+        program = args.next()?;
+    }
+
+    let rest: Vec<&str> = args.collect();
+
+    if file_name(program) == "flatpak" && rest.first() == Some(&"run") {
+        if let Some(binary) = resolve_flatpak(&rest[1..]) {
+            return Some(binary);
+        }
+    }
+
+    let path = find_executable(program)?;
+
+    if let Ok(suffix) = path.strip_prefix("/snap/bin") {
+        if let Some(binary) = resolve_snap(&suffix.to_string_lossy()) {
+            return Some(binary);
+        }
+    }
+
+    // /proc/<pid>/exe reports the real file, so follow symlinks like
+    // /usr/bin/firefox -> /usr/lib/firefox/firefox
+    Some(fs::canonicalize(&path).unwrap_or(path))
+}
+
+fn file_name(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+// Absolute paths as-is, bare names looked up on PATH
+fn find_executable(program: &str) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let is_executable = |path: &Path| {
+        fs::metadata(path)
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return (path.is_absolute() && is_executable(&path)).then_some(path);
+    }
+
+    let search = std::env::var("PATH").unwrap_or_else(|_| "/usr/local/bin:/usr/bin:/bin".to_string());
+    let found = search
+        .split(':')
+        .chain(["/usr/local/bin", "/usr/bin", "/bin", "/snap/bin"])
+        .map(|dir| Path::new(dir).join(program))
+        .find(|path| is_executable(path));
+    found
+}
+
+// `flatpak run [options] app.id [args]`: the app's command inside its
+// deployed `files` dir. Processes inside the sandbox report this file name.
+fn resolve_flatpak(args: &[&str]) -> Option<PathBuf> {
+    let mut command = None;
+    let mut app_id = None;
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("--command=") {
+            command = Some(value.to_string());
+        } else if !arg.starts_with('-') {
+            app_id = Some(*arg);
+            break;
+        }
+    }
+    let app_id = app_id?;
+
+    let installations = data_home()
+        .map(|home| home.join("flatpak"))
+        .into_iter()
+        .chain([PathBuf::from("/var/lib/flatpak")]);
+
+    for installation in installations {
+        let deploy = installation.join("app").join(app_id).join("current/active");
+        let command = match &command {
+            Some(command) => command.clone(),
+            None => {
+                // [Application] command=... in the deploy's metadata
+                let metadata = fs::read_to_string(deploy.join("metadata")).unwrap_or_default();
+                match metadata.lines().find_map(|line| line.trim().strip_prefix("command=")) {
+                    Some(command) => command.trim().to_string(),
+                    None => continue,
+                }
+            }
+        };
+
+        let binary = if command.starts_with('/') {
+            // Absolute paths are relative to the sandbox, where files/ is /app
+            deploy
+                .join("files")
+                .join(command.trim_start_matches("/app/").trim_start_matches('/'))
+        } else {
+            deploy.join("files/bin").join(&command)
+        };
+        if binary.exists() {
+            return Some(binary);
+        }
+    }
+
+    None
+}
+
+// `/snap/bin/<snap>[.<app>]` is a symlink to the snap launcher; the real
+// command is declared in the snap's meta/snap.yaml
+fn resolve_snap(launcher: &str) -> Option<PathBuf> {
+    let (snap, app) = launcher.split_once('.').unwrap_or((launcher, launcher));
+    let root = Path::new("/snap").join(snap).join("current");
+    let manifest = fs::read_to_string(root.join("meta/snap.yaml")).ok()?;
+
+    // Minimal walk of `apps: <app>: command: ...` without a YAML parser
+    let mut in_apps = false;
+    let mut in_app = false;
+    for line in manifest.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if indent == 0 {
+            in_apps = trimmed == "apps:";
+            in_app = false;
+        } else if in_apps && trimmed.ends_with(':') && !trimmed.contains(' ') {
+            in_app = trimmed.trim_end_matches(':') == app;
+        } else if in_app {
+            if let Some(command) = trimmed.strip_prefix("command:") {
+                // The command may run through wrapper scripts; take the
+                // first argument that is an executable inside the snap
+                return command
+                    .split_whitespace()
+                    .map(|part| root.join(part.trim_matches(['"', '\''])))
+                    .find(|path| path.is_file())
+                    .or_else(|| Some(PathBuf::from("/snap/bin").join(launcher)));
+            }
+        }
+    }
+
+    None
+}
+
+// Icon as a data URL so the frontend can show it without filesystem access.
+// `Icon=` is either an absolute path or a name looked up in the icon theme.
+fn icon_data_url(icon: &str) -> Option<String> {
+    let path = if icon.starts_with('/') {
+        PathBuf::from(icon)
+    } else {
+        find_theme_icon(icon)?
+    };
+
+    let mime = match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+    if fs::metadata(&path).ok()?.len() > MAX_ICON_BYTES {
+        return None;
+    }
+    let bytes = fs::read(&path).ok()?;
+
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+fn find_theme_icon(name: &str) -> Option<PathBuf> {
+    let mut bases: Vec<PathBuf> = home_dir().map(|home| home.join(".icons")).into_iter().collect();
+    bases.extend(data_dirs().into_iter().map(|dir| dir.join("icons")));
+
+    for size in ICON_SIZES {
+        for base in &bases {
+            for extension in ["png", "svg"] {
+                let path = base
+                    .join("hicolor")
+                    .join(size)
+                    .join("apps")
+                    .join(format!("{}.{}", name, extension));
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+        }
+    }
+
+    ["png", "svg"]
+        .into_iter()
+        .map(|extension| Path::new("/usr/share/pixmaps").join(format!("{}.{}", name, extension)))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exec(value: &str) -> Vec<String> {
+        split_exec(&unescape_value(value))
+    }
+
+    #[test]
+    fn field_codes_are_dropped() {
+        assert_eq!(exec("firefox %u"), ["firefox"]);
+        assert_eq!(exec("code --new-window %F"), ["code", "--new-window"]);
+        assert_eq!(exec("app %i %c %k -- %U"), ["app", "--"]);
+    }
+
+    #[test]
+    fn percent_escape_is_kept_as_a_literal() {
+        assert_eq!(exec("printf %% 100%%"), ["printf", "%", "100%"]);
+    }
+
+    #[test]
+    fn quoted_arguments_keep_spaces_and_escapes() {
+        // The file escapes backslashes once more than the Exec quoting needs
+        assert_eq!(
+            exec(r#""/opt/My App/app" --title "say \\"hi\\"" %f"#),
+            ["/opt/My App/app", "--title", r#"say "hi""#]
+        );
+        assert_eq!(exec(r#"sh -c "echo \\$HOME""#), ["sh", "-c", "echo $HOME"]);
+        assert_eq!(exec(r#"app "C:\\\\dir""#), ["app", r"C:\dir"]);
+        assert_eq!(exec(r#"app """#), ["app", ""]);
+        // Files that skip the extra escaping are read the same way
+        assert_eq!(exec(r#"app "say \"hi\"""#), ["app", r#"say "hi""#]);
+    }
+
+    #[test]
+    fn value_escapes_are_unescaped() {
+        assert_eq!(unescape_value(r"a\sb\tc\\d"), "a b\tc\\d");
+        // `\s` is a plain space by the time the Exec line is split
+        assert_eq!(exec(r"/opt/my\sapp %U"), ["/opt/my", "app"]);
+        assert_eq!(exec(r#""/opt/my\sapp" %U"#), ["/opt/my app"]);
+    }
+}
@@ -0,0 +1,681 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+use crate::doh;
+use crate::domain;
+use crate::loopback_service::{self, LoopbackServer, Refresh};
+use crate::rules::{self, AllowlistRule, WebsiteBlockRule};
+use crate::store::{Store, StoreData};
+
+// How often rules and settings are re-read. Timers and schedules start and
+// end on their own, so this also picks those up.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+// How long to wait for the upstream resolver
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(3);
+
+// TTL of sinkholed answers; short so unblocked domains resolve again quickly
+const SINKHOLE_TTL: u32 = 60;
+
+// Addresses of sinkholed answers. Windows doesn't connect 0.0.0.0 and :: to
+// loopback as Linux and macOS do, so there they are loopback itself, where
+// the block page listens.
+#[cfg(not(windows))]
+const SINKHOLE_V4: [u8; 4] = [0; 4];
+#[cfg(not(windows))]
+const SINKHOLE_V6: [u8; 16] = [0; 16];
+#[cfg(windows)]
+const SINKHOLE_V4: [u8; 4] = [127, 0, 0, 1];
+#[cfg(windows)]
+const SINKHOLE_V6: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+
+// Large enough for EDNS responses
+const MAX_UDP_MESSAGE: usize = 4096;
+
+const HEADER_LEN: usize = 12;
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const RCODE_SERVFAIL: u16 = 2;
+
+// Names allowlist-only mode never answers for: the machine itself, mDNS and
+// reverse lookups
+const ALWAYS_ALLOWED_SUFFIXES: &[&str] = &["localhost", "local", "arpa"];
+
+// Which names the sinkhole answers itself. Wildcard domains cover every
+// subdomain; exact ones only the domain and its `www.` variant, like the
+// hosts file. In allowlist-only mode every name the allowlist doesn't cover
+// is answered as well.
+#[derive(Debug, Clone, Default)]
+pub struct SinkholePolicy {
+    wildcard: HashMap<String, String>,
+    exact: HashMap<String, String>,
+    allowlist: Option<Allowlist>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Allowlist {
+    // Allowed together with their subdomains
+    domains: HashSet<String>,
+    // Blocked names are attributed to the first active allowlist
+    rule_id: String,
+}
+
+impl Allowlist {
+    fn allows(&self, name: &str) -> bool {
+        // Single-label names are local hosts or search-domain lookups
+        if !name.contains('.') {
+            return true;
+        }
+        let mut suffix = name;
+        loop {
+            if self.domains.contains(suffix) || ALWAYS_ALLOWED_SUFFIXES.contains(&suffix) {
+                return true;
+            }
+            match suffix.split_once('.') {
+                Some((_, parent)) => suffix = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+impl SinkholePolicy {
+    // Everything the sinkhole answers for at `now`: the active website rules,
+    // the DoH providers and, while an allowlist is active, every name outside
+    // it
+    pub fn at(data: &StoreData, now: &chrono::DateTime<chrono::Local>) -> Self {
+        if !data.settings.website_blocking_enabled {
+            return Self::default();
+        }
+        let rules = rules::active_at(&data.website_rules, now);
+        let allowlists = rules::active_at(&data.allowlist_rules, now);
+
+        let mut policy = Self::from_rules(&rules);
+        if !rules.is_empty() && doh::providers_blocked(&data.settings) {
+            policy.add_exact(doh::DOH_PROVIDER_HOSTS, doh::PROVIDER_RULE_ID);
+        }
+        // Accountability messages must still get out
+        let webhook_host = data
+            .settings
+            .webhook_url
+            .as_deref()
+            .and_then(|url| domain::normalize(url).ok());
+        policy.set_allowlist(&allowlists, webhook_host);
+        policy
+    }
+
+    pub fn from_rules(rules: &[WebsiteBlockRule]) -> Self {
+        let mut policy = Self::default();
+        for rule in rules {
+            let target = if rule.include_subdomains {
+                &mut policy.wildcard
+            } else {
+                &mut policy.exact
+            };
+            for domain in rule.blocked_domains() {
+                let domain = normalize_name(domain);
+                if !domain.is_empty() {
+                    target.insert(domain, rule.id.clone());
+                }
+            }
+        }
+        policy
+    }
+
+    // Also answer for `names` (and their `www.` variants) on behalf of `id`
+    pub fn add_exact(&mut self, names: &[&str], id: &str) {
+        for name in names {
+            self.exact.insert(name.to_string(), id.to_string());
+        }
+    }
+
+    // Switch allowlist-only mode on for the union of `rules` (and `extra`), or
+    // off if there are none
+    pub fn set_allowlist(&mut self, rules: &[AllowlistRule], extra: impl IntoIterator<Item = String>) {
+        let Some(first) = rules.first() else {
+            self.allowlist = None;
+            return;
+        };
+        let domains = rules
+            .iter()
+            .flat_map(|rule| &rule.domains)
+            .filter_map(|domain| domain::normalize(domain).ok())
+            .chain(extra)
+            .collect();
+        self.allowlist = Some(Allowlist {
+            domains,
+            rule_id: first.id.clone(),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.wildcard.len() + self.exact.len()
+    }
+
+    pub fn allowlist_active(&self) -> bool {
+        self.allowlist.is_some()
+    }
+
+    pub fn allowed_domain_count(&self) -> usize {
+        self.allowlist.as_ref().map_or(0, |allowlist| allowlist.domains.len())
+    }
+
+    // Id of the rule blocking `name`, if any
+    pub fn blocking_rule(&self, name: &str) -> Option<&str> {
+        let name = normalize_name(name);
+
+        let exact = self
+            .exact
+            .get(&name)
+            .or_else(|| name.strip_prefix("www.").and_then(|bare| self.exact.get(bare)));
+        if let Some(rule_id) = exact {
+            return Some(rule_id);
+        }
+
+        // youtube.com blocks m.youtube.com, a.b.youtube.com, ...
+        let mut suffix = name.as_str();
+        loop {
+            if let Some(rule_id) = self.wildcard.get(suffix) {
+                return Some(rule_id);
+            }
+            match suffix.split_once('.') {
+                Some((_, parent)) => suffix = parent,
+                None => break,
+            }
+        }
+
+        // Block rules win over the allowlist
+        let allowlist = self.allowlist.as_ref()?;
+        if name.is_empty() || allowlist.allows(&name) {
+            return None;
+        }
+        Some(&allowlist.rule_id)
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+// The question of a DNS query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Question {
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+    // Offset just past the question in the query
+    end: usize,
+}
+
+fn read_u16(msg: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*msg.get(pos)?, *msg.get(pos + 1)?]))
+}
+
+// Standard queries with at least one question; anything else is forwarded untouched
+pub fn parse_query(msg: &[u8]) -> Option<Question> {
+    let flags = read_u16(msg, 2)?;
+    let is_response = flags & 0x8000 != 0;
+    let opcode = (flags >> 11) & 0xF;
+    if is_response || opcode != 0 || read_u16(msg, 4)? == 0 {
+        return None;
+    }
+
+    let (name, pos) = read_name(msg, HEADER_LEN)?;
+    Some(Question {
+        name,
+        qtype: read_u16(msg, pos)?,
+        qclass: read_u16(msg, pos + 2)?,
+        end: pos + 4,
+    })
+}
+
+// Read a possibly compressed name; returns it and the offset after it
+fn read_name(msg: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // Bounds the number of compression pointers followed
+    let mut jumps = 0;
+
+    loop {
+        let len = *msg.get(pos)? as usize;
+        match len {
+            0 => {
+                let end = end.unwrap_or(pos + 1);
+                return Some((labels.join("."), end));
+            }
+            l if l & 0xC0 == 0xC0 => {
+                jumps += 1;
+                if jumps > 16 {
+                    return None;
+                }
+                let pointer = (read_u16(msg, pos)? & 0x3FFF) as usize;
+                end.get_or_insert(pos + 2);
+                pos = pointer;
+            }
+            l if l <= 63 => {
+                let label = msg.get(pos + 1..pos + 1 + l)?;
+                labels.push(String::from_utf8_lossy(label).to_string());
+                pos += 1 + l;
+            }
+            _ => return None,
+        }
+    }
+}
+
+// Header for a response to `query` with the given rcode and answer count
+fn response_header(query: &[u8], rcode: u16, answers: u16) -> Vec<u8> {
+    let query_flags = read_u16(query, 2).unwrap_or(0);
+    // QR, keep opcode and RD, set RA
+    let flags = 0x8000 | (query_flags & 0x7900) | 0x0080 | rcode;
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(&query[..2]);
+    header.extend_from_slice(&flags.to_be_bytes());
+    header.extend_from_slice(&1u16.to_be_bytes());
+    header.extend_from_slice(&answers.to_be_bytes());
+    header.extend_from_slice(&0u16.to_be_bytes());
+    header.extend_from_slice(&0u16.to_be_bytes());
+    header
+}
+
+// Answer A and AAAA queries with the sinkhole addresses. Other types (HTTPS, MX,
+// ...) get an empty NOERROR answer so clients don't go looking elsewhere.
+pub fn sinkhole_response(query: &[u8], question: &Question) -> Vec<u8> {
+    let rdata: &[u8] = match (question.qtype, question.qclass) {
+        (TYPE_A, CLASS_IN) => &SINKHOLE_V4,
+        (TYPE_AAAA, CLASS_IN) => &SINKHOLE_V6,
+        _ => &[],
+    };
+    let answers = if rdata.is_empty() { 0 } else { 1 };
+
+    let mut response = response_header(query, 0, answers);
+    response.extend_from_slice(&query[HEADER_LEN..question.end]);
+    if answers == 1 {
+        response.extend_from_slice(&0xC00Cu16.to_be_bytes()); // Pointer to the question name
+        response.extend_from_slice(&question.qtype.to_be_bytes());
+        response.extend_from_slice(&CLASS_IN.to_be_bytes());
+        response.extend_from_slice(&SINKHOLE_TTL.to_be_bytes());
+        response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        response.extend_from_slice(rdata);
+    }
+    response
+}
+
+fn servfail(query: &[u8], question: Option<&Question>) -> Vec<u8> {
+    let mut response = response_header(query, RCODE_SERVFAIL, 0);
+    match question {
+        Some(question) => response.extend_from_slice(&query[HEADER_LEN..question.end]),
+        // No parsable question to echo back
+        None => response[4..6].copy_from_slice(&0u16.to_be_bytes()),
+    }
+    response
+}
+
+// Answer one query: sinkhole it if a rule blocks the name, otherwise relay
+// it to `upstream` over the same transport it arrived on
+pub async fn resolve(
+    query: &[u8],
+    upstream: SocketAddr,
+    policy: &RwLock<SinkholePolicy>,
+    over_tcp: bool,
+) -> Option<Vec<u8>> {
+    if query.len() < HEADER_LEN {
+        return None;
+    }
+
+    let question = parse_query(query);
+    if let Some(question) = &question {
+        if policy.read().unwrap().blocking_rule(&question.name).is_some() {
+            return Some(sinkhole_response(query, question));
+        }
+    }
+
+    let forwarded = if over_tcp {
+        forward_tcp(query, upstream).await
+    } else {
+        forward_udp(query, upstream).await
+    };
+    Some(forwarded.unwrap_or_else(|_| servfail(query, question.as_ref())))
+}
+
+async fn forward_udp(query: &[u8], upstream: SocketAddr) -> io::Result<Vec<u8>> {
+    let local: SocketAddr = if upstream.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(upstream).await?;
+    socket.send(query).await?;
+
+    let mut buffer = vec![0u8; MAX_UDP_MESSAGE];
+    tokio::time::timeout(UPSTREAM_TIMEOUT, async {
+        loop {
+            let len = socket.recv(&mut buffer).await?;
+            // Ignore stray datagrams that don't answer this query
+            if len >= 2 && buffer[..2] == query[..2] {
+                return Ok(buffer[..len].to_vec());
+            }
+        }
+    })
+    .await
+    .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "upstream timed out"))?
+}
+
+async fn forward_tcp(query: &[u8], upstream: SocketAddr) -> io::Result<Vec<u8>> {
+    tokio::time::timeout(UPSTREAM_TIMEOUT, async {
+        let mut stream = TcpStream::connect(upstream).await?;
+        write_tcp_message(&mut stream, query).await?;
+        read_tcp_message(&mut stream).await
+    })
+    .await
+    .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "upstream timed out"))?
+}
+
+// DNS over TCP prefixes every message with its length
+async fn read_tcp_message(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let len = stream.read_u16().await? as usize;
+    let mut message = vec![0u8; len];
+    stream.read_exact(&mut message).await?;
+    Ok(message)
+}
+
+async fn write_tcp_message(stream: &mut TcpStream, message: &[u8]) -> io::Result<()> {
+    let len = u16::try_from(message.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "message too long"))?;
+    stream.write_u16(len).await?;
+    stream.write_all(message).await
+}
+
+// Serve DNS on an already bound UDP socket and TCP listener until the task is
+// dropped. Split from `DnsSinkhole` so it can run against any upstream.
+pub async fn serve(
+    udp: UdpSocket,
+    tcp: TcpListener,
+    upstream: SocketAddr,
+    policy: Arc<RwLock<SinkholePolicy>>,
+) {
+    let udp = Arc::new(udp);
+    let mut buffer = vec![0u8; MAX_UDP_MESSAGE];
+
+    loop {
+        tokio::select! {
+            received = udp.recv_from(&mut buffer) => {
+                let Ok((len, client)) = received else {
+                    continue;
+                };
+                let query = buffer[..len].to_vec();
+                let (udp, policy) = (udp.clone(), policy.clone());
+                tokio::spawn(async move {
+                    if let Some(response) = resolve(&query, upstream, &policy, false).await {
+                        let _ = udp.send_to(&response, client).await;
+                    }
+                });
+            }
+            accepted = tcp.accept() => {
+                let Ok((stream, _)) = accepted else {
+                    continue;
+                };
+                tokio::spawn(serve_tcp_client(stream, upstream, policy.clone()));
+            }
+        }
+    }
+}
+
+async fn serve_tcp_client(mut stream: TcpStream, upstream: SocketAddr, policy: Arc<RwLock<SinkholePolicy>>) {
+    // Clients may send several queries over one connection
+    while let Ok(query) = read_tcp_message(&mut stream).await {
+        let Some(response) = resolve(&query, upstream, &policy, true).await else {
+            return;
+        };
+        if write_tcp_message(&mut stream, &response).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DnsSinkholeStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub listen: String,
+    pub upstream: String,
+    pub blocked_domain_count: usize,
+    // An allowlist rule is active: every name outside it is sinkholed. This
+    // only works if the system sends all lookups here.
+    pub allowlist_active: bool,
+    pub allowed_domain_count: usize,
+    // Why the resolver isn't running (bad address, port 53 needs root, ...)
+    pub error: Option<String>,
+}
+
+// Optional built-in resolver. Started and stopped from the settings, and fed
+// the active website rules on every refresh.
+#[derive(Clone)]
+pub struct DnsSinkhole {
+    policy: Arc<RwLock<SinkholePolicy>>,
+    // Restarted when the upstream resolver changes
+    server: LoopbackServer<SocketAddr>,
+}
+
+impl DnsSinkhole {
+    pub fn new() -> Self {
+        Self {
+            policy: Arc::new(RwLock::new(SinkholePolicy::default())),
+            server: LoopbackServer::new("DNS sinkhole"),
+        }
+    }
+
+    // Spawn the refresh loop. Called once from the `setup` hook.
+    pub fn start(&self, app: AppHandle) {
+        loopback_service::start(self, app, REFRESH_INTERVAL);
+    }
+}
+
+impl Refresh for DnsSinkhole {
+    // Reload the block list and start, stop or restart the server to match
+    // the settings
+    async fn refresh(&self, app: &AppHandle) {
+        let now = chrono::Local::now();
+        let (settings, policy) = app
+            .state::<Store>()
+            .read(|data| (data.settings.clone(), SinkholePolicy::at(data, &now)));
+        *self.policy.write().unwrap() = policy;
+
+        let desired = if settings.dns_sinkhole_enabled {
+            parse_addresses(&settings.dns_sinkhole_listen, &settings.dns_upstream).map(Some)
+        } else {
+            Ok(None)
+        };
+        self.server
+            .reconcile(desired, |listen, upstream| async move {
+                let (udp, tcp) = bind(listen).await?;
+                Ok(tauri::async_runtime::spawn(serve(udp, tcp, upstream, self.policy.clone())))
+            })
+            .await;
+    }
+}
+
+impl Default for DnsSinkhole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_addresses(listen: &str, upstream: &str) -> Result<(SocketAddr, SocketAddr), String> {
+    let listen = loopback_service::parse_listen(listen)?;
+    let upstream: SocketAddr = upstream
+        .parse()
+        .map_err(|_| format!("Invalid upstream address '{}'", upstream))?;
+    if listen == upstream {
+        return Err("The upstream resolver can't be the sinkhole itself".to_string());
+    }
+    Ok((listen, upstream))
+}
+
+async fn bind(listen: SocketAddr) -> io::Result<(UdpSocket, TcpListener)> {
+    let udp = loopback_service::bind_udp(listen).await?;
+    let tcp = loopback_service::bind_tcp(listen).await?;
+    Ok((udp, tcp))
+}
+
+#[tauri::command]
+pub async fn get_dns_sinkhole_status(
+    app: AppHandle,
+    sinkhole: State<'_, DnsSinkhole>,
+    store: State<'_, Store>,
+) -> Result<DnsSinkholeStatus, String> {
+    // Apply settings saved since the last refresh before reporting
+    sinkhole.refresh(&app).await;
+
+    let settings = store.read(|data| data.settings.clone());
+    let running = sinkhole.server.is_running().await;
+    let policy = sinkhole.policy.read().unwrap();
+    Ok(DnsSinkholeStatus {
+        enabled: settings.dns_sinkhole_enabled,
+        running,
+        listen: settings.dns_sinkhole_listen,
+        upstream: settings.dns_upstream,
+        blocked_domain_count: policy.len(),
+        allowlist_active: policy.allowlist_active(),
+        allowed_domain_count: policy.allowed_domain_count(),
+        error: sinkhole.server.error(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleTiming;
+
+    fn policy(domain: &str, include_subdomains: bool) -> RwLock<SinkholePolicy> {
+        let rule = WebsiteBlockRule {
+            id: "rule-1".to_string(),
+            domain: domain.to_string(),
+            is_active: true,
+            created_at: 0,
+            timing: RuleTiming::permanent(),
+            include_subdomains,
+            domains: Vec::new(),
+        };
+        RwLock::new(SinkholePolicy::from_rules(&[rule]))
+    }
+
+    fn query(id: u16, name: &str, qtype: u16) -> Vec<u8> {
+        let mut query = Vec::new();
+        query.extend_from_slice(&id.to_be_bytes());
+        query.extend_from_slice(&0x0100u16.to_be_bytes()); // RD
+        query.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.split('.') {
+            query.push(label.len() as u8);
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.push(0);
+        query.extend_from_slice(&qtype.to_be_bytes());
+        query.extend_from_slice(&CLASS_IN.to_be_bytes());
+        query
+    }
+
+    fn rcode(response: &[u8]) -> u16 {
+        read_u16(response, 2).unwrap() & 0x000F
+    }
+
+    fn answer_count(response: &[u8]) -> u16 {
+        read_u16(response, 6).unwrap()
+    }
+
+    // An upstream that hands every query it receives to the test and, if
+    // `reply` is set, answers with it
+    async fn stub_upstream(reply: Option<Vec<u8>>) -> (SocketAddr, tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; MAX_UDP_MESSAGE];
+            while let Ok((len, client)) = socket.recv_from(&mut buffer).await {
+                let _ = sender.send(buffer[..len].to_vec());
+                if let Some(reply) = &reply {
+                    let _ = socket.send_to(reply, client).await;
+                }
+            }
+        });
+        (addr, receiver)
+    }
+
+    #[tokio::test]
+    async fn subdomains_of_a_wildcard_rule_are_sinkholed() {
+        let (upstream, mut received) = stub_upstream(None).await;
+        let policy = policy("example.com", true);
+
+        let a = query(0x1234, "ads.example.com", TYPE_A);
+        let response = resolve(&a, upstream, &policy, false).await.unwrap();
+        assert_eq!(&response[..2], &[0x12, 0x34]);
+        assert_eq!((rcode(&response), answer_count(&response)), (0, 1));
+        assert_eq!(&response[response.len() - 6..], &[0, 4, 0, 0, 0, 0]);
+
+        let aaaa = query(0x1235, "deep.ads.example.com", TYPE_AAAA);
+        let response = resolve(&aaaa, upstream, &policy, false).await.unwrap();
+        assert_eq!((rcode(&response), answer_count(&response)), (0, 1));
+        let mut rdata = vec![0, 16];
+        rdata.extend_from_slice(&SINKHOLE_V6);
+        assert_eq!(&response[response.len() - 18..], &rdata[..]);
+
+        // Nothing was asked upstream
+        assert!(received.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn subdomains_of_an_exact_rule_are_forwarded() {
+        let (upstream, mut received) = stub_upstream(None).await;
+        let policy = policy("example.com", false);
+
+        let response = resolve(&query(1, "example.com", TYPE_A), upstream, &policy, false).await.unwrap();
+        assert_eq!(answer_count(&response), 1);
+        let response = resolve(&query(2, "www.example.com", TYPE_A), upstream, &policy, false).await.unwrap();
+        assert_eq!(answer_count(&response), 1);
+        assert!(received.try_recv().is_err());
+
+        // No reply from the stub, so this comes back as SERVFAIL; what matters
+        // is that it went upstream
+        let _ = resolve(&query(3, "ads.example.com", TYPE_A), upstream, &policy, false).await;
+        assert!(received.recv().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn unrelated_names_are_forwarded_verbatim() {
+        let q = query(0x4242, "unrelated.org", TYPE_A);
+        // A reply the sinkhole couldn't have made up
+        let mut reply = q.clone();
+        reply[2] |= 0x80;
+        reply.extend_from_slice(&[0xC0, 0x0C, 0, 1, 0, 1, 0, 0, 0, 30, 0, 4, 93, 184, 216, 34]);
+        let (upstream, mut received) = stub_upstream(Some(reply.clone())).await;
+        let policy = policy("example.com", true);
+
+        let response = resolve(&q, upstream, &policy, false).await.unwrap();
+        assert_eq!(received.recv().await.unwrap(), q);
+        assert_eq!(response, reply);
+    }
+
+    #[tokio::test]
+    async fn upstream_timeout_is_servfail() {
+        let (upstream, mut received) = stub_upstream(None).await;
+        let policy = policy("example.com", true);
+
+        let q = query(0x0707, "unrelated.org", TYPE_A);
+        let response = resolve(&q, upstream, &policy, false).await.unwrap();
+        assert_eq!(received.recv().await.unwrap(), q);
+        assert_eq!(&response[..2], &[0x07, 0x07]);
+        assert_eq!((rcode(&response), answer_count(&response)), (RCODE_SERVFAIL, 0));
+        // The question is echoed back
+        assert_eq!(&response[HEADER_LEN..], &q[HEADER_LEN..]);
+    }
+}
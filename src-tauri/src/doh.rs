@@ -0,0 +1,334 @@
+// DNS-over-HTTPS bypass mitigation. Browsers with DoH on send their lookups
+// to a provider over HTTPS and never see the hosts file or local resolver, so
+// website blocks silently stop working. Two counters:
+//
+//   - the providers' hostnames join the block set, so browsers in automatic
+//     mode fail to reach them and fall back to the system resolver
+//   - enterprise policies turn DoH off (and lock it) in the browsers
+//
+// A browser set to a provider missing from the list, without a policy, still
+// bypasses the blocks; the status says so.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::browser_policy::{
+    is_installed, read_json, reg, registry_value, remove_file, write_json, Browser, PolicyTarget, BROWSERS,
+    FIREFOX_POLICIES_LOCK,
+};
+use crate::helper::HelperClient;
+use crate::store::AppSettings;
+use crate::website_backend::BackendKind;
+
+// DoH endpoints of the public resolvers browsers offer or users commonly pick
+pub const DOH_PROVIDER_HOSTS: &[&str] = &[
+    "dns.google",
+    "dns64.dns.google",
+    "cloudflare-dns.com",
+    "mozilla.cloudflare-dns.com",
+    "chrome.cloudflare-dns.com",
+    "1dot1dot1dot1.cloudflare-dns.com",
+    "security.cloudflare-dns.com",
+    "family.cloudflare-dns.com",
+    "dns.quad9.net",
+    "dns9.quad9.net",
+    "dns10.quad9.net",
+    "dns11.quad9.net",
+    "doh.opendns.com",
+    "doh.familyshield.opendns.com",
+    "dns.nextdns.io",
+    "firefox.dns.nextdns.io",
+    "chromium.dns.nextdns.io",
+    "doh.cleanbrowsing.org",
+    "dns.adguard.com",
+    "dns.adguard-dns.com",
+    "family.adguard-dns.com",
+    "unfiltered.adguard-dns.com",
+    "doh.dns.sb",
+    "doh.mullvad.net",
+    "dns.mullvad.net",
+    "freedns.controld.com",
+    "dns.controld.com",
+    "doh.xfinity.com",
+    "dns.alidns.com",
+    "doh.pub",
+    "doh.360.cn",
+    "dns.switch.ch",
+    "doh.libredns.gr",
+    "doh.applied-privacy.net",
+    "odvr.nic.cz",
+];
+
+// Stands in for a rule id where the sinkhole answers for a provider
+pub const PROVIDER_RULE_ID: &str = "doh-provider";
+
+// Chromium managed-policy file we own inside a policy directory
+const CHROMIUM_POLICY_FILE: &str = "neu-doh.json";
+
+// Registry key listing, by name, the Chromium policy keys we set
+// `DnsOverHttpsMode` in
+const REGISTRY_OWNED_KEY: &str = "HKLM\\SOFTWARE\\NotEnoughAccountability\\DohPolicies";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowserDohPolicy {
+    pub browser: String,
+    pub installed: bool,
+    // A policy turning DoH off can be written on this platform
+    pub supported: bool,
+    // DoH is off and locked by policy, ours or an administrator's
+    pub doh_disabled: bool,
+    // Policy file, directory or registry key
+    pub location: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DohStatus {
+    pub providers_blocked: bool,
+    pub browsers: Vec<BrowserDohPolicy>,
+    // Some installed browser can still resolve over HTTPS past the blocks
+    pub bypass_possible: bool,
+}
+
+// The providers are blocked by name. nftables would block their addresses
+// instead, and dns.google or dns.quad9.net share theirs with the plain DNS
+// resolvers (8.8.8.8, 9.9.9.9) many systems use.
+pub fn providers_blocked(settings: &AppSettings) -> bool {
+    settings.block_doh_providers && settings.website_block_backend != BackendKind::Nftables
+}
+
+// `domains` plus the DoH providers when anything is blocked at all
+pub fn with_providers(settings: &AppSettings, mut domains: Vec<String>) -> Vec<String> {
+    if providers_blocked(settings) && !domains.is_empty() {
+        domains.extend(DOH_PROVIDER_HOSTS.iter().map(|host| host.to_string()));
+    }
+    domains
+}
+
+pub fn status(settings: &AppSettings) -> DohStatus {
+    let browsers = policies();
+    let bypass_possible = browsers
+        .iter()
+        .any(|browser| browser.installed && !browser.doh_disabled);
+    DohStatus {
+        providers_blocked: providers_blocked(settings),
+        browsers,
+        bypass_possible,
+    }
+}
+
+// Policy state of every browser this platform knows about
+pub fn policies() -> Vec<BrowserDohPolicy> {
+    BROWSERS.iter().map(|browser| inspect(browser, None)).collect()
+}
+
+// Write (`disable`) or remove our DoH policies for every installed browser.
+// Needs root or administrator rights.
+pub fn set_policies(disable: bool) -> Vec<BrowserDohPolicy> {
+    BROWSERS
+        .iter()
+        .map(|browser| {
+            let written = if is_installed(browser) || !disable {
+                match browser.target {
+                    PolicyTarget::FirefoxJson(path) => write_firefox_policy(Path::new(path), disable),
+                    PolicyTarget::ChromiumDir(dir) => write_chromium_policy(Path::new(dir), disable),
+                    PolicyTarget::ChromiumRegistry(key) => write_chromium_registry(key, disable),
+                    PolicyTarget::Unsupported => Ok(()),
+                }
+            } else {
+                Ok(())
+            };
+            inspect(browser, written.err())
+        })
+        .collect()
+}
+
+fn inspect(browser: &Browser, error: Option<String>) -> BrowserDohPolicy {
+    let (location, doh_disabled) = match browser.target {
+        PolicyTarget::FirefoxJson(path) => (Some(path.to_string()), firefox_doh_disabled(Path::new(path))),
+        PolicyTarget::ChromiumDir(dir) => (Some(dir.to_string()), chromium_dir_doh_disabled(Path::new(dir))),
+        PolicyTarget::ChromiumRegistry(key) => (Some(key.to_string()), chromium_registry_doh_disabled(key)),
+        PolicyTarget::Unsupported => (None, false),
+    };
+    BrowserDohPolicy {
+        browser: browser.name.to_string(),
+        installed: is_installed(browser),
+        supported: !matches!(browser.target, PolicyTarget::Unsupported),
+        doh_disabled,
+        location,
+        error,
+    }
+}
+
+// `{"policies": {"DNSOverHTTPS": {"Enabled": false, "Locked": true}}}`
+fn firefox_doh_disabled(path: &Path) -> bool {
+    let Some(policies) = read_json(path) else {
+        return false;
+    };
+    let doh = &policies["policies"]["DNSOverHTTPS"];
+    doh["Enabled"] == false && doh["Locked"] == true
+}
+
+fn write_firefox_policy(path: &Path, disable: bool) -> Result<(), String> {
+    let _guard = FIREFOX_POLICIES_LOCK.lock().unwrap();
+    let existing = read_json(path);
+    if existing.is_none() && path.exists() {
+        return Err(format!("{} is not valid JSON; leaving it alone", path.display()));
+    }
+    if existing.is_none() && !disable {
+        return Ok(());
+    }
+    let mut policies = existing.unwrap_or_else(|| serde_json::json!({ "policies": {} }));
+    if !policies["policies"].is_object() {
+        policies["policies"] = serde_json::json!({});
+    }
+    let ours = serde_json::json!({ "Enabled": false, "Locked": true });
+
+    if disable {
+        policies["policies"]["DNSOverHTTPS"] = ours;
+    } else {
+        // Someone else's DoH policy stays
+        if policies["policies"]["DNSOverHTTPS"] != ours {
+            return Ok(());
+        }
+        if let Some(map) = policies["policies"].as_object_mut() {
+            map.remove("DNSOverHTTPS");
+        }
+        if policies == serde_json::json!({ "policies": {} }) {
+            return remove_file(path);
+        }
+    }
+    write_json(path, &policies)
+}
+
+// Any policy file in the directory setting `DnsOverHttpsMode` to `off`
+fn chromium_dir_doh_disabled(dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| read_json(&path))
+        .any(|policies| policies["DnsOverHttpsMode"] == "off")
+}
+
+fn write_chromium_policy(dir: &Path, disable: bool) -> Result<(), String> {
+    let path: PathBuf = dir.join(CHROMIUM_POLICY_FILE);
+    if !disable {
+        return remove_file(&path);
+    }
+    write_json(&path, &serde_json::json!({ "DnsOverHttpsMode": "off" }))
+}
+
+fn chromium_registry_doh_disabled(key: &str) -> bool {
+    registry_value(key, "DnsOverHttpsMode").is_some_and(|value| value.eq_ignore_ascii_case("off"))
+}
+
+// A `DnsOverHttpsMode` set by someone else is left alone, both ways; the
+// keys we set it in are recorded under REGISTRY_OWNED_KEY
+fn write_chromium_registry(key: &str, disable: bool) -> Result<(), String> {
+    let owned = registry_value(REGISTRY_OWNED_KEY, key).is_some();
+    if disable {
+        if chromium_registry_doh_disabled(key) && !owned {
+            return Ok(());
+        }
+        reg(key, &["add", key, "/v", "DnsOverHttpsMode", "/t", "REG_SZ", "/d", "off", "/f"])?;
+        reg(
+            REGISTRY_OWNED_KEY,
+            &["add", REGISTRY_OWNED_KEY, "/v", key, "/t", "REG_SZ", "/d", "DnsOverHttpsMode", "/f"],
+        )
+    } else if owned {
+        if chromium_registry_doh_disabled(key) {
+            reg(key, &["delete", key, "/v", "DnsOverHttpsMode", "/f"])?;
+        }
+        reg(REGISTRY_OWNED_KEY, &["delete", REGISTRY_OWNED_KEY, "/v", key, "/f"])
+    } else {
+        Ok(())
+    }
+}
+
+// Turn DoH off in every installed browser (or take our policies back out),
+// through the privileged helper when one is running
+#[tauri::command]
+pub async fn set_doh_policies(disable: bool) -> Result<Vec<BrowserDohPolicy>, String> {
+    tauri::async_runtime::spawn_blocking(move || match HelperClient::connect() {
+        Some(helper) => helper.set_doh_policies(disable),
+        None => Ok(set_policies(disable)),
+    })
+    .await
+    .map_err(|e| format!("Browser policy task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neu-doh-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn only_our_firefox_policy_is_taken_back_out() {
+        let dir = temp_dir("firefox");
+        let path = dir.join("policies.json");
+
+        // Nothing to take out, and no file is created for it
+        write_firefox_policy(&path, false).unwrap();
+        assert!(!path.exists());
+        write_firefox_policy(&path, true).unwrap();
+        assert!(firefox_doh_disabled(&path));
+        write_firefox_policy(&path, false).unwrap();
+        assert!(!path.exists());
+
+        // The administrator's other policies stay through both
+        let admin = json!({ "policies": { "DisableTelemetry": true } });
+        write_json(&path, &admin).unwrap();
+        write_firefox_policy(&path, true).unwrap();
+        assert_eq!(read_json(&path).unwrap()["policies"]["DisableTelemetry"], true);
+        write_firefox_policy(&path, false).unwrap();
+        assert_eq!(read_json(&path).unwrap(), admin);
+
+        // So does a DoH policy of their own
+        let admin = json!({
+            "policies": { "DNSOverHTTPS": { "Enabled": true, "ProviderURL": "https://doh.example/dns-query" } }
+        });
+        write_json(&path, &admin).unwrap();
+        assert!(!firefox_doh_disabled(&path));
+        write_firefox_policy(&path, false).unwrap();
+        assert_eq!(read_json(&path).unwrap(), admin);
+
+        fs::write(&path, "{ not json").unwrap();
+        assert!(write_firefox_policy(&path, true).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn only_our_chromium_policy_file_is_removed() {
+        let dir = temp_dir("chromium");
+        let admin = dir.join("admin.json");
+        write_json(&admin, &json!({ "DnsOverHttpsMode": "secure" })).unwrap();
+        assert!(!chromium_dir_doh_disabled(&dir));
+
+        write_chromium_policy(&dir, true).unwrap();
+        assert!(chromium_dir_doh_disabled(&dir));
+        write_chromium_policy(&dir, false).unwrap();
+        assert!(!dir.join(CHROMIUM_POLICY_FILE).exists());
+        assert_eq!(read_json(&admin).unwrap(), json!({ "DnsOverHttpsMode": "secure" }));
+
+        // An administrator's own file turning DoH off counts, and stays
+        write_json(&admin, &json!({ "DnsOverHttpsMode": "off" })).unwrap();
+        write_chromium_policy(&dir, false).unwrap();
+        assert!(chromium_dir_doh_disabled(&dir));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,175 @@
+// Turns whatever the user typed (`https://YouTube.com:443/watch?v=1`,
+// `bücher.de`, ` reddit.com. `) into the ASCII host name website blocks are
+// written for, or explains why it can't be blocked.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+
+const MAX_DOMAIN_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum DomainErrorReason {
+    Empty,
+    // Hosts entries and DNS answers block names, not addresses
+    IpAddress,
+    Whitespace,
+    InvalidIdn,
+    TooLong,
+    EmptyLabel,
+    LabelTooLong { label: String },
+    InvalidCharacter { label: String },
+    HyphenAtLabelEdge { label: String },
+    // `localhost`, `com`, ...
+    SingleLabel,
+    NumericTopLevel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainError {
+    pub input: String,
+    pub reason: DomainErrorReason,
+    pub message: String,
+}
+
+impl DomainError {
+    fn new(input: &str, reason: DomainErrorReason) -> Self {
+        let message = match &reason {
+            DomainErrorReason::Empty => "no domain given".to_string(),
+            DomainErrorReason::IpAddress => "IP addresses can't be blocked, only domains".to_string(),
+            DomainErrorReason::Whitespace => "contains spaces".to_string(),
+            DomainErrorReason::InvalidIdn => "not a valid internationalized domain".to_string(),
+            DomainErrorReason::TooLong => format!("longer than {} characters", MAX_DOMAIN_LEN),
+            DomainErrorReason::EmptyLabel => "has an empty label (\"..\")".to_string(),
+            DomainErrorReason::LabelTooLong { label } => {
+                format!("label '{}' is longer than {} characters", label, MAX_LABEL_LEN)
+            }
+            DomainErrorReason::InvalidCharacter { label } => {
+                format!("label '{}' contains characters not allowed in domains", label)
+            }
+            DomainErrorReason::HyphenAtLabelEdge { label } => {
+                format!("label '{}' starts or ends with a hyphen", label)
+            }
+            DomainErrorReason::SingleLabel => "needs at least two labels, e.g. example.com".to_string(),
+            DomainErrorReason::NumericTopLevel => "top-level domain can't be numeric".to_string(),
+        };
+        Self {
+            input: input.to_string(),
+            reason,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid domain '{}': {}", self.input, self.message)
+    }
+}
+
+// Lowercase ASCII (punycode) host name of a URL or host. Scheme, credentials,
+// port, path, query and fragment are dropped, as is a trailing dot.
+pub fn normalize(input: &str) -> Result<String, DomainError> {
+    let error = |reason| Err(DomainError::new(input, reason));
+
+    let trimmed = input.trim();
+    let without_scheme = trimmed
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(trimmed);
+    let authority = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+
+    // `[::1]:80` and bare IPv6 addresses have colons of their own
+    let unbracketed = host_port.trim_start_matches('[').split(']').next().unwrap_or_default();
+    if unbracketed.parse::<IpAddr>().is_ok() {
+        return error(DomainErrorReason::IpAddress);
+    }
+    let host = match host_port.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host_port,
+    };
+    let host = host.strip_suffix('.').unwrap_or(host);
+
+    if host.is_empty() {
+        return error(DomainErrorReason::Empty);
+    }
+    if host.chars().any(char::is_whitespace) {
+        return error(DomainErrorReason::Whitespace);
+    }
+    if host.parse::<IpAddr>().is_ok() {
+        return error(DomainErrorReason::IpAddress);
+    }
+
+    // UTS #46 mapping: lowercases, normalizes and punycode-encodes
+    let Ok(ascii) = idna::domain_to_ascii(host) else {
+        return error(DomainErrorReason::InvalidIdn);
+    };
+    if ascii.is_empty() {
+        return error(DomainErrorReason::Empty);
+    }
+    if ascii.len() > MAX_DOMAIN_LEN {
+        return error(DomainErrorReason::TooLong);
+    }
+
+    let labels: Vec<&str> = ascii.split('.').collect();
+    for label in &labels {
+        if label.is_empty() {
+            return error(DomainErrorReason::EmptyLabel);
+        }
+        if label.len() > MAX_LABEL_LEN {
+            return error(DomainErrorReason::LabelTooLong {
+                label: label.to_string(),
+            });
+        }
+        // Underscores aren't valid in host names but do appear in real
+        // blocklists (e.g. `ad_server.example.com`) and resolve fine
+        if !label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return error(DomainErrorReason::InvalidCharacter {
+                label: label.to_string(),
+            });
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return error(DomainErrorReason::HyphenAtLabelEdge {
+                label: label.to_string(),
+            });
+        }
+    }
+    if labels.len() < 2 {
+        return error(DomainErrorReason::SingleLabel);
+    }
+    if labels.last().is_some_and(|tld| tld.chars().all(|c| c.is_ascii_digit())) {
+        return error(DomainErrorReason::NumericTopLevel);
+    }
+
+    Ok(ascii)
+}
+
+// Normalize every input, keeping the first occurrence of each domain. Inputs
+// that aren't valid domains are returned separately.
+pub fn normalize_all<S: AsRef<str>>(inputs: &[S]) -> (Vec<String>, Vec<DomainError>) {
+    let mut seen = std::collections::HashSet::new();
+    let mut domains = Vec::new();
+    let mut errors = Vec::new();
+
+    for input in inputs {
+        match normalize(input.as_ref()) {
+            Ok(domain) => {
+                if seen.insert(domain.clone()) {
+                    domains.push(domain);
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    (domains, errors)
+}
@@ -0,0 +1,248 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::rules::{self, BlockRule};
+use crate::store::Store;
+use crate::matcher::{HashCache, MatchReason, ProcessMatcher, RuleMatch};
+use crate::termination::{self, TerminationOutcome};
+use crate::{ProcessInfo, ProcessMonitor};
+
+// Launches are handled as they happen; the full scan is a safety net for
+// anything the watcher missed (e.g. apps already running when a rule starts)
+const SCAN_INTERVAL: Duration = Duration::from_secs(10);
+
+// Rate limiting: max processes to kill per scan
+const MAX_KILLS_PER_SCAN: usize = 50;
+
+// Payload of the `process-killed` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessKilledEvent {
+    pub rule_id: String,
+    pub app_name: String,
+    pub process_name: String,
+    pub process_path: String,
+    pub pid: u32,
+    pub reason: MatchReason,
+    // Every PID in the app's process tree that was terminated
+    pub terminated_pids: Vec<u32>,
+    // True if the app ignored SIGTERM and had to be force-killed
+    pub forced: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnforcementStatus {
+    pub enabled: bool,
+    pub active_rule_count: usize,
+}
+
+// Enforcement engine state. The scan loop runs on the async runtime and keeps
+// going even if the webview stalls or reloads. Rules come straight from the
+// store and are re-evaluated on every scan.
+#[derive(Clone)]
+pub struct EnforcementEngine {
+    enabled: Arc<AtomicBool>,
+}
+
+impl EnforcementEngine {
+    pub fn new() -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Spawn the enforcement loop. Called once from the `setup` hook with the
+    // receiving end of the process launch watcher.
+    pub fn start(&self, app: AppHandle, mut launches: UnboundedReceiver<u32>) {
+        let engine = self.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut ticker = tokio::time::interval(SCAN_INTERVAL);
+
+            loop {
+                // Either a periodic full scan or a batch of newly started PIDs
+                let launched = tokio::select! {
+                    _ = ticker.tick() => None,
+                    Some(pid) = launches.recv() => {
+                        let mut pids = vec![pid];
+                        while let Ok(pid) = launches.try_recv() {
+                            pids.push(pid);
+                        }
+                        Some(pids)
+                    }
+                };
+
+                if !engine.enabled.load(Ordering::SeqCst) {
+                    continue;
+                }
+
+                let matcher = ProcessMatcher::compile(&active_block_rules(&app));
+                if matcher.is_empty() {
+                    continue;
+                }
+
+                // Matching may hash executables, so it runs off the async
+                // runtime along with the scan
+                let monitor = app.state::<ProcessMonitor>().inner().clone();
+                let hashes = app.state::<HashCache>().inner().clone();
+                let scan = tauri::async_runtime::spawn_blocking(move || {
+                    let processes = match launched {
+                        Some(pids) => monitor.process_details_for(&pids),
+                        None => monitor.process_details(),
+                    };
+                    let hits: HashMap<u32, RuleMatch> = processes
+                        .iter()
+                        .filter_map(|process| matcher.find_match(process, &hashes).map(|hit| (process.pid, hit)))
+                        .collect();
+                    (processes, hits)
+                });
+                let (processes, hits) = match scan.await {
+                    Ok(scanned) => scanned,
+                    Err(e) => {
+                        eprintln!("Enforcement scan panicked: {}", e);
+                        continue;
+                    }
+                };
+
+                enforce(&app, &processes, hits).await;
+            }
+        });
+    }
+}
+
+impl Default for EnforcementEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn active_block_rules(app: &AppHandle) -> Vec<BlockRule> {
+    let now = chrono::Local::now();
+    app.state::<Store>()
+        .read(|data| rules::active_at(&data.block_rules, &now))
+}
+
+// Terminate the process tree of every process matching an active rule (`hits`,
+// keyed by PID) and report each kill to the UI. Trees are terminated
+// concurrently so one slow app doesn't hold up the rest.
+async fn enforce(app: &AppHandle, processes: &[ProcessInfo], hits: HashMap<u32, RuleMatch>) {
+    let grace_period = Duration::from_millis(
+        app.state::<Store>()
+            .read(|data| data.settings.kill_grace_period_ms),
+    );
+
+    // A matched child (e.g. a Discord helper) is covered by its matched
+    // ancestor's tree, so only the topmost matches are killed
+    let parents: HashMap<u32, u32> = processes
+        .iter()
+        .filter_map(|p| p.parent_pid.map(|parent| (p.pid, parent)))
+        .collect();
+    let mut roots: Vec<_> = processes
+        .iter()
+        .filter(|p| !has_matched_ancestor(p.pid, &parents, &hits))
+        .filter_map(|p| hits.get(&p.pid).map(|hit| (p.clone(), hit.clone())))
+        .collect();
+
+    if roots.len() > MAX_KILLS_PER_SCAN {
+        eprintln!(
+            "Rate limit reached: {} apps matched this scan. Skipping remaining processes.",
+            roots.len()
+        );
+        roots.truncate(MAX_KILLS_PER_SCAN);
+    }
+
+    let mut kills = tokio::task::JoinSet::new();
+    for (process, hit) in roots {
+        let levels = termination::tree_levels_bottom_up(process.pid, processes);
+        kills.spawn(async move {
+            let results = termination::terminate_levels(levels, grace_period).await;
+            (process, hit, results)
+        });
+    }
+
+    while let Some(joined) = kills.join_next().await {
+        let Ok((process, hit, results)) = joined else {
+            continue;
+        };
+
+        for result in &results {
+            match result.outcome {
+                TerminationOutcome::PermissionDenied => {
+                    eprintln!("Permission denied killing process {}", result.pid)
+                }
+                TerminationOutcome::KillPending => {
+                    eprintln!("Process {} has not exited yet after SIGKILL", result.pid)
+                }
+                _ => {}
+            }
+        }
+
+        let terminated_pids: Vec<u32> = results
+            .iter()
+            .filter(|r| r.outcome == TerminationOutcome::Terminated)
+            .map(|r| r.pid)
+            .collect();
+        if terminated_pids.is_empty() {
+            continue;
+        }
+
+        let _ = app.emit(
+            "process-killed",
+            ProcessKilledEvent {
+                rule_id: hit.rule_id,
+                app_name: hit.app_name,
+                process_name: process.name,
+                process_path: process.path,
+                pid: process.pid,
+                reason: hit.reason,
+                forced: results.iter().any(|r| r.forced),
+                terminated_pids,
+                timestamp: chrono::Utc::now().timestamp_millis(),
+            },
+        );
+    }
+}
+
+fn has_matched_ancestor<T>(pid: u32, parents: &HashMap<u32, u32>, hits: &HashMap<u32, T>) -> bool {
+    let mut current = pid;
+    // Bounded walk in case PID reuse produced a cycle
+    for _ in 0..parents.len() {
+        match parents.get(&current) {
+            Some(&parent) if parent != current => {
+                if hits.contains_key(&parent) {
+                    return true;
+                }
+                current = parent;
+            }
+            _ => return false,
+        }
+    }
+    false
+}
+
+#[tauri::command]
+pub async fn set_enforcement_enabled(
+    enabled: bool,
+    engine: tauri::State<'_, EnforcementEngine>,
+) -> Result<(), String> {
+    engine.enabled.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_enforcement_status(
+    app: AppHandle,
+    engine: tauri::State<'_, EnforcementEngine>,
+) -> Result<EnforcementStatus, String> {
+    Ok(EnforcementStatus {
+        enabled: engine.enabled.load(Ordering::SeqCst),
+        active_rule_count: active_block_rules(&app).len(),
+    })
+}
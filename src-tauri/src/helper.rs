@@ -0,0 +1,752 @@
+// Privileged helper: a small root service that makes the system changes the
+// app can't make as a normal user (hosts file, resolver config, nftables,
+// browser policies). It also kills processes for the app, but only ones that
+// belong to the connecting user, never root's. The app talks to it over a
+// Unix socket, one JSON request and one JSON response per connection. Only
+// the uids and executables the helper was started with may connect, and only
+// the requests below exist, so the webview never needs elevated rights.
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::browser_policy::{BrowserUrlPolicy, UrlBlocklist};
+use crate::doh::BrowserDohPolicy;
+use crate::termination::TerminationResult;
+use crate::website_backend::{ApplyResult, BackendKind};
+
+pub const SOCKET_PATH: &str = "/run/neu-helper.sock";
+
+// The helper has no app data dir; its hosts backups live here
+#[cfg(target_os = "linux")]
+const STATE_DIR: &str = "/var/lib/neu-helper";
+
+// Long imported blocklists make for large requests
+#[cfg(target_os = "linux")]
+const MAX_MESSAGE_LEN: u64 = 32 * 1024 * 1024;
+
+// Clients get this long to send their request
+#[cfg(target_os = "linux")]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Applying blocks restarts services and verifies every domain
+#[cfg(target_os = "linux")]
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Connections served at once; further ones are dropped until one finishes
+#[cfg(target_os = "linux")]
+const MAX_CLIENTS: usize = 16;
+
+// Set in the helper process, which must never call itself
+static IS_HELPER: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum HelperRequest {
+    // Block exactly `domains` through `backend`, clearing the other backends
+    SetWebsiteDomains {
+        backend: BackendKind,
+        domains: Vec<String>,
+        // DNS sinkhole address the systemd-resolved backend routes to
+        sinkhole: Option<SocketAddr>,
+    },
+    ListHostsBackups,
+    // Restore a hosts backup with a NEU block for `domains`
+    RestoreHostsBackup {
+        name: Option<String>,
+        domains: Vec<String>,
+    },
+    KillProcess {
+        pid: u32,
+        grace_period_ms: u64,
+    },
+    // Write or remove the browser policies that turn DNS-over-HTTPS off
+    SetDohPolicies {
+        disable: bool,
+    },
+    // Replace our entries in the browsers' URL blocklist policies
+    SetUrlBlocklist {
+        blocklist: UrlBlocklist,
+    },
+    // Bind a port below 1024 on loopback (block page on 80, sinkhole on 53).
+    // The socket is passed back next to the `Bound` response.
+    BindSocket {
+        addr: SocketAddr,
+        kind: SocketKind,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum HelperResponse {
+    Applied(ApplyResult),
+    Backups { names: Vec<String> },
+    Restored { name: String },
+    Killed(TerminationResult),
+    DohPolicies { browsers: Vec<BrowserDohPolicy> },
+    UrlPolicies { browsers: Vec<BrowserUrlPolicy> },
+    Bound,
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SocketKind {
+    Tcp,
+    Udp,
+}
+
+// Connection to a running helper
+pub struct HelperClient {
+    #[cfg(target_os = "linux")]
+    stream: std::os::unix::net::UnixStream,
+}
+
+impl HelperClient {
+    // `None` if no helper is running; callers then make the change themselves
+    pub fn connect() -> Option<Self> {
+        if IS_HELPER.load(Ordering::Relaxed) {
+            return None;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            let stream = std::os::unix::net::UnixStream::connect(SOCKET_PATH).ok()?;
+            Some(Self { stream })
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn call(self, request: &HelperRequest) -> Result<HelperResponse, String> {
+        use std::io::Read;
+
+        let stream = self.send(request)?;
+        let mut response = Vec::new();
+        stream
+            .take(MAX_MESSAGE_LEN)
+            .read_to_end(&mut response)
+            .map_err(|e| format!("No response from the privileged helper: {}", e))?;
+        parse_response(&response)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn send(self, request: &HelperRequest) -> Result<std::os::unix::net::UnixStream, String> {
+        use std::io::Write;
+
+        let mut line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let mut stream = self.stream;
+        let _ = stream.set_read_timeout(Some(RESPONSE_TIMEOUT));
+        stream
+            .write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to send request to the privileged helper: {}", e))?;
+        Ok(stream)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn call(self, _request: &HelperRequest) -> Result<HelperResponse, String> {
+        Err("The privileged helper is only available on Linux".to_string())
+    }
+
+    pub fn set_website_domains(
+        self,
+        backend: BackendKind,
+        domains: &[String],
+        sinkhole: Option<SocketAddr>,
+    ) -> Result<ApplyResult, String> {
+        let request = HelperRequest::SetWebsiteDomains {
+            backend,
+            domains: domains.to_vec(),
+            sinkhole,
+        };
+        match self.call(&request)? {
+            HelperResponse::Applied(result) => Ok(result),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn list_hosts_backups(self) -> Result<Vec<String>, String> {
+        match self.call(&HelperRequest::ListHostsBackups)? {
+            HelperResponse::Backups { names } => Ok(names),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn restore_hosts_backup(self, name: Option<String>, domains: Vec<String>) -> Result<String, String> {
+        match self.call(&HelperRequest::RestoreHostsBackup { name, domains })? {
+            HelperResponse::Restored { name } => Ok(name),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn kill_process(self, pid: u32, grace_period: Duration) -> Result<TerminationResult, String> {
+        let request = HelperRequest::KillProcess {
+            pid,
+            grace_period_ms: grace_period.as_millis() as u64,
+        };
+        match self.call(&request)? {
+            HelperResponse::Killed(result) => Ok(result),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn set_doh_policies(self, disable: bool) -> Result<Vec<BrowserDohPolicy>, String> {
+        match self.call(&HelperRequest::SetDohPolicies { disable })? {
+            HelperResponse::DohPolicies { browsers } => Ok(browsers),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn set_url_blocklist(self, blocklist: &UrlBlocklist) -> Result<Vec<BrowserUrlPolicy>, String> {
+        let request = HelperRequest::SetUrlBlocklist {
+            blocklist: blocklist.clone(),
+        };
+        match self.call(&request)? {
+            HelperResponse::UrlPolicies { browsers } => Ok(browsers),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn bind_socket(self, addr: SocketAddr, kind: SocketKind) -> Result<std::os::fd::OwnedFd, String> {
+        use std::io::Read;
+
+        let stream = self.send(&HelperRequest::BindSocket { addr, kind })?;
+        let mut response = vec![0; 1024];
+        let (len, fd) =
+            recv_with_fd(&stream, &mut response).map_err(|e| format!("No response from the privileged helper: {}", e))?;
+        response.truncate(len);
+        (&stream)
+            .take(MAX_MESSAGE_LEN)
+            .read_to_end(&mut response)
+            .map_err(|e| format!("No response from the privileged helper: {}", e))?;
+        match parse_response(&response)? {
+            HelperResponse::Bound => fd.ok_or_else(|| "The privileged helper sent no socket".to_string()),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn parse_response(response: &[u8]) -> Result<HelperResponse, String> {
+    match serde_json::from_slice(response) {
+        Ok(HelperResponse::Error { message }) => Err(message),
+        Ok(response) => Ok(response),
+        Err(e) => Err(format!("Invalid response from the privileged helper: {}", e)),
+    }
+}
+
+// Room for the one file descriptor passed with a message
+#[cfg(target_os = "linux")]
+type FdControl = [u64; 4];
+
+// Receive the first part of a message along with the file descriptor sent
+// with it, if any. Returns the number of bytes read into `buf`.
+#[cfg(target_os = "linux")]
+fn recv_with_fd(
+    stream: &std::os::unix::net::UnixStream,
+    buf: &mut [u8],
+) -> std::io::Result<(usize, Option<std::os::fd::OwnedFd>)> {
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let mut control: FdControl = [0; 4];
+    // SAFETY: an all-zero msghdr is valid (no name, no buffers)
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = std::mem::size_of::<FdControl>() as _;
+    // SAFETY: `msg` points at `buf` and `control`, which outlive the call
+    let received = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC) };
+    if received < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut fd = None;
+    // SAFETY: the kernel filled in `control` and `msg_controllen`; the
+    // CMSG_* macros stay within them
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let raw = std::ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const RawFd);
+                fd = Some(OwnedFd::from_raw_fd(raw));
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    Ok((received as usize, fd))
+}
+
+fn unexpected(response: HelperResponse) -> String {
+    format!("Unexpected response from the privileged helper: {:?}", response)
+}
+
+// Who may connect: a program listed in `allowed_exes` running as one of
+// `allowed_uids`. Both must be given.
+#[derive(Debug, Clone, Default)]
+pub struct HelperConfig {
+    pub allowed_uids: Vec<u32>,
+    pub allowed_exes: Vec<std::path::PathBuf>,
+}
+
+// systemd unit that runs the helper as root for the given user and app
+pub fn systemd_unit(helper_path: &std::path::Path, uid: u32, app_path: &std::path::Path) -> String {
+    format!(
+        "[Unit]\nDescription=Not Enough Accountability privileged helper\n\n[Service]\nExecStart={} --serve --allow-uid {} --allow-exe {}\nRestart=on-failure\n\n[Install]\nWantedBy=multi-user.target\n",
+        helper_path.display(),
+        uid,
+        app_path.display()
+    )
+}
+
+#[cfg(target_os = "linux")]
+mod server {
+    use std::fs;
+    use std::io::{self, BufRead, BufReader, Read, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::net::{SocketAddr, TcpListener, UdpSocket};
+    use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::{
+        FdControl, HelperConfig, HelperRequest, HelperResponse, Ordering, SocketKind, IS_HELPER, MAX_CLIENTS,
+        MAX_MESSAGE_LEN, REQUEST_TIMEOUT, SOCKET_PATH, STATE_DIR,
+    };
+    use crate::browser_policy;
+    use crate::doh;
+    use crate::hosts::{self, HostsFile};
+    use crate::termination;
+    use crate::website_backend;
+
+    struct Helper {
+        config: HelperConfig,
+        hosts: HostsFile,
+        // Backends rewrite config files and restart services; one apply at a time
+        apply_lock: Mutex<()>,
+        runtime: tokio::runtime::Runtime,
+        clients: AtomicUsize,
+    }
+
+    // Frees a client slot when its thread finishes
+    struct ClientSlot(Arc<Helper>);
+
+    impl Drop for ClientSlot {
+        fn drop(&mut self) {
+            self.0.clients.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn serve(config: HelperConfig) -> Result<(), String> {
+        // SAFETY: geteuid(2) has no preconditions
+        if unsafe { libc::geteuid() } != 0 {
+            return Err("The privileged helper must run as root".to_string());
+        }
+        if config.allowed_uids.is_empty() {
+            return Err("No --allow-uid given; nobody could use the helper".to_string());
+        }
+        if config.allowed_exes.is_empty() {
+            return Err("No --allow-exe given; any program of an allowed uid could use the helper".to_string());
+        }
+        IS_HELPER.store(true, Ordering::Relaxed);
+
+        let hosts = HostsFile::new(Path::new(STATE_DIR).join(hosts::BACKUP_DIR_NAME));
+        if let Err(e) = hosts.self_check() {
+            eprintln!("Hosts file self-check failed: {}", e);
+        }
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to start runtime: {}", e))?;
+
+        // A socket left behind by a previous run would make bind fail
+        let _ = fs::remove_file(SOCKET_PATH);
+        let listener =
+            UnixListener::bind(SOCKET_PATH).map_err(|e| format!("Failed to bind {}: {}", SOCKET_PATH, e))?;
+        // Anyone may connect; each connection is checked against the config
+        fs::set_permissions(SOCKET_PATH, fs::Permissions::from_mode(0o666))
+            .map_err(|e| format!("Failed to set permissions on {}: {}", SOCKET_PATH, e))?;
+
+        let helper = Arc::new(Helper {
+            config,
+            hosts,
+            apply_lock: Mutex::new(()),
+            runtime,
+            clients: AtomicUsize::new(0),
+        });
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            // Each client holds a thread for up to REQUEST_TIMEOUT before it
+            // is even authorized; don't let a flood of them pile up
+            if helper.clients.fetch_add(1, Ordering::Relaxed) >= MAX_CLIENTS {
+                helper.clients.fetch_sub(1, Ordering::Relaxed);
+                eprintln!("Privileged helper: too many connections, dropping one");
+                continue;
+            }
+            let slot = ClientSlot(helper.clone());
+            std::thread::spawn(move || {
+                if let Err(e) = slot.0.serve_client(stream) {
+                    eprintln!("Privileged helper: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    impl Helper {
+        fn serve_client(&self, mut stream: UnixStream) -> io::Result<()> {
+            stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+            // Read the request even from a peer that gets turned away, so it
+            // sees why instead of a broken pipe
+            let mut line = String::new();
+            BufReader::new((&stream).take(MAX_MESSAGE_LEN)).read_line(&mut line)?;
+            let (response, socket) = match authorize(&stream, &self.config) {
+                Ok(peer_uid) => match serde_json::from_str(&line) {
+                    // The socket goes back next to the response, not in it
+                    Ok(HelperRequest::BindSocket { addr, kind }) => match bind_socket(addr, kind) {
+                        Ok(socket) => (HelperResponse::Bound, Some(socket)),
+                        Err(message) => (HelperResponse::Error { message }, None),
+                    },
+                    Ok(request) => (self.handle(request, peer_uid), None),
+                    Err(e) => (
+                        HelperResponse::Error {
+                            message: format!("Invalid request: {}", e),
+                        },
+                        None,
+                    ),
+                },
+                Err(message) => {
+                    eprintln!("Privileged helper: rejected connection: {}", message);
+                    (HelperResponse::Error { message }, None)
+                }
+            };
+            let json = serde_json::to_vec(&response)?;
+            match socket {
+                Some(socket) => send_with_fd(&stream, &json, socket.as_fd()),
+                None => stream.write_all(&json),
+            }
+        }
+
+        fn handle(&self, request: HelperRequest, peer_uid: u32) -> HelperResponse {
+            let result = match request {
+                HelperRequest::SetWebsiteDomains {
+                    backend,
+                    domains,
+                    sinkhole,
+                } => {
+                    let _guard = self.apply_lock.lock().unwrap();
+                    website_backend::apply_with(backend, &self.hosts, sinkhole, &domains).map(HelperResponse::Applied)
+                }
+                HelperRequest::ListHostsBackups => self
+                    .hosts
+                    .list_backups()
+                    .map(|names| HelperResponse::Backups { names }),
+                HelperRequest::RestoreHostsBackup { name, domains } => self
+                    .hosts
+                    .restore_backup(name.as_deref(), &domains)
+                    .map(|name| HelperResponse::Restored { name }),
+                HelperRequest::KillProcess { pid, grace_period_ms } => killable(pid, peer_uid).map(|()| {
+                    let grace_period = Duration::from_millis(grace_period_ms);
+                    HelperResponse::Killed(
+                        self.runtime
+                            .block_on(termination::terminate_process(pid, grace_period)),
+                    )
+                }),
+                HelperRequest::SetDohPolicies { disable } => Ok(HelperResponse::DohPolicies {
+                    browsers: doh::set_policies(disable),
+                }),
+                HelperRequest::SetUrlBlocklist { blocklist } => Ok(HelperResponse::UrlPolicies {
+                    browsers: browser_policy::write_blocklist(&blocklist),
+                }),
+                HelperRequest::BindSocket { .. } => unreachable!("sockets are bound in serve_client"),
+            };
+            result.unwrap_or_else(|message| HelperResponse::Error { message })
+        }
+    }
+
+    // The peer's uid and executable must both be allowed; returns the uid
+    pub(super) fn authorize(stream: &UnixStream, config: &HelperConfig) -> Result<u32, String> {
+        let peer = peer_credentials(stream).map_err(|e| format!("Failed to read peer credentials: {}", e))?;
+        if !config.allowed_uids.contains(&peer.uid) {
+            return Err(format!("uid {} is not allowed to use the privileged helper", peer.uid));
+        }
+        let exe = fs::read_link(format!("/proc/{}/exe", peer.pid))
+            .map_err(|e| format!("Failed to identify the connecting program: {}", e))?;
+        if config.allowed_exes.contains(&exe) {
+            Ok(peer.uid)
+        } else {
+            Err(format!("{} is not allowed to use the privileged helper", exe.display()))
+        }
+    }
+
+    // Only ports below 1024 on loopback: the app binds any other port itself,
+    // and no socket handed out may be reachable from other machines
+    pub(super) fn bind_socket(addr: SocketAddr, kind: SocketKind) -> Result<OwnedFd, String> {
+        if !addr.ip().is_loopback() {
+            return Err(format!("{} is not a loopback address", addr.ip()));
+        }
+        if addr.port() == 0 || addr.port() >= 1024 {
+            return Err(format!("Port {} doesn't need the privileged helper", addr.port()));
+        }
+        let socket = match kind {
+            SocketKind::Tcp => TcpListener::bind(addr).map(OwnedFd::from),
+            SocketKind::Udp => UdpSocket::bind(addr).map(OwnedFd::from),
+        };
+        socket.map_err(|e| format!("Failed to bind {}: {}", addr, e))
+    }
+
+    // Send `data` with `fd` attached to its first byte
+    pub(super) fn send_with_fd(stream: &UnixStream, data: &[u8], fd: BorrowedFd) -> io::Result<()> {
+        let mut iov = libc::iovec {
+            iov_base: data.as_ptr() as *mut libc::c_void,
+            iov_len: data.len(),
+        };
+        let mut control: FdControl = [0; 4];
+        // SAFETY: an all-zero msghdr is valid (no name, no buffers)
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        // SAFETY: CMSG_SPACE only does arithmetic
+        msg.msg_controllen = unsafe { libc::CMSG_SPACE(std::mem::size_of::<RawFd>() as u32) } as _;
+        // SAFETY: `control` has room for one header and one fd, which is
+        // what msg_controllen says
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<RawFd>() as u32) as _;
+            std::ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut RawFd, fd.as_raw_fd());
+        }
+        // SAFETY: `msg` points at `data` and `control`, which outlive the call
+        let sent = unsafe { libc::sendmsg(stream.as_raw_fd(), &msg, libc::MSG_NOSIGNAL) };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // The fd went with the first part; send the rest as usual
+        (&*stream).write_all(&data[sent as usize..])
+    }
+
+    fn peer_credentials(stream: &UnixStream) -> io::Result<libc::ucred> {
+        let mut cred = libc::ucred { pid: 0, uid: 0, gid: 0 };
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        // SAFETY: `cred` and `len` are valid for writes of the size passed
+        let result = unsafe {
+            libc::getsockopt(
+                stream.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                &mut cred as *mut libc::ucred as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(cred)
+    }
+
+    // Only the caller's own processes may be killed: every one of the
+    // process's real, effective, saved and filesystem uids must be the
+    // peer's. That rules out kernel threads, root, setuid programs and other
+    // users' processes.
+    pub(super) fn killable(pid: u32, peer_uid: u32) -> Result<(), String> {
+        if fs::read_link(format!("/proc/{}/exe", pid)).is_err() {
+            return Err(format!("Process {} is a kernel thread or doesn't exist", pid));
+        }
+        let status = fs::read_to_string(format!("/proc/{}/status", pid))
+            .map_err(|e| format!("Failed to read process {}: {}", pid, e))?;
+        let uids = status
+            .lines()
+            .find_map(|line| line.strip_prefix("Uid:"))
+            .and_then(|uids| {
+                uids.split_whitespace()
+                    .map(|uid| uid.parse::<u32>().ok())
+                    .collect::<Option<Vec<u32>>>()
+            })
+            .filter(|uids| !uids.is_empty())
+            .ok_or_else(|| format!("Failed to read the owner of process {}", pid))?;
+        if uids.contains(&0) {
+            return Err(format!("Process {} runs as root", pid));
+        }
+        if uids.iter().any(|&uid| uid != peer_uid) {
+            return Err(format!("Process {} belongs to another user", pid));
+        }
+        Ok(())
+    }
+}
+
+// Entry point of the `neu-helper` binary:
+//
+//     neu-helper --serve --allow-uid <uid>... --allow-exe <path>...
+//     neu-helper --unit <uid> <app path>    print a systemd unit
+pub fn run() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let usage = || -> ! {
+        eprintln!("Usage: neu-helper --serve --allow-uid <uid>... --allow-exe <path>...");
+        eprintln!("       neu-helper --unit <uid> <app path>");
+        std::process::exit(2);
+    };
+
+    match args.first().map(String::as_str) {
+        Some("--unit") => {
+            let (Some(uid), Some(app_path)) = (args.get(1).and_then(|uid| uid.parse().ok()), args.get(2)) else {
+                usage();
+            };
+            let helper_path = std::env::current_exe().unwrap_or_default();
+            let app_path = std::path::Path::new(app_path);
+            print!("{}", systemd_unit(&helper_path, uid, app_path));
+        }
+        Some("--serve") => {
+            let mut config = HelperConfig::default();
+            let mut rest = args[1..].iter();
+            while let Some(flag) = rest.next() {
+                match (flag.as_str(), rest.next()) {
+                    ("--allow-uid", Some(uid)) => match uid.parse() {
+                        Ok(uid) => config.allowed_uids.push(uid),
+                        Err(_) => usage(),
+                    },
+                    ("--allow-exe", Some(path)) => config.allowed_exes.push(path.into()),
+                    _ => usage(),
+                }
+            }
+
+            #[cfg(target_os = "linux")]
+            let served = server::serve(config);
+            #[cfg(not(target_os = "linux"))]
+            let served: Result<(), String> = {
+                let _ = config;
+                Err("The privileged helper is only available on Linux".to_string())
+            };
+            if let Err(e) = served {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        _ => usage(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_decode_from_the_wire_format() {
+        let request: HelperRequest =
+            serde_json::from_str(r#"{"type":"killProcess","pid":42,"gracePeriodMs":500}"#).unwrap();
+        assert!(matches!(
+            request,
+            HelperRequest::KillProcess {
+                pid: 42,
+                grace_period_ms: 500
+            }
+        ));
+
+        let request: HelperRequest = serde_json::from_str(
+            r#"{"type":"restoreHostsBackup","name":null,"domains":["reddit.com"]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            request,
+            HelperRequest::RestoreHostsBackup { name: None, domains } if domains == ["reddit.com"]
+        ));
+    }
+
+    #[test]
+    fn unknown_or_cut_off_requests_are_rejected() {
+        for line in [
+            r#"{"type":"runCommand","command":"rm -rf /"}"#,
+            r#"{"type":"killProcess","pid":-1,"gracePeriodMs":0}"#,
+            r#"{"type":"killProcess","pid":42"#,
+            "",
+        ] {
+            assert!(serde_json::from_str::<HelperRequest>(line).is_err(), "{}", line);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn peer_needs_an_allowed_uid_and_executable() {
+        use std::os::unix::net::UnixStream;
+
+        let (client, _server) = UnixStream::pair().unwrap();
+        // SAFETY: getuid has no preconditions
+        let uid = unsafe { libc::getuid() };
+        let exe = std::fs::read_link("/proc/self/exe").unwrap();
+        let config = |uids: Vec<u32>, exes: Vec<std::path::PathBuf>| HelperConfig {
+            allowed_uids: uids,
+            allowed_exes: exes,
+        };
+
+        assert_eq!(server::authorize(&client, &config(vec![uid], vec![exe.clone()])), Ok(uid));
+        assert!(server::authorize(&client, &config(vec![uid + 1], vec![exe.clone()])).is_err());
+        assert!(server::authorize(&client, &config(vec![uid], vec!["/usr/bin/true".into()])).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn only_privileged_loopback_ports_are_bound() {
+        let bind = |addr: &str| server::bind_socket(addr.parse().unwrap(), SocketKind::Tcp);
+        assert!(bind("0.0.0.0:80").is_err());
+        assert!(bind("192.168.1.2:53").is_err());
+        assert!(bind("127.0.0.1:8080").is_err());
+        assert!(bind("127.0.0.1:0").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn bound_sockets_are_passed_with_the_response() {
+        use std::io::Read;
+        use std::os::fd::AsFd;
+        use std::os::unix::net::UnixStream;
+
+        let (client, server) = UnixStream::pair().unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let json = serde_json::to_vec(&HelperResponse::Bound).unwrap();
+        server::send_with_fd(&server, &json, listener.as_fd()).unwrap();
+        drop(server);
+        drop(listener);
+
+        let mut response = vec![0; 4];
+        let (len, fd) = recv_with_fd(&client, &mut response).unwrap();
+        response.truncate(len);
+        (&client).read_to_end(&mut response).unwrap();
+        assert!(matches!(parse_response(&response), Ok(HelperResponse::Bound)));
+
+        // The passed socket still listens after the sender closed its copy
+        let listener = std::net::TcpListener::from(fd.unwrap());
+        assert_eq!(listener.local_addr().unwrap(), addr);
+        std::net::TcpStream::connect(addr).unwrap();
+        listener.accept().unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn only_the_peers_own_processes_are_killable() {
+        // SAFETY: getuid has no preconditions
+        let uid = unsafe { libc::getuid() };
+        let own = server::killable(std::process::id(), uid);
+        if uid == 0 {
+            assert!(own.is_err(), "root's processes are never killable");
+        } else {
+            assert_eq!(own, Ok(()));
+        }
+        assert!(server::killable(std::process::id(), uid + 1).is_err());
+        // init runs as root
+        assert!(server::killable(1, uid.max(1)).is_err());
+        assert!(server::killable(u32::MAX, uid).is_err());
+    }
+}
@@ -0,0 +1,727 @@
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::helper::HelperClient;
+use crate::store::Store;
+use crate::website_backend::{self, BackendKind};
+
+// Website blocking via hosts file modification (the default backend)
+#[cfg(target_os = "windows")]
+pub const HOSTS_FILE_PATH: &str = "C:\\Windows\\System32\\drivers\\etc\\hosts";
+
+#[cfg(not(target_os = "windows"))]
+pub const HOSTS_FILE_PATH: &str = "/etc/hosts";
+
+pub const NEU_MARKER_START: &str = "# NEU_BLOCK_START - Do not edit this section manually";
+pub const NEU_MARKER_END: &str = "# NEU_BLOCK_END";
+
+// Name of the backup directory inside the app data dir
+pub const BACKUP_DIR_NAME: &str = "hosts_backups";
+
+// Inside the backup directory: the names NEU last wrote to its block, one per
+// line, so edits made while the app was closed can still be told apart
+const APPLIED_FILE_NAME: &str = "applied.txt";
+
+// Backups older than the newest `MAX_BACKUPS` are deleted
+const MAX_BACKUPS: usize = 10;
+
+// Addresses blocked names point at, one per address family. Windows doesn't
+// connect 0.0.0.0 and :: to loopback, so there they point at loopback itself
+// and still reach the block page.
+#[cfg(not(windows))]
+const SINKHOLE_V4: &str = "0.0.0.0";
+#[cfg(not(windows))]
+const SINKHOLE_V6: &str = "::";
+#[cfg(windows)]
+const SINKHOLE_V4: &str = "127.0.0.1";
+#[cfg(windows)]
+const SINKHOLE_V6: &str = "::1";
+
+// Prefix of the temp files written next to the hosts file
+const TEMP_PREFIX: &str = ".hosts.neu-";
+
+// Owns every write to the hosts file so the blocker, restores and repairs
+// never interleave. Also remembers the domains it last wrote, so edits made
+// by anyone else can be told apart from our own.
+pub struct HostsFile {
+    path: PathBuf,
+    backup_dir: PathBuf,
+    applied_file: PathBuf,
+    // Names NEU expects in its block, `www.` variants included; guarded writes
+    // happen under this lock
+    applied: Mutex<BTreeSet<String>>,
+}
+
+// Difference between the NEU block on disk and what NEU last wrote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostsTamper {
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+}
+
+impl HostsFile {
+    // Expect what was last written before the app was closed, so a block
+    // edited in the meantime shows up in `check_tamper`. Without a saved
+    // state (first run) the block on disk is taken as it is.
+    pub fn new(backup_dir: PathBuf) -> Self {
+        let path = PathBuf::from(HOSTS_FILE_PATH);
+        let applied_file = backup_dir.join(APPLIED_FILE_NAME);
+        let applied = match fs::read_to_string(&applied_file) {
+            Ok(saved) => saved.lines().map(str::to_string).collect(),
+            Err(_) => fs::read_to_string(&path)
+                .map(|content| block_names(&content))
+                .unwrap_or_default(),
+        };
+        Self {
+            path,
+            backup_dir,
+            applied_file,
+            applied: Mutex::new(applied),
+        }
+    }
+
+    pub fn read(&self) -> Result<String, String> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => Ok(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(format!("Failed to read hosts file: {}", e)),
+        }
+    }
+
+    // Domains in the NEU block
+    pub fn blocked_domains(&self) -> Result<Vec<String>, String> {
+        Ok(block_entries(&self.read()?))
+    }
+
+    // Replace the NEU block with entries for `domains` (or drop it if empty)
+    pub fn set_domains(&self, domains: &[String]) -> Result<(), String> {
+        let mut applied = self.applied.lock().unwrap();
+        let current = self.read()?;
+        let updated = with_block(&current, domains);
+        if updated != current {
+            self.backup(&current)?;
+            write_atomically(&self.path, &updated)?;
+        }
+        self.remember(&mut applied, block_names(&updated));
+        Ok(())
+    }
+
+    // Expect `domains` in the NEU block from now on, when someone we trust
+    // (the privileged helper) writes it instead of `set_domains`
+    pub fn expect_domains(&self, domains: &[String]) {
+        let mut applied = self.applied.lock().unwrap();
+        self.remember(&mut applied, block_names(&render("", domains)));
+    }
+
+    // Take the block on disk as the expected state
+    pub fn resync(&self) -> Result<(), String> {
+        let mut applied = self.applied.lock().unwrap();
+        self.remember(&mut applied, block_names(&self.read()?));
+        Ok(())
+    }
+
+    // Restore a backup by file name, or the newest one, with a NEU block for
+    // `domains` in place of the backup's own. Returns the name used.
+    pub fn restore_backup(&self, name: Option<&str>, domains: &[String]) -> Result<String, String> {
+        let mut applied = self.applied.lock().unwrap();
+        let backups = self.list_backups()?;
+        let name = match name {
+            // Only names we listed, so a caller can't point us at another file
+            Some(name) => backups
+                .iter()
+                .find(|backup| backup.as_str() == name)
+                .ok_or_else(|| format!("No hosts backup named {}", name))?,
+            None => backups.last().ok_or("No hosts backups available")?,
+        };
+
+        let backup = fs::read_to_string(self.backup_dir.join(name))
+            .map_err(|e| format!("Failed to read hosts backup: {}", e))?;
+        let content = with_block(&backup, domains);
+        write_atomically(&self.path, &content)?;
+        // A deliberate restore isn't tampering
+        self.remember(&mut applied, block_names(&content));
+        Ok(name.clone())
+    }
+
+    // Backup file names, oldest first
+    pub fn list_backups(&self) -> Result<Vec<String>, String> {
+        let entries = match fs::read_dir(&self.backup_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to list hosts backups: {}", e)),
+        };
+
+        // Timestamps are zero-padded, so name order is chronological
+        let mut names: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("hosts-") && name.ends_with(".bak"))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    // Expect `names` from now on and save them for the next start. Takes the
+    // locked set so the file is written under the same lock.
+    fn remember(&self, applied: &mut BTreeSet<String>, names: BTreeSet<String>) {
+        if *applied == names && self.applied_file.exists() {
+            return;
+        }
+        let saved: String = names.iter().map(|name| format!("{}\n", name)).collect();
+        let written = fs::create_dir_all(&self.backup_dir)
+            .and_then(|_| fs::write(&self.applied_file, saved));
+        if let Err(e) = written {
+            eprintln!("Failed to save the applied hosts entries: {}", e);
+        }
+        *applied = names;
+    }
+
+    // Save `content` as a timestamped backup unless it matches the newest one,
+    // then drop the oldest beyond `MAX_BACKUPS`
+    fn backup(&self, content: &str) -> Result<(), String> {
+        fs::create_dir_all(&self.backup_dir)
+            .map_err(|e| format!("Failed to create hosts backup directory: {}", e))?;
+
+        let backups = self.list_backups()?;
+        let newest = backups
+            .last()
+            .and_then(|name| fs::read_to_string(self.backup_dir.join(name)).ok());
+        if newest.as_deref() == Some(content) {
+            return Ok(());
+        }
+
+        let name = format!("hosts-{}.bak", chrono::Local::now().format("%Y%m%dT%H%M%S%.3f"));
+        fs::write(self.backup_dir.join(&name), content)
+            .map_err(|e| format!("Failed to write hosts backup: {}", e))?;
+
+        let backups = self.list_backups()?;
+        let excess = backups.len().saturating_sub(MAX_BACKUPS);
+        for old in &backups[..excess] {
+            let _ = fs::remove_file(self.backup_dir.join(old));
+        }
+        Ok(())
+    }
+
+    // Startup check: clean up temp files from an interrupted write, restore
+    // the newest backup if the hosts file was left empty, and close or drop
+    // unbalanced NEU markers. Returns whether anything was repaired.
+    pub fn self_check(&self) -> Result<bool, String> {
+        let mut applied = self.applied.lock().unwrap();
+        let mut repaired = remove_stale_temp_files(&self.path);
+
+        let current = self.read()?;
+        if current.trim().is_empty() {
+            if let Some(newest) = self.list_backups()?.last() {
+                let content = fs::read_to_string(self.backup_dir.join(newest))
+                    .map_err(|e| format!("Failed to read hosts backup: {}", e))?;
+                if !content.trim().is_empty() {
+                    eprintln!("Hosts file is empty, restoring backup {}", newest);
+                    write_atomically(&self.path, &content)?;
+                    self.remember(&mut applied, block_names(&content));
+                    return Ok(true);
+                }
+            }
+            return Ok(repaired);
+        }
+
+        let fixed = repair_markers(&current);
+        if fixed != current {
+            eprintln!("Hosts file has unbalanced NEU markers, repairing");
+            self.backup(&current)?;
+            write_atomically(&self.path, &fixed)?;
+            repaired = true;
+        }
+
+        Ok(repaired)
+    }
+
+    // Compare the NEU block on disk with what we last wrote
+    pub fn check_tamper(&self) -> Result<Option<HostsTamper>, String> {
+        let applied = self.applied.lock().unwrap();
+        let found = block_names(&self.read()?);
+        if found == *applied {
+            return Ok(None);
+        }
+
+        Ok(Some(HostsTamper {
+            removed: fold_www(&applied.difference(&found).cloned().collect()),
+            added: fold_www(&found.difference(&applied).cloned().collect()),
+        }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn is_marker_start(line: &str) -> bool {
+    line.contains(NEU_MARKER_START)
+}
+
+fn is_marker_end(line: &str) -> bool {
+    line.contains(NEU_MARKER_END)
+}
+
+// `<ip> <host>` lines as written inside the NEU block
+fn is_host_entry(line: &str) -> bool {
+    let mut parts = line.split_whitespace();
+    matches!(
+        (parts.next(), parts.next()),
+        (Some(ip), Some(_)) if ip.parse::<std::net::IpAddr>().is_ok()
+    )
+}
+
+// Make every NEU block well formed: a stray end marker is dropped, nested
+// start markers are merged, and a block cut off before its end marker keeps
+// its host entries and is closed right after them
+pub fn repair_markers(content: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    let mut block: Option<Vec<&str>> = None;
+
+    for line in content.lines() {
+        if is_marker_start(line) {
+            block.get_or_insert_with(Vec::new);
+        } else if is_marker_end(line) {
+            if let Some(entries) = block.take() {
+                out.push(NEU_MARKER_START);
+                out.extend(entries);
+                out.push(NEU_MARKER_END);
+            }
+        } else if let Some(entries) = block.as_mut() {
+            entries.push(line);
+        } else {
+            out.push(line);
+        }
+    }
+
+    if let Some(entries) = block {
+        let kept = entries.iter().take_while(|line| is_host_entry(line)).count();
+        out.push(NEU_MARKER_START);
+        out.extend(&entries[..kept]);
+        out.push(NEU_MARKER_END);
+        out.extend(&entries[kept..]);
+    }
+
+    if out.iter().copied().eq(content.lines()) {
+        return content.to_string();
+    }
+    let mut repaired = out.join("\n");
+    repaired.push('\n');
+    repaired
+}
+
+// Content with the NEU block removed. Expects balanced markers.
+pub fn strip_block(content: &str) -> String {
+    let mut stripped = String::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        if is_marker_start(line) {
+            in_block = true;
+            continue;
+        }
+        if is_marker_end(line) {
+            in_block = false;
+            continue;
+        }
+        if !in_block {
+            stripped.push_str(line);
+            stripped.push('\n');
+        }
+    }
+
+    stripped
+}
+
+// `content` with its NEU block, if any, replaced by one for `domains`
+pub fn with_block(content: &str, domains: &[String]) -> String {
+    render(&strip_block(&repair_markers(content)), domains)
+}
+
+// Append a NEU block for `domains` to `base` (which has no block). Each
+// domain and its `www.` variant get an IPv4 and an IPv6 sinkhole entry, so
+// neither address family reaches the real site.
+pub fn render(base: &str, domains: &[String]) -> String {
+    // Blank lines left before an earlier block would pile up with every write
+    let base = base.trim_end();
+    let mut content = if base.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", base)
+    };
+    let domains: Vec<&str> = domains
+        .iter()
+        .map(|domain| domain.trim())
+        .filter(|domain| !domain.is_empty())
+        .collect();
+    if domains.is_empty() {
+        return content;
+    }
+
+    content.push('\n');
+    content.push_str(NEU_MARKER_START);
+    content.push('\n');
+    for domain in domains {
+        let www = format!("www.{}", domain);
+        let mut names = vec![domain];
+        if !domain.starts_with("www.") {
+            names.push(&www);
+        }
+        for name in names {
+            content.push_str(&format!("{} {}\n", SINKHOLE_V4, name));
+            content.push_str(&format!("{} {}\n", SINKHOLE_V6, name));
+        }
+    }
+    content.push_str(NEU_MARKER_END);
+    content.push('\n');
+    content
+}
+
+// Domains listed inside the NEU block, once each and without the `www.`
+// variants `render` adds. Blocks written by older versions (`127.0.0.1`, IPv4
+// only) are read the same way.
+pub fn block_entries(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut seen = HashSet::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        if is_marker_start(line) {
+            in_block = true;
+            continue;
+        }
+        if is_marker_end(line) {
+            in_block = false;
+            continue;
+        }
+        if in_block && is_host_entry(line) {
+            if let Some(name) = line.split_whitespace().nth(1) {
+                if seen.insert(name.to_string()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+
+    names
+        .into_iter()
+        .filter(|name| {
+            name.strip_prefix("www.")
+                .is_none_or(|bare| !seen.contains(bare))
+        })
+        .collect()
+}
+
+// Every name listed inside the NEU block, `www.` variants included, so
+// deleting just those lines shows up as tampering
+fn block_names(content: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        if is_marker_start(line) {
+            in_block = true;
+        } else if is_marker_end(line) {
+            in_block = false;
+        } else if in_block && is_host_entry(line) {
+            names.extend(line.split_whitespace().skip(1).map(str::to_string));
+        }
+    }
+    names
+}
+
+// `names` without the `www.` variants whose bare domain is listed too
+fn fold_www(names: &BTreeSet<String>) -> Vec<String> {
+    names
+        .iter()
+        .filter(|name| {
+            name.strip_prefix("www.")
+                .is_none_or(|bare| !names.contains(bare))
+        })
+        .cloned()
+        .collect()
+}
+
+// One `<ip> <name>...` line of a hosts file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostsEntry {
+    // 1-based
+    pub line: usize,
+    pub address: std::net::IpAddr,
+    // Lowercased
+    pub names: Vec<String>,
+    pub in_block: bool,
+}
+
+// Every address line in the file, in order, inside the NEU block or not.
+// Comments and lines that don't start with an address are skipped.
+pub fn entries(content: &str) -> Vec<HostsEntry> {
+    let mut entries = Vec::new();
+    let mut in_block = false;
+
+    for (index, line) in content.lines().enumerate() {
+        if is_marker_start(line) {
+            in_block = true;
+            continue;
+        }
+        if is_marker_end(line) {
+            in_block = false;
+            continue;
+        }
+        let line_content = line.split('#').next().unwrap_or_default();
+        let mut parts = line_content.split_whitespace();
+        let Some(Ok(address)) = parts.next().map(str::parse) else {
+            continue;
+        };
+        let names: Vec<String> = parts.map(str::to_lowercase).collect();
+        if !names.is_empty() {
+            entries.push(HostsEntry {
+                line: index + 1,
+                address,
+                names,
+                in_block,
+            });
+        }
+    }
+
+    entries
+}
+
+// Replace `path` without ever exposing a partial file: write a temp file in
+// the same directory, fsync it, copy the original's permissions (and owner on
+// Unix), then rename it over the original
+pub fn write_atomically(path: &Path, content: &str) -> Result<(), String> {
+    let dir = path.parent().ok_or("Hosts file has no parent directory")?;
+    let tmp_path = dir.join(format!("{}{}.tmp", TEMP_PREFIX, std::process::id()));
+    let original = fs::metadata(path).ok();
+
+    let written = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(content.as_bytes())?;
+        if let Some(original) = &original {
+            fs::set_permissions(&tmp_path, original.permissions())?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+                std::os::unix::fs::fchown(&file, Some(original.uid()), Some(original.gid()))?;
+            }
+        }
+        file.sync_all()
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Failed to write hosts file (requires admin): {}", e));
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        // A bind-mounted hosts file (containers) can't be renamed over; fall
+        // back to rewriting it in place
+        if is_mount_point_error(&e) {
+            return write_in_place(path, content);
+        }
+        return Err(format!("Failed to replace hosts file: {}", e));
+    }
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+fn is_mount_point_error(error: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        matches!(error.raw_os_error(), Some(libc::EBUSY) | Some(libc::EXDEV))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+fn write_in_place(path: &Path, content: &str) -> Result<(), String> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open hosts file for writing (requires admin): {}", e))?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write to hosts file: {}", e))
+}
+
+// Temp files left behind by a write that never reached the rename
+fn remove_stale_temp_files(path: &Path) -> bool {
+    let Some(dir) = path.parent() else {
+        return false;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+
+    let mut removed = false;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(TEMP_PREFIX) && name.ends_with(".tmp") {
+            removed |= fs::remove_file(entry.path()).is_ok();
+        }
+    }
+    removed
+}
+
+#[tauri::command]
+pub async fn list_hosts_backups(app: AppHandle) -> Result<Vec<String>, String> {
+    blocking(app, |app| {
+        // A running helper writes the hosts file, so the backups are its own
+        if let Some(helper) = HelperClient::connect() {
+            return helper.list_hosts_backups();
+        }
+        app.state::<HostsFile>().list_backups()
+    })
+    .await
+}
+
+// Put a backed-up hosts file back, the newest one unless `name` is given.
+// Only the lines outside the NEU block come from the backup; the block is
+// written for the rules active now, so a restore never lifts a block.
+#[tauri::command]
+pub async fn restore_hosts_backup(name: Option<String>, app: AppHandle) -> Result<String, String> {
+    blocking(app, move |app| {
+        let now = chrono::Local::now();
+        let domains = app.state::<Store>().read(|data| match data.settings.website_block_backend {
+            BackendKind::Hosts => website_backend::required_domains(data, &now),
+            _ => Vec::new(),
+        });
+        let hosts = app.state::<HostsFile>();
+        if let Some(helper) = HelperClient::connect() {
+            // The helper's write isn't tampering
+            hosts.expect_domains(&domains);
+            let restored = helper.restore_hosts_backup(name, domains);
+            if restored.is_err() {
+                let _ = hosts.resync();
+            }
+            return restored;
+        }
+        hosts.restore_backup(name.as_deref(), &domains)
+    })
+    .await
+}
+
+// Backups and restores may go through the helper's socket; keep them off the
+// async runtime
+async fn blocking<T: Send + 'static>(
+    app: AppHandle,
+    f: impl FnOnce(&AppHandle) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(move || f(&app))
+        .await
+        .map_err(|e| format!("Hosts backup task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domains(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn block_names_keep_www_variants() {
+        let content = render("127.0.0.1 localhost\n", &domains(&["example.com"]));
+        let names: Vec<String> = block_names(&content).into_iter().collect();
+        assert_eq!(names, ["example.com", "www.example.com"]);
+        assert_eq!(block_entries(&content), ["example.com"]);
+    }
+
+    #[test]
+    fn deleting_only_www_lines_changes_the_names() {
+        let content = render("", &domains(&["example.com", "example.org"]));
+        let edited: String = content
+            .lines()
+            .filter(|line| !line.ends_with(" www.example.com"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+
+        let applied = block_names(&content);
+        let found = block_names(&edited);
+        assert_ne!(applied, found);
+        let removed: BTreeSet<String> = applied.difference(&found).cloned().collect();
+        assert_eq!(fold_www(&removed), ["www.example.com"]);
+    }
+
+    #[test]
+    fn fold_www_drops_variants_of_listed_domains() {
+        let names: BTreeSet<String> = domains(&["a.com", "www.a.com", "www.b.com"]).into_iter().collect();
+        assert_eq!(fold_www(&names), ["a.com", "www.b.com"]);
+    }
+
+    #[test]
+    fn restored_backup_gets_the_current_block() {
+        // Backed up before reddit.com was blocked, with an old block for news.com
+        let backup = render("127.0.0.1 localhost\n10.0.0.2 nas\n", &domains(&["news.com"]));
+        let restored = with_block(&backup, &domains(&["reddit.com"]));
+        assert_eq!(block_entries(&restored), ["reddit.com"]);
+        assert_eq!(strip_block(&restored), "127.0.0.1 localhost\n10.0.0.2 nas\n\n");
+        assert_eq!(with_block(&restored, &domains(&["reddit.com"])), restored);
+    }
+
+    #[test]
+    fn balanced_content_is_left_alone() {
+        let content = render("127.0.0.1 localhost\n", &domains(&["example.com"]));
+        assert_eq!(repair_markers(&content), content);
+        // Including the lack of a trailing newline
+        assert_eq!(repair_markers("127.0.0.1 localhost"), "127.0.0.1 localhost");
+    }
+
+    #[test]
+    fn missing_end_marker_closes_after_the_host_entries() {
+        let content = format!(
+            "127.0.0.1 localhost\n{}\n0.0.0.0 example.com\n:: example.com\n# added by hand\n10.0.0.2 nas\n",
+            NEU_MARKER_START
+        );
+        let expected = format!(
+            "127.0.0.1 localhost\n{}\n0.0.0.0 example.com\n:: example.com\n{}\n# added by hand\n10.0.0.2 nas\n",
+            NEU_MARKER_START, NEU_MARKER_END
+        );
+        let repaired = repair_markers(&content);
+        assert_eq!(repaired, expected);
+        assert_eq!(block_entries(&repaired), ["example.com"]);
+        assert_eq!(strip_block(&repaired), "127.0.0.1 localhost\n# added by hand\n10.0.0.2 nas\n");
+    }
+
+    #[test]
+    fn missing_start_marker_drops_the_stray_end_marker() {
+        let content = format!("127.0.0.1 localhost\n0.0.0.0 example.com\n{}\n10.0.0.2 nas\n", NEU_MARKER_END);
+        assert_eq!(
+            repair_markers(&content),
+            "127.0.0.1 localhost\n0.0.0.0 example.com\n10.0.0.2 nas\n"
+        );
+    }
+
+    #[test]
+    fn nested_start_markers_are_merged() {
+        let content = format!(
+            "{start}\n0.0.0.0 a.com\n{start}\n0.0.0.0 b.com\n{end}\n",
+            start = NEU_MARKER_START,
+            end = NEU_MARKER_END
+        );
+        let repaired = repair_markers(&content);
+        assert_eq!(
+            repaired,
+            format!("{}\n0.0.0.0 a.com\n0.0.0.0 b.com\n{}\n", NEU_MARKER_START, NEU_MARKER_END)
+        );
+        assert_eq!(repair_markers(&repaired), repaired);
+    }
+}
@@ -0,0 +1,162 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::hosts::{HostsFile, HostsTamper};
+use crate::store::Store;
+use crate::website_backend::{self, BackendKind};
+use crate::WebhookMessage;
+
+// Editors and package managers often write in several steps; wait for the
+// burst of events to settle before reading the file
+const SETTLE_DELAY: Duration = Duration::from_millis(250);
+
+// Watch the hosts file for edits to the NEU block. When someone else changes
+// it, put back the domains of the active website rules, log a `violation`
+// event and, if enabled, alert the webhook with what was removed.
+//
+// The block is first compared with what was written before the app was
+// closed. That happens here, before the website blocker applies the active
+// rules, so the apply doesn't hide edits made in the meantime.
+pub fn start(app: AppHandle) {
+    let at_startup = match website_backend::selected_kind(&app) {
+        BackendKind::Hosts => app.state::<HostsFile>().check_tamper().unwrap_or_else(|e| {
+            eprintln!("Failed to check hosts file: {}", e);
+            None
+        }),
+        _ => None,
+    };
+
+    std::thread::Builder::new()
+        .name("neu-hosts-watch".to_string())
+        .spawn(move || {
+            if let Some(tamper) = &at_startup {
+                handle_tamper(&app, tamper);
+            }
+            if let Err(e) = watch(&app) {
+                eprintln!("Hosts file watcher stopped: {}", e);
+            }
+        })
+        .expect("failed to spawn hosts watch thread");
+}
+
+fn watch(app: &AppHandle) -> Result<(), String> {
+    let hosts_path = app.state::<HostsFile>().path().to_path_buf();
+    // Watch the directory: the file itself is replaced on every atomic write
+    let dir = hosts_path
+        .parent()
+        .ok_or("Hosts file has no parent directory")?
+        .to_path_buf();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+
+    let mut last_reported: Option<HostsTamper> = None;
+
+    loop {
+        let event = match rx.recv() {
+            Ok(Ok(event)) => event,
+            Ok(Err(e)) => {
+                eprintln!("Hosts file watch error: {}", e);
+                continue;
+            }
+            Err(_) => return Err("watcher channel closed".to_string()),
+        };
+        if matches!(event.kind, EventKind::Access(_)) || !event.paths.contains(&hosts_path) {
+            continue;
+        }
+
+        std::thread::sleep(SETTLE_DELAY);
+        while rx.try_recv().is_ok() {}
+
+        // Another backend is in charge of website blocks
+        if website_backend::selected_kind(app) != BackendKind::Hosts {
+            last_reported = None;
+            continue;
+        }
+
+        match app.state::<HostsFile>().check_tamper() {
+            Ok(Some(tamper)) => {
+                // A failed re-apply leaves the same difference; report it once
+                if last_reported.as_ref() != Some(&tamper) {
+                    handle_tamper(app, &tamper);
+                }
+                last_reported = Some(tamper);
+            }
+            Ok(None) => last_reported = None,
+            Err(e) => eprintln!("Failed to check hosts file: {}", e),
+        }
+    }
+}
+
+fn handle_tamper(app: &AppHandle, tamper: &HostsTamper) {
+    let now = chrono::Local::now();
+    let expected = app
+        .state::<Store>()
+        .read(|data| website_backend::required_domains(data, &now));
+    let reapplied = website_backend::apply(app, &expected).map(|_| ());
+
+    let mut message = "NEU section of the hosts file was modified externally".to_string();
+    if !tamper.removed.is_empty() {
+        message.push_str(&format!("; removed: {}", tamper.removed.join(", ")));
+    }
+    if !tamper.added.is_empty() {
+        message.push_str(&format!("; added: {}", tamper.added.join(", ")));
+    }
+    match &reapplied {
+        Ok(()) => message.push_str(&format!(". Re-applied {} blocked domain(s).", expected.len())),
+        Err(e) => message.push_str(&format!(". Re-applying failed: {}", e)),
+    }
+
+    let store = app.state::<Store>();
+    if let Err(e) = store.log_event("violation", "Hosts file", message) {
+        eprintln!("Failed to log hosts tamper event: {}", e);
+    }
+    let _ = app.emit("hosts-tampered", tamper.clone());
+
+    let settings = store.read(|data| data.settings.clone());
+    let webhook_url = settings.webhook_url.filter(|url| !url.is_empty());
+    if let (true, true, Some(url)) = (
+        settings.webhook_enabled,
+        settings.send_tamper_notifications,
+        webhook_url,
+    ) {
+        let content = webhook_diff(tamper, reapplied.is_ok());
+        tauri::async_runtime::spawn(async move {
+            let client = reqwest::Client::new();
+            if let Err(e) = client
+                .post(&url)
+                .json(&WebhookMessage { content })
+                .send()
+                .await
+            {
+                eprintln!("Failed to send hosts tamper webhook: {}", e);
+            }
+        });
+    }
+}
+
+fn webhook_diff(tamper: &HostsTamper, reapplied: bool) -> String {
+    let mut content = "⚠️ **Hosts File Tampered**\n\nThe NEU block list in the hosts file was edited outside the app.\n".to_string();
+    if !tamper.removed.is_empty() || !tamper.added.is_empty() {
+        content.push_str("\n```diff\n");
+        for domain in &tamper.removed {
+            content.push_str(&format!("- {}\n", domain));
+        }
+        for domain in &tamper.added {
+            content.push_str(&format!("+ {}\n", domain));
+        }
+        content.push_str("```\n");
+    }
+    content.push_str(if reapplied {
+        "\n_The blocks have been re-applied._"
+    } else {
+        "\n_Re-applying the blocks failed._"
+    });
+    content
+}
@@ -1,15 +1,57 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use sysinfo::{ProcessRefreshKind, System, ProcessesToUpdate};
 use tauri::{State, Manager, Emitter, menu::{Menu, MenuItem}};
 use tauri::tray::{TrayIconBuilder, TrayIconEvent};
 
+mod block_page;
+mod blocklist;
+mod browser_policy;
+#[cfg(target_os = "linux")]
+mod desktop_apps;
+mod dns_sinkhole;
+mod doh;
+mod domain;
+mod enforcement;
+mod helper;
+mod hosts;
+mod hosts_watch;
+mod loopback_service;
+mod matcher;
+mod native_host;
+mod process_monitor;
+mod process_watch;
+mod rules;
+mod sni_proxy;
+mod steam;
+mod store;
+mod termination;
+mod url_pattern;
+mod vdf;
+mod website_backend;
+
+use block_page::BlockPage;
+use browser_policy::BrowserPolicies;
+use dns_sinkhole::DnsSinkhole;
+use enforcement::EnforcementEngine;
+use hosts::HostsFile;
+use matcher::{HashCache, ProcessMatcher, RuleMatch};
+pub use helper::run as run_helper;
+pub use native_host::run as run_native_host;
+pub use process_monitor::{ProcessInfo, ProcessMonitor};
+use sni_proxy::SniProxy;
+use store::Store;
+use termination::TerminationResult;
+use website_backend::WebsiteBlocker;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppInfo {
     pub name: String,
     pub path: String,
     pub pid: Option<u32>,
+    // Icon as a data URL, when the platform exposes one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,105 +59,21 @@ pub struct WebhookMessage {
     pub content: String,
 }
 
-// State to track blocked apps
-pub struct BlockedApps(Arc<Mutex<HashMap<String, bool>>>);
-
 // State to track webhook rate limiting with message deduplication
 // Key: (webhook_url, message_hash) -> last_send_timestamp
 pub struct WebhookRateLimiter(Arc<Mutex<HashMap<(String, String), std::time::Instant>>>);
 
-// Process cache to avoid redundant scans
-pub struct ProcessCache {
-    cache: Arc<Mutex<Option<(Vec<AppInfo>, std::time::Instant)>>>,
-    ttl: std::time::Duration,
-}
-
-impl ProcessCache {
-    fn new(ttl_seconds: u64) -> Self {
-        Self {
-            cache: Arc::new(Mutex::new(None)),
-            ttl: std::time::Duration::from_secs(ttl_seconds),
-        }
-    }
-    
-    fn get_or_refresh(&self) -> Result<Vec<AppInfo>, String> {
-        use std::time::Instant;
-        
-        let mut cache = self.cache.lock().unwrap();
-        let now = Instant::now();
-        
-        // Check if cache is valid
-        if let Some((cached_processes, cached_time)) = cache.as_ref() {
-            if now.duration_since(*cached_time) < self.ttl {
-                return Ok(cached_processes.clone());
-            }
-        }
-        
-        // Cache expired or doesn't exist, refresh
-        let processes = fetch_running_processes()?;
-        *cache = Some((processes.clone(), now));
-        Ok(processes)
-    }
-    
-    fn invalidate(&self) {
-        let mut cache = self.cache.lock().unwrap();
-        *cache = None;
-    }
-}
-
-// Extract the actual process fetching logic
-fn fetch_running_processes() -> Result<Vec<AppInfo>, String> {
-    let mut sys = System::new_all();
-    sys.refresh_processes_specifics(
-        ProcessesToUpdate::All,
-        true,
-        ProcessRefreshKind::everything(),
-    );
-    
-    let mut apps: Vec<AppInfo> = sys
-        .processes()
-        .iter()
-        .filter_map(|(pid, process)| {
-            let path = process
-                .exe()
-                .unwrap_or_else(|| std::path::Path::new(""))
-                .to_string_lossy()
-                .to_string();
-            
-            // Only include processes with valid executable paths
-            if path.is_empty() {
-                return None;
-            }
-            
-            Some(AppInfo {
-                name: process.name().to_string_lossy().to_string(),
-                path,
-                pid: Some(pid.as_u32()),
-            })
-        })
-        .collect();
-    
-    // Sort by path (more reliable than name)
-    apps.sort_by(|a, b| a.path.to_lowercase().cmp(&b.path.to_lowercase()));
-    
-    // Deduplicate by path (not name, since same path = same executable)
-    apps.dedup_by(|a, b| a.path.to_lowercase() == b.path.to_lowercase());
-    
-    Ok(apps)
-}
-
-#[tauri::command]
-async fn get_running_processes(cache: State<'_, ProcessCache>) -> Result<Vec<AppInfo>, String> {
-    cache.get_or_refresh()
-}
-
 #[tauri::command]
-async fn invalidate_process_cache(cache: State<'_, ProcessCache>) -> Result<(), String> {
-    cache.invalidate();
-    Ok(())
+async fn get_running_processes(monitor: State<'_, ProcessMonitor>) -> Result<Vec<AppInfo>, String> {
+    // A process scan blocks for a while, so it stays off the async runtime
+    let monitor = monitor.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || monitor.running_apps())
+        .await
+        .map_err(|e| format!("Failed to list processes: {}", e))
 }
 
 // Helper function to scan a directory for executables (non-recursive for performance)
+#[cfg(target_os = "windows")]
 fn scan_directory_for_exes(dir: &str, max_depth: usize) -> Vec<AppInfo> {
     use std::fs;
     use std::path::Path;
@@ -156,6 +114,7 @@ fn scan_directory_for_exes(dir: &str, max_depth: usize) -> Vec<AppInfo> {
                                         name,
                                         path: path_str,
                                         pid: None,
+                                        icon: None,
                                     });
                                 }
                             }
@@ -173,47 +132,9 @@ fn scan_directory_for_exes(dir: &str, max_depth: usize) -> Vec<AppInfo> {
     apps
 }
 
-// Helper function to find Steam library folders
-fn get_steam_library_paths() -> Vec<String> {
-    use std::fs;
-    use std::path::Path;
-    
-    let mut paths = Vec::new();
-    
-    // Default Steam installation path
-    let default_steam_path = "C:\\Program Files (x86)\\Steam\\steamapps\\common";
-    if Path::new(default_steam_path).exists() {
-        paths.push(default_steam_path.to_string());
-    }
-    
-    // Try to read libraryfolders.vdf to find additional Steam library locations
-    let library_folders_path = "C:\\Program Files (x86)\\Steam\\steamapps\\libraryfolders.vdf";
-    if let Ok(content) = fs::read_to_string(library_folders_path) {
-        // Parse the VDF file to find library paths
-        for line in content.lines() {
-            if line.contains("\"path\"") {
-                // Extract path from line like: "path"		"C:\\SteamLibrary"
-                if let Some(start) = line.rfind('"') {
-                    if let Some(end) = line[..start].rfind('"') {
-                        let path = &line[end + 1..start];
-                        // Unescape the path (replace \\ with \)
-                        let unescaped = path.replace("\\\\", "\\");
-                        let steamapps_common = format!("{}\\steamapps\\common", unescaped);
-                        if Path::new(&steamapps_common).exists() {
-                            paths.push(steamapps_common);
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    paths
-}
-
 #[tauri::command]
 async fn get_installed_apps() -> Result<Vec<AppInfo>, String> {
-    let mut apps = Vec::new();
+    let mut apps: Vec<AppInfo> = Vec::new();
     
     #[cfg(target_os = "windows")]
     {
@@ -244,11 +165,6 @@ async fn get_installed_apps() -> Result<Vec<AppInfo>, String> {
             }
         }
         
-        // 2. Scan Steam library folders
-        for steam_path in get_steam_library_paths() {
-            apps.extend(scan_directory_for_exes(&steam_path, 1)); // Only scan game folders, not deep
-        }
-        
         // Helper function to query registry value
         let query_reg_string = |hkey, key_name: &str| -> Option<String> {
             unsafe {
@@ -273,7 +189,7 @@ async fn get_installed_apps() -> Result<Vec<AppInfo>, String> {
             None
         };
         
-        // 3. Check Windows Registry for installed applications
+        // 2. Check Windows Registry for installed applications
         unsafe {
             let registry_roots = vec![
                 (HKEY_LOCAL_MACHINE, vec![
@@ -384,6 +300,7 @@ async fn get_installed_apps() -> Result<Vec<AppInfo>, String> {
                                             name: display_name,
                                             path: exe_path,
                                             pid: None,
+                                            icon: None,
                                         });
                                     }
                                 }
@@ -401,6 +318,15 @@ async fn get_installed_apps() -> Result<Vec<AppInfo>, String> {
         }
     }
     
+    #[cfg(target_os = "linux")]
+    {
+        // XDG desktop entries: menus, Flatpak and Snap exports
+        apps.extend(desktop_apps::installed_apps());
+    }
+    
+    // Games from every Steam library, named as they are in Steam
+    apps.extend(steam::installed_apps());
+    
     // Sort and deduplicate by executable path (not name)
     // This prevents duplicate entries for the same application
     apps.sort_by(|a, b| a.path.to_lowercase().cmp(&b.path.to_lowercase()));
@@ -445,86 +371,83 @@ async fn browse_for_executable(app: tauri::AppHandle) -> Result<Option<AppInfo>,
             name,
             path: path_str,
             pid: None,
+            icon: None,
         }))
     } else {
         Ok(None)
     }
 }
 
+// Gracefully terminate a process (SIGTERM, then SIGKILL after the grace period)
 #[tauri::command]
-async fn kill_process(pid: u32) -> Result<bool, String> {
-    #[cfg(target_os = "windows")]
-    {
-        use windows::Win32::System::Threading::{
-            OpenProcess, TerminateProcess, PROCESS_TERMINATE,
-        };
-        
-        unsafe {
-            let handle = OpenProcess(PROCESS_TERMINATE, false, pid);
-            if let Ok(handle) = handle {
-                if handle.is_invalid() {
-                    return Err("Failed to open process".to_string());
-                }
-                
-                let result = TerminateProcess(handle, 1);
-                let _ = windows::Win32::Foundation::CloseHandle(handle);
-                
-                if result.is_ok() {
-                    return Ok(true);
-                }
-            }
-        }
-    }
-    
-    #[cfg(not(target_os = "windows"))]
-    {
-        use std::process::Command;
-        let output = Command::new("kill")
-            .arg("-9")
-            .arg(pid.to_string())
-            .output();
-        
-        if let Ok(output) = output {
-            return Ok(output.status.success());
-        }
-    }
-    
-    Err("Failed to kill process".to_string())
-}
-
-#[tauri::command]
-async fn block_application(
-    app_name: String,
-    blocked_apps: State<'_, BlockedApps>,
-) -> Result<bool, String> {
-    let mut apps = blocked_apps.0.lock().unwrap();
-    apps.insert(app_name.to_lowercase(), true);
-    Ok(true)
+async fn kill_process(pid: u32, grace_period_ms: Option<u64>) -> Result<TerminationResult, String> {
+    let grace_period = grace_period_ms
+        .map(std::time::Duration::from_millis)
+        .unwrap_or(termination::DEFAULT_GRACE_PERIOD);
+    Ok(termination::terminate_process(pid, grace_period).await)
 }
 
+// Terminate a process and all of its descendants, children first. Returns the
+// result for every PID acted on.
 #[tauri::command]
-async fn unblock_application(
-    app_name: String,
-    blocked_apps: State<'_, BlockedApps>,
-) -> Result<bool, String> {
-    let mut apps = blocked_apps.0.lock().unwrap();
-    apps.remove(&app_name.to_lowercase());
-    Ok(true)
+async fn kill_process_tree(
+    pid: u32,
+    grace_period_ms: Option<u64>,
+    monitor: State<'_, ProcessMonitor>,
+) -> Result<Vec<TerminationResult>, String> {
+    let grace_period = grace_period_ms
+        .map(std::time::Duration::from_millis)
+        .unwrap_or(termination::DEFAULT_GRACE_PERIOD);
+    let monitor = monitor.inner().clone();
+    let processes = tauri::async_runtime::spawn_blocking(move || monitor.process_details())
+        .await
+        .map_err(|e| format!("Failed to list processes: {}", e))?;
+    Ok(termination::terminate_tree(pid, &processes, grace_period).await)
 }
 
+// Check an app against the active block rules. With a `pid` the live process
+// (command line and parent) is matched; otherwise just the name and path.
 #[tauri::command]
 async fn is_app_blocked(
     app_name: String,
-    blocked_apps: State<'_, BlockedApps>,
-) -> Result<bool, String> {
-    let apps = blocked_apps.0.lock().unwrap();
-    Ok(apps.contains_key(&app_name.to_lowercase()))
+    app_path: Option<String>,
+    pid: Option<u32>,
+    store: State<'_, Store>,
+    hashes: State<'_, HashCache>,
+    monitor: State<'_, ProcessMonitor>,
+) -> Result<Option<RuleMatch>, String> {
+    let now = chrono::Local::now();
+    let matcher = store.read(|data| ProcessMatcher::compile(&rules::active_at(&data.block_rules, &now)));
+
+    // Matching may hash the executable, so it stays off the async runtime
+    let monitor = monitor.inner().clone();
+    let hashes = hashes.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let process = pid
+            .and_then(|pid| monitor.process_details_for(&[pid]).pop())
+            .unwrap_or(ProcessInfo {
+                pid: pid.unwrap_or(0),
+                name: app_name,
+                path: app_path.unwrap_or_default(),
+                cmd: String::new(),
+                parent_pid: None,
+                parent_name: None,
+            });
+        matcher.find_match(&process, &hashes)
+    })
+    .await
+    .map_err(|e| format!("Failed to match app: {}", e))
 }
 
 #[tauri::command]
-async fn get_blocked_apps(blocked_apps: State<'_, BlockedApps>) -> Result<Vec<String>, String> {
-    let apps = blocked_apps.0.lock().unwrap();
-    Ok(apps.keys().cloned().collect())
+async fn get_blocked_apps(store: State<'_, Store>) -> Result<Vec<String>, String> {
+    let now = chrono::Local::now();
+    Ok(store.read(|data| {
+        rules::active_at(&data.block_rules, &now)
+            .into_iter()
+            .map(|rule| rule.app_name)
+            .collect()
+    }))
 }
 
 #[tauri::command]
@@ -606,7 +529,7 @@ async fn send_discord_webhook(
 }
 
 #[tauri::command]
-async fn get_browser_processes() -> Result<Vec<AppInfo>, String> {
+async fn get_browser_processes(monitor: State<'_, ProcessMonitor>) -> Result<Vec<AppInfo>, String> {
     let browser_names = vec![
         "chrome.exe",
         "firefox.exe",
@@ -625,28 +548,21 @@ async fn get_browser_processes() -> Result<Vec<AppInfo>, String> {
         "yandex.exe",        // Yandex Browser
     ];
     
-    let mut sys = System::new_all();
-    sys.refresh_processes_specifics(
-        ProcessesToUpdate::All,
-        true,
-        ProcessRefreshKind::everything(),
-    );
-    
-    let browsers: Vec<AppInfo> = sys
-        .processes()
-        .iter()
-        .filter(|(_, process)| {
-            let name = process.name().to_string_lossy().to_lowercase();
+    let monitor = monitor.inner().clone();
+    let processes = tauri::async_runtime::spawn_blocking(move || monitor.process_details())
+        .await
+        .map_err(|e| format!("Failed to list processes: {}", e))?;
+    let browsers: Vec<AppInfo> = processes
+        .into_iter()
+        .filter(|process| {
+            let name = process.name.to_lowercase();
             browser_names.iter().any(|b| name.contains(b))
         })
-        .map(|(pid, process)| AppInfo {
-            name: process.name().to_string_lossy().to_string(),
-            path: process
-                .exe()
-                .unwrap_or_else(|| std::path::Path::new(""))
-                .to_string_lossy()
-                .to_string(),
-            pid: Some(pid.as_u32()),
+        .map(|process| AppInfo {
+            name: process.name,
+            path: process.path,
+            pid: Some(process.pid),
+            icon: None,
         })
         .collect();
     
@@ -684,128 +600,6 @@ fn hash_pin(pin: String) -> Result<String, String> {
     Ok(password_hash.to_string())
 }
 
-// Website blocking via hosts file modification
-#[cfg(target_os = "windows")]
-const HOSTS_FILE_PATH: &str = "C:\\Windows\\System32\\drivers\\etc\\hosts";
-
-#[cfg(not(target_os = "windows"))]
-const HOSTS_FILE_PATH: &str = "/etc/hosts";
-
-const NEU_MARKER_START: &str = "# NEU_BLOCK_START - Do not edit this section manually";
-const NEU_MARKER_END: &str = "# NEU_BLOCK_END";
-
-#[tauri::command]
-async fn apply_website_blocks(domains: Vec<String>) -> Result<(), String> {
-    use std::fs::{self, OpenOptions};
-    use std::io::{Read, Write};
-
-    // Read existing hosts file
-    let mut hosts_content = String::new();
-    if let Ok(mut file) = fs::File::open(HOSTS_FILE_PATH) {
-        file.read_to_string(&mut hosts_content)
-            .map_err(|e| format!("Failed to read hosts file: {}", e))?;
-    }
-
-    // Remove existing NEU block if present
-    let mut new_content = String::new();
-    let mut skip_neu_block = false;
-    
-    for line in hosts_content.lines() {
-        if line.contains(NEU_MARKER_START) {
-            skip_neu_block = true;
-            continue;
-        }
-        if line.contains(NEU_MARKER_END) {
-            skip_neu_block = false;
-            continue;
-        }
-        if !skip_neu_block {
-            new_content.push_str(line);
-            new_content.push('\n');
-        }
-    }
-
-    // Add NEU block with new domains if any domains provided
-    if !domains.is_empty() {
-        new_content.push_str("\n");
-        new_content.push_str(NEU_MARKER_START);
-        new_content.push('\n');
-        
-        for domain in domains {
-            let domain = domain.trim();
-            if !domain.is_empty() {
-                new_content.push_str(&format!("127.0.0.1 {}\n", domain));
-                new_content.push_str(&format!("127.0.0.1 www.{}\n", domain));
-            }
-        }
-        
-        new_content.push_str(NEU_MARKER_END);
-        new_content.push('\n');
-    }
-
-    // Write back to hosts file (requires admin privileges)
-    let mut file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(HOSTS_FILE_PATH)
-        .map_err(|e| format!("Failed to open hosts file for writing (requires admin): {}", e))?;
-
-    file.write_all(new_content.as_bytes())
-        .map_err(|e| format!("Failed to write to hosts file: {}", e))?;
-
-    // Flush DNS cache on Windows
-    #[cfg(target_os = "windows")]
-    {
-        use std::process::Command;
-        let _ = Command::new("ipconfig")
-            .args(["/flushdns"])
-            .output();
-    }
-
-    Ok(())
-}
-
-#[tauri::command]
-async fn remove_website_blocks() -> Result<(), String> {
-    apply_website_blocks(vec![]).await
-}
-
-#[tauri::command]
-async fn get_blocked_domains() -> Result<Vec<String>, String> {
-    use std::fs;
-    use std::io::Read;
-
-    let mut hosts_content = String::new();
-    if let Ok(mut file) = fs::File::open(HOSTS_FILE_PATH) {
-        file.read_to_string(&mut hosts_content)
-            .map_err(|e| format!("Failed to read hosts file: {}", e))?;
-    }
-
-    let mut domains = Vec::new();
-    let mut in_neu_block = false;
-
-    for line in hosts_content.lines() {
-        if line.contains(NEU_MARKER_START) {
-            in_neu_block = true;
-            continue;
-        }
-        if line.contains(NEU_MARKER_END) {
-            in_neu_block = false;
-            continue;
-        }
-        if in_neu_block && line.trim().starts_with("127.0.0.1") {
-            if let Some(domain) = line.split_whitespace().nth(1) {
-                // Skip www. variants to avoid duplicates
-                if !domain.starts_with("www.") {
-                    domains.push(domain.to_string());
-                }
-            }
-        }
-    }
-
-    Ok(domains)
-}
-
 #[tauri::command]
 async fn notify_app_closing(webhook_url: Option<String>) -> Result<(), String> {
     if let Some(url) = webhook_url {
@@ -830,10 +624,51 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_dialog::init())
-        .manage(BlockedApps(Arc::new(Mutex::new(HashMap::new()))))
+        .manage(HashCache::new())
         .manage(WebhookRateLimiter(Arc::new(Mutex::new(HashMap::new()))))
-        .manage(ProcessCache::new(2)) // 2-second TTL for process cache
+        .manage(ProcessMonitor::new())
+        .manage(EnforcementEngine::new())
+        .manage(DnsSinkhole::new())
+        .manage(BlockPage::new())
+        .manage(BrowserPolicies::new())
+        .manage(SniProxy::new())
+        .manage(WebsiteBlocker::new())
         .setup(|app| {
+            // Load the persistent store from the app data dir
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(Store::load(data_dir.join(store::STORE_FILE_NAME))?);
+
+            // Repair a hosts file left half-written or with unbalanced markers
+            let hosts = HostsFile::new(data_dir.join(hosts::BACKUP_DIR_NAME));
+            if let Err(e) = hosts.self_check() {
+                eprintln!("Hosts file self-check failed: {}", e);
+            }
+            app.manage(hosts);
+            // Reports edits made while the app was closed, then keeps watching
+            hosts_watch::start(app.handle().clone());
+
+            // Apply the active website rules and follow them as they change
+            app.state::<WebsiteBlocker>().start(app.handle().clone());
+
+            // Optional local DNS resolver and block page; both follow the settings
+            // on their own
+            app.state::<DnsSinkhole>().start(app.handle().clone());
+            app.state::<BlockPage>().start(app.handle().clone());
+
+            // Website and URL rules as browser enterprise policies, if enabled
+            app.state::<BrowserPolicies>().start(app.handle().clone());
+
+            // Optional local proxy refusing blocked sites by host and TLS SNI
+            app.state::<SniProxy>().start(app.handle().clone());
+
+            // Pick up visits reported by the browser extension's native host
+            native_host::start(app.handle().clone());
+
+            // Start the Rust-side enforcement loop, fed by the process launch watcher
+            let (launch_tx, launch_rx) = tokio::sync::mpsc::unbounded_channel();
+            process_watch::start(launch_tx);
+            app.state::<EnforcementEngine>().start(app.handle().clone(), launch_rx);
+
             // Create system tray
             let quit = MenuItem::with_id(app, "quit", "Quit NEU", true, None::<&str>)?;
             let show = MenuItem::with_id(app, "show", "Show Window", true, None::<&str>)?;
@@ -908,22 +743,53 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             get_running_processes,
-            invalidate_process_cache,
             get_installed_apps,
             browse_for_executable,
             kill_process,
-            block_application,
-            unblock_application,
+            kill_process_tree,
             is_app_blocked,
             get_blocked_apps,
             send_discord_webhook,
             get_browser_processes,
             verify_pin,
             hash_pin,
-            apply_website_blocks,
-            remove_website_blocks,
-            get_blocked_domains,
+            website_backend::apply_website_blocks,
+            website_backend::remove_website_blocks,
+            website_backend::get_blocked_domains,
+            website_backend::get_website_block_backends,
+            website_backend::website_block_status,
+            hosts::list_hosts_backups,
+            hosts::restore_hosts_backup,
+            dns_sinkhole::get_dns_sinkhole_status,
+            block_page::get_block_page_status,
+            doh::set_doh_policies,
+            browser_policy::get_browser_policy_status,
+            sni_proxy::get_sni_proxy_status,
+            sni_proxy::set_system_proxy,
             notify_app_closing,
+            enforcement::set_enforcement_enabled,
+            enforcement::get_enforcement_status,
+            rules::get_active_rules,
+            store::get_rules,
+            store::save_rule,
+            store::delete_rule,
+            store::get_website_rules,
+            store::save_website_rule,
+            store::delete_website_rule,
+            blocklist::browse_for_blocklist,
+            blocklist::import_blocklist,
+            store::get_url_rules,
+            store::save_url_rule,
+            store::delete_url_rule,
+            store::get_allowlist_rules,
+            store::save_allowlist_rule,
+            store::delete_allowlist_rule,
+            store::get_settings,
+            store::save_settings,
+            store::get_events,
+            store::append_event,
+            store::clear_events,
+            store::migrate_local_storage,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
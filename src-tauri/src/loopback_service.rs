@@ -0,0 +1,251 @@
+// Shared plumbing of the optional servers on a local address (block page,
+// DNS sinkhole, SNI proxy): a refresh loop that starts, stops or moves the
+// server to follow the settings, the last error for the status commands, and
+// a per-host throttle for the events they log. Ports below 1024 need root on
+// Linux; those sockets come from the privileged helper.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::AppHandle;
+use tokio::net::{TcpListener, UdpSocket};
+
+use crate::helper::SocketKind;
+
+// A service with a refresh loop. `refresh` re-reads the settings and calls
+// `LoopbackServer::reconcile`.
+pub trait Refresh: Clone + Send + Sync + 'static {
+    fn refresh(&self, app: &AppHandle) -> impl Future<Output = ()> + Send;
+}
+
+// Spawn the refresh loop of `service`. Called once from the `setup` hook.
+pub fn start<S: Refresh>(service: &S, app: AppHandle, interval: Duration) {
+    let service = service.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            service.refresh(&app).await;
+        }
+    });
+}
+
+struct Running<C> {
+    listen: SocketAddr,
+    config: C,
+    task: JoinHandle<()>,
+}
+
+// The server task of one service and why it isn't running. `C` is whatever
+// else restarts the server when it changes (the sinkhole's upstream).
+#[derive(Clone)]
+pub struct LoopbackServer<C> {
+    // Prefix of logged errors, e.g. "Block page"
+    name: &'static str,
+    running: Arc<tokio::sync::Mutex<Option<Running<C>>>>,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl<C: Clone + PartialEq + Send + 'static> LoopbackServer<C> {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            running: Arc::new(tokio::sync::Mutex::new(None)),
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Make the server match `desired`: `Ok(None)` stops it, an error (bad
+    // settings) stops it and is reported. A changed address or config
+    // restarts it through `serve`, which binds and spawns the server task.
+    // A failed bind is retried on the next refresh.
+    pub async fn reconcile<F, Fut>(&self, desired: Result<Option<(SocketAddr, C)>, String>, serve: F)
+    where
+        F: FnOnce(SocketAddr, C) -> Fut,
+        Fut: Future<Output = io::Result<JoinHandle<()>>>,
+    {
+        let desired = match desired {
+            Ok(desired) => {
+                if desired.is_none() {
+                    self.set_error(None);
+                }
+                desired
+            }
+            Err(e) => {
+                self.set_error(Some(e));
+                None
+            }
+        };
+
+        let mut running = self.running.lock().await;
+        if let Some(current) = running.as_ref() {
+            if desired.as_ref() == Some(&(current.listen, current.config.clone())) {
+                return;
+            }
+            current.task.abort();
+            *running = None;
+        }
+
+        let Some((listen, config)) = desired else {
+            return;
+        };
+        match serve(listen, config.clone()).await {
+            Ok(task) => {
+                *running = Some(Running { listen, config, task });
+                self.set_error(None);
+            }
+            Err(e) => self.set_error(Some(format!("Failed to listen on {}: {}", listen, e))),
+        }
+    }
+
+    pub async fn is_running(&self) -> bool {
+        self.running.lock().await.is_some()
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    fn set_error(&self, error: Option<String>) {
+        let mut current = self.error.lock().unwrap();
+        if error.is_some() && *current != error {
+            eprintln!("{}: {}", self.name, error.as_deref().unwrap_or_default());
+        }
+        *current = error;
+    }
+}
+
+// Parse a listen address from the settings
+pub fn parse_listen(listen: &str) -> Result<SocketAddr, String> {
+    listen
+        .parse()
+        .map_err(|_| format!("Invalid listen address '{}'", listen))
+}
+
+// Bind a TCP listener, through the privileged helper if the port is
+// privileged and binding it ourselves is refused
+pub async fn bind_tcp(listen: SocketAddr) -> io::Result<TcpListener> {
+    match TcpListener::bind(listen).await {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            let listener: std::net::TcpListener = bind_with_helper(listen, SocketKind::Tcp, e).await?;
+            listener.set_nonblocking(true)?;
+            TcpListener::from_std(listener)
+        }
+        result => result,
+    }
+}
+
+// `bind_tcp` for UDP
+pub async fn bind_udp(listen: SocketAddr) -> io::Result<UdpSocket> {
+    match UdpSocket::bind(listen).await {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            let socket: std::net::UdpSocket = bind_with_helper(listen, SocketKind::Udp, e).await?;
+            socket.set_nonblocking(true)?;
+            UdpSocket::from_std(socket)
+        }
+        result => result,
+    }
+}
+
+// `denied` is what binding it ourselves failed with, returned as is when no
+// helper is running
+#[cfg(target_os = "linux")]
+async fn bind_with_helper<T>(listen: SocketAddr, kind: SocketKind, denied: io::Error) -> io::Result<T>
+where
+    T: From<std::os::fd::OwnedFd> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(helper) = crate::helper::HelperClient::connect() else {
+            return Err(denied);
+        };
+        helper
+            .bind_socket(listen, kind)
+            .map(T::from)
+            .map_err(|e| io::Error::new(denied.kind(), format!("{} (privileged helper: {})", denied, e)))
+    })
+    .await
+    .map_err(|e| io::Error::other(e.to_string()))?
+}
+
+// Only Linux keeps ports below 1024 from normal users
+#[cfg(not(target_os = "linux"))]
+async fn bind_with_helper<T>(_listen: SocketAddr, _kind: SocketKind, denied: io::Error) -> io::Result<T> {
+    Err(denied)
+}
+
+// Lets an event per host through at most once per interval. Browsers retry
+// and a page load brings many requests along, so one visit would otherwise
+// fill the event log. The hits held back are counted and reported with the
+// next event let through.
+#[derive(Clone)]
+pub struct HostThrottle {
+    interval: Duration,
+    // When each host was last let through, and the hits held back since
+    hosts: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
+}
+
+impl HostThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // Count a hit on `host`. `Some(held_back)` if it should be logged now,
+    // with the number of hits held back since the last one logged; `None`
+    // while the host is throttled.
+    pub fn hit(&self, host: &str) -> Option<u32> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let now = Instant::now();
+        if let Some((at, held_back)) = hosts.get_mut(host) {
+            if now.duration_since(*at) < self.interval {
+                *held_back += 1;
+                return None;
+            }
+        }
+        // Hosts with held back hits stay until their next hit reports them
+        hosts.retain(|_, (at, held_back)| *held_back > 0 || now.duration_since(*at) < self.interval);
+        let held_back = hosts.insert(host.to_string(), (now, 0)).map_or(0, |(_, held_back)| held_back);
+        Some(held_back)
+    }
+}
+
+// Suffix for a logged event reporting `held_back` throttled hits
+pub fn held_back_note(held_back: u32) -> String {
+    match held_back {
+        0 => String::new(),
+        1 => " (1 more hit since the last report)".to_string(),
+        n => format!(" ({} more hits since the last report)", n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttle_lets_each_host_through_once_per_interval() {
+        let throttle = HostThrottle::new(Duration::from_millis(50));
+        assert_eq!(throttle.hit("a.example"), Some(0));
+        assert_eq!(throttle.hit("a.example"), None);
+        assert_eq!(throttle.hit("a.example"), None);
+        assert_eq!(throttle.hit("b.example"), Some(0));
+        std::thread::sleep(Duration::from_millis(60));
+        // Another host's hit doesn't drop the count held back for a.example
+        assert_eq!(throttle.hit("b.example"), Some(0));
+        assert_eq!(throttle.hit("a.example"), Some(2));
+        assert_eq!(throttle.hit("a.example"), None);
+    }
+
+    #[test]
+    fn held_back_hits_are_noted() {
+        assert_eq!(held_back_note(0), "");
+        assert_eq!(held_back_note(1), " (1 more hit since the last report)");
+        assert_eq!(held_back_note(7), " (7 more hits since the last report)");
+    }
+}
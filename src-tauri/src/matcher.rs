@@ -0,0 +1,436 @@
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::rules::BlockRule;
+use crate::ProcessInfo;
+
+// A single way a rule can identify a process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MatchCriterion {
+    // Full executable path (case-insensitive, `\` and `/` treated alike)
+    ExactPath { path: String },
+    // Glob on the executable file name, e.g. `discord*.exe`
+    NameGlob { pattern: String },
+    // Regex on the full command line
+    CommandLineRegex { pattern: String },
+    // Name of the parent process, e.g. blocking everything Steam launches
+    ParentName { name: String },
+    // SHA-256 of the executable, survives renames and moves
+    Sha256 { hash: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MatchReason {
+    ExactPath { path: String },
+    NameGlob { pattern: String, name: String },
+    CommandLineRegex { pattern: String },
+    ParentName { name: String },
+    Sha256 { hash: String },
+}
+
+// Which rule matched a process and why
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleMatch {
+    pub rule_id: String,
+    pub app_name: String,
+    pub reason: MatchReason,
+}
+
+enum CompiledCriterion {
+    ExactPath(String),
+    NameGlob(Pattern),
+    CommandLineRegex(Regex),
+    ParentName(String),
+    Sha256(String),
+}
+
+struct CompiledRule {
+    rule_id: String,
+    app_name: String,
+    criteria: Vec<CompiledCriterion>,
+}
+
+// Rule set compiled for matching against running processes. Build it once per
+// scan with `ProcessMatcher::compile` and reuse it for every process.
+pub struct ProcessMatcher {
+    rules: Vec<CompiledRule>,
+}
+
+const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+impl ProcessMatcher {
+    pub fn compile(rules: &[BlockRule]) -> Self {
+        let rules = rules
+            .iter()
+            .map(|rule| CompiledRule {
+                rule_id: rule.id.clone(),
+                app_name: rule.app_name.clone(),
+                criteria: criteria_for(rule)
+                    .into_iter()
+                    .filter_map(|criterion| match compile_criterion(&criterion) {
+                        Ok(compiled) => Some(compiled),
+                        Err(e) => {
+                            eprintln!("Skipping invalid criterion in rule {}: {}", rule.id, e);
+                            None
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // First rule that matches `process`. Hashing is only done if no cheaper
+    // criterion matched first.
+    pub fn find_match(&self, process: &ProcessInfo, hashes: &HashCache) -> Option<RuleMatch> {
+        let path = normalize_path(&process.path);
+        let file_name = path.rsplit('/').next().unwrap_or("").to_string();
+        let name = process.name.to_lowercase();
+
+        for rule in &self.rules {
+            let reason = rule.criteria.iter().find_map(|criterion| match criterion {
+                CompiledCriterion::ExactPath(expected) => (!path.is_empty() && path == *expected)
+                    .then(|| MatchReason::ExactPath {
+                        path: process.path.clone(),
+                    }),
+                CompiledCriterion::NameGlob(pattern) => [&file_name, &name]
+                    .into_iter()
+                    .find(|candidate| {
+                        !candidate.is_empty() && pattern.matches_with(candidate, GLOB_OPTIONS)
+                    })
+                    .map(|candidate| MatchReason::NameGlob {
+                        pattern: pattern.as_str().to_string(),
+                        name: candidate.clone(),
+                    }),
+                CompiledCriterion::CommandLineRegex(regex) => (!process.cmd.is_empty()
+                    && regex.is_match(&process.cmd))
+                .then(|| MatchReason::CommandLineRegex {
+                    pattern: regex.as_str().to_string(),
+                }),
+                CompiledCriterion::ParentName(expected) => process
+                    .parent_name
+                    .as_deref()
+                    .filter(|parent| strip_exe(&parent.to_lowercase()) == *expected)
+                    .map(|parent| MatchReason::ParentName {
+                        name: parent.to_string(),
+                    }),
+                CompiledCriterion::Sha256(_) => None,
+            });
+
+            if let Some(reason) = reason {
+                return Some(RuleMatch {
+                    rule_id: rule.rule_id.clone(),
+                    app_name: rule.app_name.clone(),
+                    reason,
+                });
+            }
+        }
+
+        // Hash last: it reads the executable (cached by path, size and mtime)
+        if process.path.is_empty() {
+            return None;
+        }
+        let mut digest = None;
+        for rule in &self.rules {
+            for criterion in &rule.criteria {
+                if let CompiledCriterion::Sha256(expected) = criterion {
+                    let actual = digest.get_or_insert_with(|| hashes.sha256(Path::new(&process.path)));
+                    if actual.as_deref() == Some(expected.as_str()) {
+                        return Some(RuleMatch {
+                            rule_id: rule.rule_id.clone(),
+                            app_name: rule.app_name.clone(),
+                            reason: MatchReason::Sha256 {
+                                hash: expected.clone(),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+// Explicit criteria win; rules created before criteria existed fall back to
+// the old behaviour (exact path, executable file name, process name) plus the
+// recorded executable hash
+fn criteria_for(rule: &BlockRule) -> Vec<MatchCriterion> {
+    let mut criteria = rule.match_criteria.clone();
+
+    if criteria.is_empty() {
+        if !rule.app_path.is_empty() {
+            criteria.push(MatchCriterion::ExactPath {
+                path: rule.app_path.clone(),
+            });
+            let file_name = normalize_path(&rule.app_path)
+                .rsplit('/')
+                .next()
+                .unwrap_or("")
+                .to_string();
+            if !file_name.is_empty() {
+                criteria.push(MatchCriterion::NameGlob {
+                    pattern: Pattern::escape(&file_name),
+                });
+            }
+        }
+        if !rule.app_name.is_empty() {
+            // Process names may or may not carry the `.exe` suffix
+            let stem = Pattern::escape(&strip_exe(&rule.app_name.to_lowercase()));
+            criteria.push(MatchCriterion::NameGlob {
+                pattern: stem.clone(),
+            });
+            criteria.push(MatchCriterion::NameGlob {
+                pattern: format!("{}.exe", stem),
+            });
+        }
+    }
+
+    if let Some(hash) = &rule.exe_sha256 {
+        criteria.push(MatchCriterion::Sha256 { hash: hash.clone() });
+    }
+
+    criteria
+}
+
+fn compile_criterion(criterion: &MatchCriterion) -> Result<CompiledCriterion, String> {
+    Ok(match criterion {
+        MatchCriterion::ExactPath { path } => CompiledCriterion::ExactPath(normalize_path(path)),
+        MatchCriterion::NameGlob { pattern } => CompiledCriterion::NameGlob(
+            Pattern::new(pattern).map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?,
+        ),
+        MatchCriterion::CommandLineRegex { pattern } => CompiledCriterion::CommandLineRegex(
+            Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?,
+        ),
+        MatchCriterion::ParentName { name } => {
+            CompiledCriterion::ParentName(strip_exe(&name.to_lowercase()))
+        }
+        MatchCriterion::Sha256 { hash } => CompiledCriterion::Sha256(hash.to_lowercase()),
+    })
+}
+
+fn normalize_path(path: &str) -> String {
+    path.to_lowercase().replace('\\', "/")
+}
+
+fn strip_exe(name: &str) -> String {
+    name.strip_suffix(".exe").unwrap_or(name).to_string()
+}
+
+// SHA-256 of an executable as lowercase hex
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+struct CachedHash {
+    size: u64,
+    modified: Option<SystemTime>,
+    hash: String,
+}
+
+// Cache of executable hashes keyed by path, invalidated when size or mtime
+// change. Hashing reads the whole file, so callers on the async runtime match
+// inside `spawn_blocking`.
+#[derive(Clone)]
+pub struct HashCache(Arc<Mutex<HashMap<PathBuf, CachedHash>>>);
+
+impl HashCache {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(HashMap::new())))
+    }
+
+    pub fn sha256(&self, path: &Path) -> Option<String> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let size = metadata.len();
+        let modified = metadata.modified().ok();
+
+        if let Some(cached) = self.0.lock().unwrap().get(path) {
+            if cached.size == size && cached.modified == modified {
+                return Some(cached.hash.clone());
+            }
+        }
+
+        let hash = sha256_file(path).ok()?;
+        self.0.lock().unwrap().insert(
+            path.to_path_buf(),
+            CachedHash {
+                size,
+                modified,
+                hash: hash.clone(),
+            },
+        );
+        Some(hash)
+    }
+}
+
+impl Default for HashCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn rule(app_name: &str, app_path: &str, criteria: Vec<MatchCriterion>) -> BlockRule {
+        let mut rule: BlockRule = serde_json::from_value(serde_json::json!({
+            "id": "rule-1",
+            "appName": app_name,
+            "appPath": app_path,
+            "isActive": true,
+            "createdAt": 0,
+            "type": "permanent",
+        }))
+        .unwrap();
+        rule.match_criteria = criteria;
+        rule
+    }
+
+    fn process(name: &str, path: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid: 4242,
+            name: name.to_string(),
+            path: path.to_string(),
+            cmd: path.to_string(),
+            parent_pid: None,
+            parent_name: None,
+        }
+    }
+
+    fn reason(rules: &[BlockRule], process: &ProcessInfo) -> Option<MatchReason> {
+        ProcessMatcher::compile(rules)
+            .find_match(process, &HashCache::new())
+            .map(|hit| hit.reason)
+    }
+
+    #[test]
+    fn renamed_executable_is_caught_by_its_hash() {
+        let dir = std::env::temp_dir().join(format!("neu-matcher-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let original = dir.join("discord.exe");
+        let renamed = dir.join("notes.exe");
+        let other = dir.join("editor.exe");
+        fs::write(&original, b"discord binary").unwrap();
+        fs::copy(&original, &renamed).unwrap();
+        fs::write(&other, b"some other binary").unwrap();
+
+        // As `save_rule` records it
+        let mut discord = rule("Discord", &original.to_string_lossy(), Vec::new());
+        let hash = sha256_file(&original).unwrap();
+        discord.exe_sha256 = Some(hash.clone());
+        let rules = [discord];
+
+        assert_eq!(
+            reason(&rules, &process("notes.exe", &renamed.to_string_lossy())),
+            Some(MatchReason::Sha256 { hash })
+        );
+        assert_eq!(reason(&rules, &process("editor.exe", &other.to_string_lossy())), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn name_glob_matches_file_or_process_name() {
+        let rules = [rule(
+            "Discord",
+            "",
+            vec![MatchCriterion::NameGlob {
+                pattern: "discord*.exe".to_string(),
+            }],
+        )];
+        assert_eq!(
+            reason(&rules, &process("x", "C:\\Apps\\DiscordPTB.exe")),
+            Some(MatchReason::NameGlob {
+                pattern: "discord*.exe".to_string(),
+                name: "discordptb.exe".to_string(),
+            })
+        );
+        assert!(reason(&rules, &process("discord.exe", "")).is_some());
+        assert_eq!(reason(&rules, &process("discord", "/usr/bin/discord")), None);
+    }
+
+    #[test]
+    fn command_line_regex_matches() {
+        let rules = [rule(
+            "Minecraft",
+            "",
+            vec![MatchCriterion::CommandLineRegex {
+                pattern: r"net\.minecraft\.client".to_string(),
+            }],
+        )];
+        let mut java = process("java", "/usr/bin/java");
+        java.cmd = "/usr/bin/java -cp mc.jar net.minecraft.client.main.Main".to_string();
+        assert!(matches!(reason(&rules, &java), Some(MatchReason::CommandLineRegex { .. })));
+        java.cmd = "/usr/bin/java -jar other.jar".to_string();
+        assert_eq!(reason(&rules, &java), None);
+    }
+
+    #[test]
+    fn parent_name_ignores_case_and_exe_suffix() {
+        let rules = [rule(
+            "Steam games",
+            "",
+            vec![MatchCriterion::ParentName {
+                name: "steam".to_string(),
+            }],
+        )];
+        let mut game = process("game.exe", "C:\\Games\\game.exe");
+        game.parent_name = Some("Steam.exe".to_string());
+        assert_eq!(
+            reason(&rules, &game),
+            Some(MatchReason::ParentName {
+                name: "Steam.exe".to_string()
+            })
+        );
+        game.parent_name = Some("steamwebhelper.exe".to_string());
+        assert_eq!(reason(&rules, &game), None);
+    }
+
+    #[test]
+    fn legacy_rules_match_path_file_and_process_name() {
+        let rules = [rule("Discord.exe", "C:\\Apps\\Discord\\Discord.exe", Vec::new())];
+        assert!(matches!(
+            reason(&rules, &process("x", "c:/apps/discord/discord.exe")),
+            Some(MatchReason::ExactPath { .. })
+        ));
+        assert!(reason(&rules, &process("discord", "/opt/discord/Discord")).is_some());
+        assert_eq!(reason(&rules, &process("slack", "/opt/slack/slack")), None);
+    }
+}
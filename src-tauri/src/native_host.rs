@@ -0,0 +1,386 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::rules::{self, UrlBlockRule};
+use crate::store::{self, BlockEvent, Store};
+use crate::url_pattern::{self, UrlPattern};
+
+// Name the browser extension connects to (`runtime.connectNative`)
+pub const HOST_NAME: &str = "com.virtu.neu";
+
+// Tauri's app identifier; the app data dir is named after it
+const APP_IDENTIFIER: &str = "com.virtu.neu";
+
+// Visits recorded by the host, one JSON event per line, until the app moves
+// them into the store. The host never writes the store itself, since the app
+// keeps its own copy in memory and would overwrite it.
+const SPOOL_FILE_NAME: &str = "native_host_events.jsonl";
+
+// How often the app picks up spooled visits
+const DRAIN_INTERVAL: Duration = Duration::from_secs(5);
+
+// Browsers send at most 4 GB per message; nothing we expect comes close
+const MAX_MESSAGE_LEN: usize = 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Request {
+    Ping,
+    // Should this URL be blocked right now?
+    Check { url: String },
+    // A tab navigated to this URL. Recorded as a violation if a rule blocks it,
+    // otherwise left out of the log.
+    Visit { url: String },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Response {
+    Pong {
+        version: &'static str,
+    },
+    Verdict {
+        url: String,
+        blocked: bool,
+        rule_id: Option<String>,
+        pattern: Option<String>,
+        // Unix millis when the block lifts; absent for permanent rules
+        ends_at: Option<i64>,
+    },
+    Recorded {
+        url: String,
+        blocked: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+// Where the app keeps its store. `NEU_DATA_DIR` overrides it for setups where
+// the browser runs as a different user than the app.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("NEU_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+
+    #[cfg(target_os = "windows")]
+    let base = std::env::var_os("APPDATA").map(PathBuf::from);
+
+    #[cfg(target_os = "macos")]
+    let base = std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+
+    base.map(|base| base.join(APP_IDENTIFIER))
+}
+
+// The active URL rule that blocks `url`, if any. URL rules follow the same
+// switch as website rules.
+pub fn blocking_rule(
+    data: &store::StoreData,
+    url: &str,
+    now: &chrono::DateTime<chrono::Local>,
+) -> Option<UrlBlockRule> {
+    if !data.settings.website_blocking_enabled {
+        return None;
+    }
+    rules::active_at(&data.url_rules, now)
+        .into_iter()
+        .find(|rule| match UrlPattern::parse(&rule.pattern) {
+            Ok(pattern) => pattern.matches(url),
+            Err(_) => false,
+        })
+}
+
+// Answer one request. `data_dir` holds the store and the visit spool.
+pub fn handle(request: Request, data_dir: &Path) -> Response {
+    let (url, record) = match request {
+        Request::Ping => {
+            return Response::Pong {
+                version: env!("CARGO_PKG_VERSION"),
+            }
+        }
+        Request::Check { url } => (url, false),
+        Request::Visit { url } => (url, true),
+    };
+
+    let data = match Store::read_snapshot(&data_dir.join(store::STORE_FILE_NAME)) {
+        Ok(data) => data,
+        Err(message) => return Response::Error { message },
+    };
+    let now = chrono::Local::now();
+    let rule = blocking_rule(&data, &url, &now);
+
+    if !record {
+        return Response::Verdict {
+            blocked: rule.is_some(),
+            rule_id: rule.as_ref().map(|rule| rule.id.clone()),
+            pattern: rule.as_ref().map(|rule| rule.pattern.clone()),
+            ends_at: rule
+                .as_ref()
+                .and_then(|rule| rule.timing.ends_at(&now))
+                .map(|end| end.timestamp_millis()),
+            url,
+        };
+    }
+
+    // The verdict is worked out here rather than taken from the extension.
+    // Only violations are kept: the event log is short, and allowed browsing
+    // is none of its business.
+    if let Some(rule) = &rule {
+        // Host and path only; queries often carry search terms or tokens
+        let (host, page) = url_pattern::split_url(&url)
+            .map(|(host, path)| {
+                let path = path.split(['?', '#']).next().unwrap_or_default().to_string();
+                (host.clone(), format!("{}{}", host, path))
+            })
+            .unwrap_or_else(|| (url.clone(), url.clone()));
+        let event = BlockEvent::new(
+            "violation",
+            &host,
+            format!("Blocked visit to {} (rule: {})", page, rule.pattern),
+        );
+        if let Err(message) = spool_event(data_dir, &event) {
+            return Response::Error { message };
+        }
+    }
+    Response::Recorded {
+        url,
+        blocked: rule.is_some(),
+    }
+}
+
+fn spool_event(data_dir: &Path, event: &BlockEvent) -> Result<(), String> {
+    let mut line = serde_json::to_string(event).map_err(|e| e.to_string())?;
+    line.push('\n');
+    fs::create_dir_all(data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    // One write per line, so concurrent hosts (one per browser) never interleave
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_dir.join(SPOOL_FILE_NAME))
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to record visit: {}", e))
+}
+
+// Move spooled visits into the store. The spool is renamed first, so hosts
+// appending meanwhile start a fresh file instead of losing lines.
+pub fn drain_spool(data_dir: &Path, store: &Store) -> Result<usize, String> {
+    let spool = data_dir.join(SPOOL_FILE_NAME);
+    let draining = spool.with_extension("jsonl.draining");
+    // A leftover from a drain that failed after the rename is picked up first
+    if !draining.exists() {
+        match fs::rename(&spool, &draining) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("Failed to read visit spool: {}", e)),
+        }
+    }
+
+    let content = fs::read_to_string(&draining).map_err(|e| format!("Failed to read visit spool: {}", e))?;
+    let mut events: Vec<BlockEvent> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    events.sort_by_key(|event| event.timestamp);
+    let count = events.len();
+
+    store.append_events(events)?;
+    let _ = fs::remove_file(&draining);
+    Ok(count)
+}
+
+// Spawn the loop that drains the visit spool. Called once from the `setup` hook.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let Ok(data_dir) = app.path().app_data_dir() else {
+            return;
+        };
+        let mut ticker = tokio::time::interval(DRAIN_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = drain_spool(&data_dir, &app.state::<Store>()) {
+                eprintln!("Native host: {}", e);
+            }
+        }
+    });
+}
+
+// Native messaging framing: a u32 length in native byte order, then that many
+// bytes of UTF-8 JSON. `None` once the browser closes the pipe.
+pub fn read_message(input: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_ne_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes is too large", len),
+        ));
+    }
+    let mut message = vec![0u8; len];
+    input.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+pub fn write_message(output: &mut impl Write, response: &Response) -> io::Result<()> {
+    let json = serde_json::to_vec(response)?;
+    output.write_all(&(json.len() as u32).to_ne_bytes())?;
+    output.write_all(&json)?;
+    output.flush()
+}
+
+// Manifest that registers the host with a browser. Chromium browsers list
+// allowed extension origins, Firefox lists extension ids.
+pub fn manifest(browser: &str, extension_id: &str, host_path: &Path) -> Result<String, String> {
+    let mut manifest = serde_json::json!({
+        "name": HOST_NAME,
+        "description": "Not Enough Accountability URL blocking",
+        "path": host_path,
+        "type": "stdio",
+    });
+    match browser {
+        "chrome" | "chromium" | "edge" | "brave" => {
+            manifest["allowed_origins"] = serde_json::json!([format!("chrome-extension://{}/", extension_id)]);
+        }
+        "firefox" => {
+            manifest["allowed_extensions"] = serde_json::json!([extension_id]);
+        }
+        other => return Err(format!("Unknown browser '{}'", other)),
+    }
+    serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())
+}
+
+// Entry point of the `neu-native-host` binary. Browsers start it with the
+// calling extension as an argument; `--manifest <browser> <extension id>`
+// prints the registration manifest instead.
+pub fn run() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("--manifest") {
+        let (Some(browser), Some(extension_id)) = (args.get(1), args.get(2)) else {
+            eprintln!("Usage: neu-native-host --manifest <chrome|firefox> <extension id>");
+            std::process::exit(2);
+        };
+        let host_path = std::env::current_exe().unwrap_or_default();
+        match manifest(browser, extension_id, &host_path) {
+            Ok(manifest) => println!("{}", manifest),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    let Some(data_dir) = data_dir() else {
+        eprintln!("Could not locate the NEU data directory");
+        std::process::exit(1);
+    };
+
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    loop {
+        let message = match read_message(&mut stdin) {
+            Ok(Some(message)) => message,
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("Native host: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let response = match serde_json::from_slice::<Request>(&message) {
+            Ok(request) => handle(request, &data_dir),
+            Err(e) => Response::Error {
+                message: format!("Invalid request: {}", e),
+            },
+        };
+        if write_message(&mut stdout, &response).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleTiming;
+
+    fn framed(message: &[u8]) -> Vec<u8> {
+        let mut framed = (message.len() as u32).to_ne_bytes().to_vec();
+        framed.extend_from_slice(message);
+        framed
+    }
+
+    #[test]
+    fn messages_are_length_prefixed() {
+        let mut input = framed(br#"{"type":"ping"}"#);
+        input.extend(framed(b"{}"));
+        let mut input = &input[..];
+        assert_eq!(read_message(&mut input).unwrap().unwrap(), br#"{"type":"ping"}"#);
+        assert_eq!(read_message(&mut input).unwrap().unwrap(), b"{}");
+        // The browser closing the pipe ends the session
+        assert!(read_message(&mut input).unwrap().is_none());
+
+        let mut output = Vec::new();
+        write_message(&mut output, &Response::Pong { version: "1" }).unwrap();
+        let mut output = &output[..];
+        let message = read_message(&mut output).unwrap().unwrap();
+        assert_eq!(message, br#"{"type":"pong","version":"1"}"#);
+    }
+
+    #[test]
+    fn oversized_and_cut_off_messages_are_rejected() {
+        let mut input = &((MAX_MESSAGE_LEN + 1) as u32).to_ne_bytes()[..];
+        let error = read_message(&mut input).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let framed = framed(br#"{"type":"ping"}"#);
+        let mut input = &framed[..framed.len() - 1];
+        assert!(read_message(&mut input).is_err());
+    }
+
+    #[test]
+    fn only_blocked_visits_are_spooled() {
+        let dir = std::env::temp_dir().join(format!("neu-native-host-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let store = Store::load(dir.join(store::STORE_FILE_NAME)).unwrap();
+        store
+            .update(|data| {
+                data.url_rules.push(UrlBlockRule {
+                    id: "rule-1".to_string(),
+                    pattern: "reddit.com/r/all".to_string(),
+                    is_active: true,
+                    created_at: 0,
+                    timing: RuleTiming::permanent(),
+                })
+            })
+            .unwrap();
+
+        let visit = |url: &str| handle(Request::Visit { url: url.to_string() }, &dir);
+        assert!(matches!(visit("https://reddit.com/r/allergies"), Response::Recorded { blocked: false, .. }));
+        assert!(matches!(
+            visit("https://www.reddit.com/r/all?q=secret"),
+            Response::Recorded { blocked: true, .. }
+        ));
+        let spooled = fs::read_to_string(dir.join(SPOOL_FILE_NAME)).unwrap();
+        let events: Vec<BlockEvent> = spooled.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "violation");
+        assert_eq!(events[0].message, "Blocked visit to www.reddit.com/r/all (rule: reddit.com/r/all)");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
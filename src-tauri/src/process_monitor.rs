@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+use crate::AppInfo;
+
+// Per-process details used for rule matching
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub path: String,
+    pub cmd: String,
+    pub parent_pid: Option<u32>,
+    pub parent_name: Option<String>,
+}
+
+// Long-lived `System` shared by every process scan. Only the fields we match
+// on are refreshed: name, parent and exe come with every process refresh,
+// while cmd is only re-read for processes that are new or whose identity
+// changed since the last scan.
+#[derive(Clone)]
+pub struct ProcessMonitor {
+    scan: Arc<Mutex<Scan>>,
+}
+
+struct Scan {
+    sys: System,
+    // Identity of each process as of the last scan. An exec keeps the PID but
+    // changes the name or exe, and a reused PID has a new start time; either
+    // way the cached cmd belongs to the old program.
+    identities: HashMap<Pid, Identity>,
+}
+
+type Identity = (OsString, Option<PathBuf>, u64);
+
+fn identity(process: &Process) -> Identity {
+    (
+        process.name().to_os_string(),
+        process.exe().map(PathBuf::from),
+        process.start_time(),
+    )
+}
+
+fn refresh_kind(exe_and_cmd: UpdateKind) -> ProcessRefreshKind {
+    ProcessRefreshKind::new()
+        .with_exe(exe_and_cmd)
+        .with_cmd(exe_and_cmd)
+}
+
+impl Scan {
+    fn remember(&mut self, pids: &[Pid]) {
+        for pid in pids {
+            match self.sys.process(*pid) {
+                Some(process) => self.identities.insert(*pid, identity(process)),
+                None => self.identities.remove(pid),
+            };
+        }
+    }
+}
+
+impl ProcessMonitor {
+    pub fn new() -> Self {
+        Self {
+            scan: Arc::new(Mutex::new(Scan {
+                sys: System::new(),
+                identities: HashMap::new(),
+            })),
+        }
+    }
+
+    // Every running process (not deduplicated by path, so each PID can be killed)
+    pub fn process_details(&self) -> Vec<ProcessInfo> {
+        let mut scan = self.scan.lock().unwrap();
+        let Scan { sys, identities } = &mut *scan;
+        sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::new()
+                .with_exe(UpdateKind::Always)
+                .with_cmd(UpdateKind::OnlyIfNotSet),
+        );
+
+        let changed: Vec<Pid> = sys
+            .processes()
+            .iter()
+            .filter(|(pid, process)| {
+                identities
+                    .get(pid)
+                    .is_some_and(|known| *known != identity(process))
+            })
+            .map(|(pid, _)| *pid)
+            .collect();
+        if !changed.is_empty() {
+            sys.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&changed),
+                false,
+                ProcessRefreshKind::new().with_cmd(UpdateKind::Always),
+            );
+        }
+
+        *identities = sys
+            .processes()
+            .iter()
+            .map(|(pid, process)| (*pid, identity(process)))
+            .collect();
+
+        sys.processes()
+            .values()
+            .map(|process| process_info(sys, process))
+            .collect()
+    }
+
+    // Details for just the given PIDs (and their parents, for parent names).
+    // Used for newly launched processes, where a full scan would be wasteful.
+    // An exec keeps the PID, so exe and cmd are always re-read here.
+    pub fn process_details_for(&self, pids: &[u32]) -> Vec<ProcessInfo> {
+        let pids: Vec<Pid> = pids.iter().map(|pid| Pid::from_u32(*pid)).collect();
+
+        let mut scan = self.scan.lock().unwrap();
+        scan.sys.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&pids),
+            false,
+            refresh_kind(UpdateKind::Always),
+        );
+        scan.remember(&pids);
+        let sys = &mut scan.sys;
+
+        let unknown_parents: Vec<Pid> = pids
+            .iter()
+            .filter_map(|pid| sys.process(*pid)?.parent())
+            .filter(|parent| sys.process(*parent).is_none())
+            .collect();
+        if !unknown_parents.is_empty() {
+            sys.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&unknown_parents),
+                false,
+                refresh_kind(UpdateKind::OnlyIfNotSet),
+            );
+        }
+
+        pids.iter()
+            .filter_map(|pid| sys.process(*pid))
+            .map(|process| process_info(sys, process))
+            .collect()
+    }
+
+    // Running processes with an executable path, one entry per executable
+    pub fn running_apps(&self) -> Vec<AppInfo> {
+        let mut apps: Vec<AppInfo> = self
+            .process_details()
+            .into_iter()
+            // Only include processes with valid executable paths
+            .filter(|process| !process.path.is_empty())
+            .map(|process| AppInfo {
+                name: process.name,
+                path: process.path,
+                pid: Some(process.pid),
+                icon: None,
+            })
+            .collect();
+
+        // Sort by path (more reliable than name)
+        apps.sort_by_key(|app| app.path.to_lowercase());
+
+        // Deduplicate by path (not name, since same path = same executable)
+        apps.dedup_by(|a, b| a.path.to_lowercase() == b.path.to_lowercase());
+
+        apps
+    }
+}
+
+impl Default for ProcessMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn process_info(sys: &System, process: &Process) -> ProcessInfo {
+    ProcessInfo {
+        pid: process.pid().as_u32(),
+        name: process.name().to_string_lossy().to_string(),
+        path: process
+            .exe()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        cmd: process
+            .cmd()
+            .iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" "),
+        parent_pid: process.parent().map(|parent| parent.as_u32()),
+        parent_name: process
+            .parent()
+            .and_then(|parent| sys.process(parent))
+            .map(|parent| parent.name().to_string_lossy().to_string()),
+    }
+}
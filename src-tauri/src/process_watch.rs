@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+// How often the polling fallback diffs the process list
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Watch for newly started processes and send their PIDs to `launches`.
+// On Linux this subscribes to the kernel's proc connector (needs
+// CAP_NET_ADMIN); if that isn't available, or on other platforms, it falls
+// back to diffing the PID list every `POLL_INTERVAL`.
+pub fn start(launches: UnboundedSender<u32>) {
+    std::thread::Builder::new()
+        .name("neu-process-watch".to_string())
+        .spawn(move || {
+            #[cfg(target_os = "linux")]
+            match netlink::ProcConnector::subscribe() {
+                Ok(connector) => {
+                    connector.run(&launches);
+                    // The socket failed after subscribing; keep watching by polling
+                    eprintln!("Proc connector stopped, falling back to polling");
+                }
+                Err(e) => eprintln!("Proc connector unavailable ({}), falling back to polling", e),
+            }
+
+            poll_for_launches(&launches);
+        })
+        .expect("failed to spawn process watch thread");
+}
+
+// Fallback: diff the set of PIDs against the previous pass
+fn poll_for_launches(launches: &UnboundedSender<u32>) {
+    let mut known = list_pids();
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let current = list_pids();
+        for pid in current.difference(&known) {
+            if launches.send(*pid).is_err() {
+                return; // Engine is gone
+            }
+        }
+        known = current;
+    }
+}
+
+// Reading /proc directly is far cheaper than a sysinfo refresh
+#[cfg(target_os = "linux")]
+fn list_pids() -> HashSet<u32> {
+    std::fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn list_pids() -> HashSet<u32> {
+    use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::new());
+    sys.processes().keys().map(|pid| pid.as_u32()).collect()
+}
+
+#[cfg(target_os = "linux")]
+mod netlink {
+    use std::io;
+    use std::mem::size_of;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use tokio::sync::mpsc::UnboundedSender;
+
+    // From <linux/connector.h> and <linux/cn_proc.h>
+    const CN_IDX_PROC: u32 = 1;
+    const CN_VAL_PROC: u32 = 1;
+    const PROC_CN_MCAST_LISTEN: u32 = 1;
+    const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+
+    const NLMSG_HDR_LEN: usize = 16;
+    // struct cn_msg: cb_id (idx, val), seq, ack, len (u16), flags (u16)
+    const CN_MSG_LEN: usize = 20;
+    // struct proc_event: what, cpu, timestamp_ns, then the event data
+    const PROC_EVENT_DATA_OFFSET: usize = 16;
+
+    pub struct ProcConnector {
+        socket: OwnedFd,
+    }
+
+    impl ProcConnector {
+        pub fn subscribe() -> io::Result<Self> {
+            // SAFETY: plain socket(2) call; the fd is owned by `OwnedFd` below
+            let fd = unsafe {
+                libc::socket(
+                    libc::AF_NETLINK,
+                    libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                    libc::NETLINK_CONNECTOR,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: `fd` is a freshly created, valid socket we exclusively own
+            let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+            // SAFETY: sockaddr_nl is plain old data; all-zero is a valid value
+            let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+            addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            addr.nl_pid = 0; // Let the kernel assign a port id
+            addr.nl_groups = CN_IDX_PROC;
+
+            // SAFETY: `addr` is a valid sockaddr_nl and the length matches it
+            let bound = unsafe {
+                libc::bind(
+                    socket.as_raw_fd(),
+                    &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                    size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+                )
+            };
+            if bound < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let connector = Self { socket };
+            connector.send_listen()?;
+            Ok(connector)
+        }
+
+        // Ask the kernel to start multicasting process events to this socket
+        fn send_listen(&self) -> io::Result<()> {
+            let payload_len = CN_MSG_LEN + size_of::<u32>();
+            let total_len = NLMSG_HDR_LEN + payload_len;
+
+            let mut message = Vec::with_capacity(total_len);
+            // nlmsghdr: len, type, flags, seq, pid
+            message.extend_from_slice(&(total_len as u32).to_ne_bytes());
+            message.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+            message.extend_from_slice(&0u16.to_ne_bytes());
+            message.extend_from_slice(&0u32.to_ne_bytes());
+            message.extend_from_slice(&std::process::id().to_ne_bytes());
+            // cn_msg
+            message.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+            message.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+            message.extend_from_slice(&0u32.to_ne_bytes()); // seq
+            message.extend_from_slice(&0u32.to_ne_bytes()); // ack
+            message.extend_from_slice(&(size_of::<u32>() as u16).to_ne_bytes());
+            message.extend_from_slice(&0u16.to_ne_bytes()); // flags
+            // proc_cn_mcast_op
+            message.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+
+            // SAFETY: `message` is a valid, initialized buffer of `message.len()` bytes
+            let sent = unsafe {
+                libc::send(
+                    self.socket.as_raw_fd(),
+                    message.as_ptr() as *const libc::c_void,
+                    message.len(),
+                    0,
+                )
+            };
+            if sent < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        // Forward the PID of every exec'd process until the socket fails or
+        // the receiver is dropped
+        pub fn run(&self, launches: &UnboundedSender<u32>) {
+            let mut buffer = [0u8; 4096];
+
+            loop {
+                // SAFETY: `buffer` is valid for writes of `buffer.len()` bytes
+                let received = unsafe {
+                    libc::recv(
+                        self.socket.as_raw_fd(),
+                        buffer.as_mut_ptr() as *mut libc::c_void,
+                        buffer.len(),
+                        0,
+                    )
+                };
+                if received < 0 {
+                    let error = io::Error::last_os_error();
+                    if error.kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    eprintln!("Proc connector receive failed: {}", error);
+                    return;
+                }
+
+                for pid in parse_exec_events(&buffer[..received as usize]) {
+                    if launches.send(pid).is_err() {
+                        return; // Engine is gone
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+    }
+
+    // Walk the netlink messages in one datagram and collect the thread group
+    // id (the process PID) of every exec event
+    pub fn parse_exec_events(mut data: &[u8]) -> Vec<u32> {
+        let mut pids = Vec::new();
+
+        while let Some(len) = read_u32(data, 0) {
+            let len = len as usize;
+            if len < NLMSG_HDR_LEN || len > data.len() {
+                break;
+            }
+
+            let event = NLMSG_HDR_LEN + CN_MSG_LEN;
+            if read_u32(data, event) == Some(PROC_EVENT_EXEC) {
+                // exec event data: process_pid, process_tgid
+                if let Some(tgid) = read_u32(data, event + PROC_EVENT_DATA_OFFSET + 4) {
+                    pids.push(tgid);
+                }
+            }
+
+            // Messages are 4-byte aligned
+            let advance = (len + 3) & !3;
+            if advance >= data.len() {
+                break;
+            }
+            data = &data[advance..];
+        }
+
+        pids
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::netlink::parse_exec_events;
+
+    // nlmsghdr + cn_msg + proc_event header + the pid/tgid pair, padded to
+    // `len` bytes (rounded up to 4 like the kernel does)
+    fn message(len: usize, what: u32, pid: u32, tgid: u32) -> Vec<u8> {
+        let mut data = vec![0u8; (len + 3) & !3];
+        data[0..4].copy_from_slice(&(len as u32).to_ne_bytes());
+        data[36..40].copy_from_slice(&what.to_ne_bytes());
+        data[52..56].copy_from_slice(&pid.to_ne_bytes());
+        data[56..60].copy_from_slice(&tgid.to_ne_bytes());
+        data
+    }
+
+    #[test]
+    fn exec_events_report_the_thread_group() {
+        let mut data = message(60, 2, 101, 100);
+        // A fork event in between, with a length that needs padding
+        data.extend(message(62, 1, 300, 300));
+        data.extend(message(60, 2, 201, 200));
+        assert_eq!(parse_exec_events(&data), [100, 200]);
+    }
+
+    #[test]
+    fn truncated_messages_are_ignored() {
+        let mut data = message(60, 2, 101, 100);
+        let mut cut = message(60, 2, 201, 200);
+        cut.truncate(40);
+        data.extend(cut);
+        assert_eq!(parse_exec_events(&data), [100]);
+        assert!(parse_exec_events(&[0, 0]).is_empty());
+        // A length shorter than the header stops the walk
+        let mut short = message(60, 2, 1, 1);
+        short[0..4].copy_from_slice(&8u32.to_ne_bytes());
+        assert!(parse_exec_events(&short).is_empty());
+    }
+}
@@ -0,0 +1,416 @@
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::matcher::MatchCriterion;
+use crate::store::Store;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    Timer,
+    Schedule,
+    Permanent,
+}
+
+// When a rule applies. Serialized flat into the rule so the JSON shape matches
+// the frontend types (`type`, `duration`, `startTime`, `days`, ...).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleTiming {
+    #[serde(rename = "type")]
+    pub kind: RuleKind,
+
+    // Timer specific: duration in minutes from `start_time` (unix millis)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<i64>,
+
+    // Schedule specific: days are 0-6 (Sunday-Saturday), times are local wall clock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<Vec<u8>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_hour: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_minute: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_hour: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_minute: Option<u32>,
+}
+
+impl RuleTiming {
+    // Timing of a rule that applies until it is removed
+    #[cfg(test)]
+    pub fn permanent() -> Self {
+        Self {
+            kind: RuleKind::Permanent,
+            duration: None,
+            start_time: None,
+            days: None,
+            start_hour: None,
+            start_minute: None,
+            end_hour: None,
+            end_minute: None,
+        }
+    }
+
+    // Pure evaluation of the timing at `now`. Schedules are evaluated in the
+    // time zone of `now`, so callers pass `Local::now()` for wall-clock rules.
+    pub fn is_active_at<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> bool {
+        match self.kind {
+            RuleKind::Permanent => true,
+            RuleKind::Timer => {
+                let (Some(start), Some(duration)) = (self.start_time, self.duration) else {
+                    return false;
+                };
+                let now_ms = now.timestamp_millis();
+                let end = start + duration * 60 * 1000;
+                now_ms >= start && now_ms <= end
+            }
+            RuleKind::Schedule => self.schedule_active_at(now),
+        }
+    }
+
+    // When a block that is active at `now` lifts. `None` for permanent rules.
+    pub fn ends_at<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        match self.kind {
+            RuleKind::Permanent => None,
+            RuleKind::Timer => {
+                let end = self.start_time? + self.duration? * 60 * 1000;
+                now.timezone().timestamp_millis_opt(end).single()
+            }
+            RuleKind::Schedule => {
+                // The end minute itself is still blocked (see `schedule_active_at`)
+                let local = now.naive_local();
+                let mut end = local.date().and_hms_opt(self.end_hour?, self.end_minute?, 0)?
+                    + Duration::minutes(1);
+                if end <= local {
+                    // Overnight window, evening part
+                    end += Duration::days(1);
+                }
+                // An end inside a DST gap never shows on the clock; the block
+                // lifts when the clock jumps past it
+                let tz = now.timezone();
+                (0..=24 * 60).find_map(|minutes| tz.from_local_datetime(&(end + Duration::minutes(minutes))).earliest())
+            }
+        }
+    }
+
+    fn schedule_active_at<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> bool {
+        let (Some(start_hour), Some(start_minute), Some(end_hour), Some(end_minute)) =
+            (self.start_hour, self.start_minute, self.end_hour, self.end_minute)
+        else {
+            return false;
+        };
+        let days = self.days.as_deref().unwrap_or(&[]);
+
+        // Wall-clock view of `now` in its own time zone. Across a DST change the
+        // local time jumps, so minutes are always read from the converted clock
+        // rather than derived from a UTC offset.
+        let local = now.naive_local();
+        let today = local.weekday().num_days_from_sunday() as u8;
+        let yesterday = (local.date() - Duration::days(1))
+            .weekday()
+            .num_days_from_sunday() as u8;
+        let current_minutes = local.hour() * 60 + local.minute();
+
+        let start_minutes = start_hour * 60 + start_minute;
+        let end_minutes = end_hour * 60 + end_minute;
+
+        if end_minutes < start_minutes {
+            // Overnight (e.g. 10PM to 6AM): the part after midnight belongs to
+            // the day the window started on
+            (days.contains(&today) && current_minutes >= start_minutes)
+                || (days.contains(&yesterday) && current_minutes <= end_minutes)
+        } else {
+            // Normal same-day schedule: active if between start and end
+            days.contains(&today)
+                && current_minutes >= start_minutes
+                && current_minutes <= end_minutes
+        }
+    }
+}
+
+// Common behaviour of every rule type that carries timer/schedule/permanent timing
+pub trait TimedRule {
+    fn enabled(&self) -> bool;
+    fn timing(&self) -> &RuleTiming;
+
+    fn is_active_at<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> bool {
+        self.enabled() && self.timing().is_active_at(now)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockRule {
+    pub id: String,
+    pub app_name: String,
+    pub app_path: String,
+    pub is_active: bool,
+    pub created_at: i64,
+    #[serde(flatten)]
+    pub timing: RuleTiming,
+
+    // How processes are matched. Empty means path/file name/process name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_criteria: Vec<MatchCriterion>,
+    // SHA-256 of `app_path`, recorded when the rule is saved so renamed
+    // copies of the executable are still caught
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe_sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebsiteBlockRule {
+    pub id: String,
+    // The blocked domain, or the name of an imported blocklist
+    pub domain: String,
+    pub is_active: bool,
+    pub created_at: i64,
+    #[serde(flatten)]
+    pub timing: RuleTiming,
+
+    // DNS sinkhole only: block every subdomain too, not just the domain and `www.`
+    #[serde(default = "default_true")]
+    pub include_subdomains: bool,
+
+    // Imported blocklists: every domain of the list, so a list is one rule
+    // instead of thousands
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub domains: Vec<String>,
+}
+
+impl WebsiteBlockRule {
+    // The domains this rule blocks
+    pub fn blocked_domains(&self) -> &[String] {
+        if self.domains.is_empty() {
+            std::slice::from_ref(&self.domain)
+        } else {
+            &self.domains
+        }
+    }
+}
+
+// Blocks part of a site, e.g. `youtube.com/shorts`. Enforced by the browser
+// extension through the native messaging host; see `url_pattern` for the syntax.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlBlockRule {
+    pub id: String,
+    pub pattern: String,
+    pub is_active: bool,
+    pub created_at: i64,
+    #[serde(flatten)]
+    pub timing: RuleTiming,
+}
+
+// Allowlist-only mode: while active, the DNS sinkhole answers for every name
+// except these domains and their subdomains. Several active allowlists allow
+// the union of their domains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllowlistRule {
+    pub id: String,
+    pub name: String,
+    pub domains: Vec<String>,
+    pub is_active: bool,
+    pub created_at: i64,
+    #[serde(flatten)]
+    pub timing: RuleTiming,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl TimedRule for BlockRule {
+    fn enabled(&self) -> bool {
+        self.is_active
+    }
+
+    fn timing(&self) -> &RuleTiming {
+        &self.timing
+    }
+}
+
+impl TimedRule for WebsiteBlockRule {
+    fn enabled(&self) -> bool {
+        self.is_active
+    }
+
+    fn timing(&self) -> &RuleTiming {
+        &self.timing
+    }
+}
+
+impl TimedRule for UrlBlockRule {
+    fn enabled(&self) -> bool {
+        self.is_active
+    }
+
+    fn timing(&self) -> &RuleTiming {
+        &self.timing
+    }
+}
+
+impl TimedRule for AllowlistRule {
+    fn enabled(&self) -> bool {
+        self.is_active
+    }
+
+    fn timing(&self) -> &RuleTiming {
+        &self.timing
+    }
+}
+
+// Keep only the rules that are active at `now`
+pub fn active_at<R: TimedRule + Clone, Tz: TimeZone>(rules: &[R], now: &DateTime<Tz>) -> Vec<R> {
+    rules.iter().filter(|r| r.is_active_at(now)).cloned().collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveRules {
+    pub block_rules: Vec<BlockRule>,
+    pub website_rules: Vec<WebsiteBlockRule>,
+    pub allowlist_rules: Vec<AllowlistRule>,
+}
+
+#[tauri::command]
+pub async fn get_active_rules(store: State<'_, Store>) -> Result<ActiveRules, String> {
+    let now = chrono::Local::now();
+    Ok(store.read(|data| ActiveRules {
+        block_rules: active_at(&data.block_rules, &now),
+        website_rules: active_at(&data.website_rules, &now),
+        allowlist_rules: active_at(&data.allowlist_rules, &now),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use chrono_tz::America::New_York;
+
+    // 2026-10-19 is a Monday
+    const MONDAY: u8 = 1;
+    const EVERY_DAY: [u8; 7] = [0, 1, 2, 3, 4, 5, 6];
+
+    fn schedule(days: &[u8], start: (u32, u32), end: (u32, u32)) -> RuleTiming {
+        RuleTiming {
+            kind: RuleKind::Schedule,
+            duration: None,
+            start_time: None,
+            days: Some(days.to_vec()),
+            start_hour: Some(start.0),
+            start_minute: Some(start.1),
+            end_hour: Some(end.0),
+            end_minute: Some(end.1),
+        }
+    }
+
+    fn new_york(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<chrono_tz::Tz> {
+        New_York.with_ymd_and_hms(2026, month, day, hour, minute, 0).unwrap()
+    }
+
+    // An instant given in UTC, seen on the New York wall clock; unambiguous
+    // even where the local time occurs twice or not at all
+    fn utc_in_new_york(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<chrono_tz::Tz> {
+        Utc.with_ymd_and_hms(2026, month, day, hour, minute, 0).unwrap().with_timezone(&New_York)
+    }
+
+    #[test]
+    fn overnight_window_counts_against_the_day_it_starts() {
+        let timing = schedule(&[MONDAY], (22, 0), (6, 0));
+
+        // Monday evening and the early hours of Tuesday
+        assert!(timing.is_active_at(&new_york(10, 19, 23, 0)));
+        assert!(timing.is_active_at(&new_york(10, 20, 3, 0)));
+        // The early hours of Monday belong to Sunday's window
+        assert!(!timing.is_active_at(&new_york(10, 19, 3, 0)));
+        // Tuesday evening starts a Tuesday window
+        assert!(!timing.is_active_at(&new_york(10, 20, 23, 0)));
+        // Between the two parts
+        assert!(!timing.is_active_at(&new_york(10, 19, 12, 0)));
+    }
+
+    #[test]
+    fn overnight_window_ends_the_next_morning() {
+        let timing = schedule(&[MONDAY], (22, 0), (6, 0));
+
+        assert_eq!(timing.ends_at(&new_york(10, 19, 23, 0)), Some(new_york(10, 20, 6, 1)));
+        assert_eq!(timing.ends_at(&new_york(10, 20, 3, 0)), Some(new_york(10, 20, 6, 1)));
+    }
+
+    #[test]
+    fn start_and_end_minutes_are_both_included() {
+        let timing = schedule(&[MONDAY], (9, 0), (17, 30));
+
+        assert!(!timing.is_active_at(&new_york(10, 19, 8, 59)));
+        assert!(timing.is_active_at(&new_york(10, 19, 9, 0)));
+        assert!(timing.is_active_at(&new_york(10, 19, 17, 30)));
+        assert!(!timing.is_active_at(&new_york(10, 19, 17, 31)));
+
+        let overnight = schedule(&[MONDAY], (22, 0), (6, 0));
+        assert!(overnight.is_active_at(&new_york(10, 19, 22, 0)));
+        assert!(overnight.is_active_at(&new_york(10, 20, 6, 0)));
+        assert!(!overnight.is_active_at(&new_york(10, 20, 6, 1)));
+    }
+
+    #[test]
+    fn daily_window_on_spring_forward_day() {
+        // 2026-03-08: clocks jump from 02:00 EST to 03:00 EDT, so 02:00-03:00
+        // is only its last minute long
+        let timing = schedule(&EVERY_DAY, (2, 0), (3, 0));
+
+        let before = utc_in_new_york(3, 8, 6, 59);
+        assert_eq!((before.hour(), before.minute()), (1, 59));
+        assert!(!timing.is_active_at(&before));
+
+        let after = utc_in_new_york(3, 8, 7, 0);
+        assert_eq!((after.hour(), after.minute()), (3, 0));
+        assert!(timing.is_active_at(&after));
+        assert!(!timing.is_active_at(&utc_in_new_york(3, 8, 7, 1)));
+
+        // A window spanning the gap ends at its wall-clock end
+        let timing = schedule(&EVERY_DAY, (1, 0), (3, 30));
+        assert!(timing.is_active_at(&utc_in_new_york(3, 8, 7, 15)));
+        assert!(!timing.is_active_at(&utc_in_new_york(3, 8, 7, 31)));
+        assert_eq!(timing.ends_at(&utc_in_new_york(3, 8, 6, 30)), Some(utc_in_new_york(3, 8, 7, 31)));
+    }
+
+    #[test]
+    fn window_ending_in_the_gap_ends_when_the_clock_jumps() {
+        // Ends at 02:00 and 02:30 on the wall clock, which 2026-03-08 skips;
+        // both lift at 03:00 EDT
+        let jump = utc_in_new_york(3, 8, 7, 0);
+        for end in [(1, 59), (2, 29)] {
+            let timing = schedule(&EVERY_DAY, (0, 0), end);
+            let during = utc_in_new_york(3, 8, 6, 30);
+            assert!(timing.is_active_at(&during));
+            assert_eq!(timing.ends_at(&during), Some(jump));
+            assert!(!timing.is_active_at(&jump));
+        }
+    }
+
+    #[test]
+    fn daily_window_on_fall_back_day() {
+        // 2026-11-01: clocks go back from 02:00 EDT to 01:00 EST, so 01:xx
+        // happens twice and both are inside the window
+        let timing = schedule(&EVERY_DAY, (1, 0), (1, 59));
+
+        let first = utc_in_new_york(11, 1, 5, 30);
+        let second = utc_in_new_york(11, 1, 6, 30);
+        assert_eq!((first.hour(), second.hour()), (1, 1));
+        assert!(timing.is_active_at(&first));
+        assert!(timing.is_active_at(&second));
+        // 02:00 EST
+        assert!(!timing.is_active_at(&utc_in_new_york(11, 1, 7, 0)));
+        // The end is the first 02:00 on the wall clock
+        assert_eq!(timing.ends_at(&first), Some(utc_in_new_york(11, 1, 7, 0)));
+    }
+}
@@ -0,0 +1,819 @@
+// Optional local forward proxy that enforces website rules on connections
+// that never consult the hosts file or the sinkhole: hardcoded IPs, VPNs and
+// apps with their own resolvers. Nothing is decrypted. HTTPS goes through
+// `CONNECT`, and both the requested host and the server name (SNI) of the
+// TLS ClientHello are checked; plain HTTP requests are checked by URL and
+// `Host` header and get the block page. With Encrypted Client Hello only the
+// outer, public name is visible.
+
+use serde::Serialize;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::process::Command;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::block_page::{self, BlockedBy};
+use crate::loopback_service::{self, HostThrottle, LoopbackServer, Refresh};
+use crate::store::Store;
+
+// How often the proxy is started, stopped or moved to match the settings
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+// Clients get this long to send their request head
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Upper bound on connecting to the destination
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Wait this long for the first bytes through a tunnel; protocols where the
+// server speaks first are passed through unchecked after it
+const FIRST_BYTES_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Refusals of the same host are logged at most this often; browsers retry
+// and open many connections per page
+const LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+// A ClientHello fits in one TLS record
+const MAX_CLIENT_HELLO: usize = 5 + 16 * 1024;
+
+const TLS_HANDSHAKE: u8 = 0x16;
+const HANDSHAKE_CLIENT_HELLO: u8 = 0x01;
+const EXTENSION_SERVER_NAME: u16 = 0;
+const NAME_TYPE_HOST_NAME: u8 = 0;
+
+// Request headers that only concern the connection to the proxy
+const HOP_BY_HOP_HEADERS: &[&str] = &["connection", "proxy-connection", "keep-alive", "proxy-authorization"];
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]))
+}
+
+// Length of the TLS record `data` starts with, if it is a handshake record
+fn tls_record_len(data: &[u8]) -> Option<usize> {
+    if *data.first()? != TLS_HANDSHAKE {
+        return None;
+    }
+    Some(5 + read_u16(data, 3)? as usize)
+}
+
+// Lowercased server name of the TLS ClientHello `data` starts with. `None`
+// if it isn't one, is cut short or names no server.
+pub fn parse_sni(data: &[u8]) -> Option<String> {
+    let record = data.get(5..tls_record_len(data)?)?;
+    if *record.first()? != HANDSHAKE_CLIENT_HELLO {
+        return None;
+    }
+
+    // Handshake header (4), client version (2) and random (32)
+    let mut pos = 4 + 2 + 32;
+    pos += 1 + *record.get(pos)? as usize; // Session id
+    pos += 2 + read_u16(record, pos)? as usize; // Cipher suites
+    pos += 1 + *record.get(pos)? as usize; // Compression methods
+    let extensions_len = read_u16(record, pos)? as usize;
+    let extensions = record.get(pos + 2..pos + 2 + extensions_len)?;
+
+    let mut pos = 0;
+    while pos + 4 <= extensions.len() {
+        let kind = read_u16(extensions, pos)?;
+        let len = read_u16(extensions, pos + 2)? as usize;
+        let body = extensions.get(pos + 4..pos + 4 + len)?;
+        pos += 4 + len;
+        if kind != EXTENSION_SERVER_NAME {
+            continue;
+        }
+
+        // List length, then (type, length, name) entries
+        let mut entry = 2;
+        while entry + 3 <= body.len() {
+            let name_len = read_u16(body, entry + 1)? as usize;
+            let name = body.get(entry + 3..entry + 3 + name_len)?;
+            if body[entry] == NAME_TYPE_HOST_NAME {
+                let name = std::str::from_utf8(name).ok()?;
+                return Some(name.trim_end_matches('.').to_ascii_lowercase());
+            }
+            entry += 3 + name_len;
+        }
+        return None;
+    }
+    None
+}
+
+// A request to the proxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyRequest {
+    // `CONNECT host:port`
+    Tunnel { host: String, port: u16 },
+    // Absolute-form plain HTTP request, e.g. `GET http://host/path`
+    Http {
+        host: String,
+        port: u16,
+        // Origin-form head to send on, one request per connection
+        head: String,
+        // `Host` header, if it names another host than the URL
+        host_header: Option<String>,
+    },
+}
+
+// Host and port of an authority (`example.com:443`, `[::1]:8080`)
+fn split_authority(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default_port,
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    (!host.is_empty()).then_some((host, port))
+}
+
+pub fn parse_request(head: &str) -> Option<ProxyRequest> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let (method, target, version) = (request_line.next()?, request_line.next()?, request_line.next()?);
+    if !version.starts_with("HTTP/") {
+        return None;
+    }
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = split_authority(target, 443)?;
+        return Some(ProxyRequest::Tunnel { host, port });
+    }
+
+    let (scheme, rest) = target.split_once("://")?;
+    if !scheme.eq_ignore_ascii_case("http") {
+        return None;
+    }
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, "/"),
+    };
+    let (host, port) = split_authority(authority.rsplit('@').next()?, 80)?;
+
+    let mut head = format!("{} {} {}\r\n", method, path, version);
+    let mut host_header = None;
+    for line in lines.take_while(|line| !line.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        if name == "host" {
+            host_header = split_authority(value.trim(), port).map(|(host, _)| host);
+        }
+        head.push_str(line);
+        head.push_str("\r\n");
+    }
+    head.push_str("Connection: close\r\n\r\n");
+
+    Some(ProxyRequest::Http {
+        host_header: host_header.filter(|header| *header != host),
+        host,
+        port,
+        head,
+    })
+}
+
+// The request head as text and whatever the client sent after it
+async fn read_head(stream: &mut TcpStream) -> io::Result<(String, Vec<u8>)> {
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 4096];
+    loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = data.split_off(end + 4);
+            return Ok((String::from_utf8_lossy(&data).to_string(), rest));
+        }
+        if data.len() >= MAX_REQUEST_HEAD {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "request head too large"));
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        data.extend_from_slice(&buffer[..read]);
+    }
+}
+
+// First bytes through a tunnel: a whole ClientHello if it is TLS, otherwise
+// the first read
+async fn read_first_bytes(stream: &mut TcpStream, mut data: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut buffer = [0u8; 4096];
+    loop {
+        let complete = match tls_record_len(&data) {
+            Some(len) => data.len() >= len.min(MAX_CLIENT_HELLO),
+            None => !data.is_empty(),
+        };
+        if complete {
+            return Ok(data);
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(data);
+        }
+        data.extend_from_slice(&buffer[..read]);
+    }
+}
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+async fn connect(host: &str, port: u16) -> io::Result<TcpStream> {
+    tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host, port)))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "connect timed out"))?
+}
+
+// What the proxy refuses and where refusals are recorded. `AppGate` uses the
+// app's rules and event log; tests bring their own.
+pub trait Gate: Clone + Send + Sync + 'static {
+    fn blocking_rule(&self, host: &str, now: &chrono::DateTime<chrono::Local>) -> Option<BlockedBy>;
+    fn refused(&self, host: &str, blocked_by: &BlockedBy, via: &str);
+}
+
+#[derive(Clone)]
+struct AppGate {
+    app: AppHandle,
+    proxy: SniProxy,
+}
+
+impl Gate for AppGate {
+    fn blocking_rule(&self, host: &str, now: &chrono::DateTime<chrono::Local>) -> Option<BlockedBy> {
+        block_page::blocking_rule(&self.app, host, now)
+    }
+
+    fn refused(&self, host: &str, blocked_by: &BlockedBy, via: &str) {
+        self.proxy.log_refusal(&self.app, host, blocked_by, via);
+    }
+}
+
+async fn serve_client<G: Gate>(gate: G, mut client: TcpStream) {
+    let Ok(Ok((head, rest))) = tokio::time::timeout(REQUEST_TIMEOUT, read_head(&mut client)).await else {
+        return;
+    };
+    let Some(request) = parse_request(&head) else {
+        let reply = response("400 Bad Request", "Only CONNECT and absolute http:// requests are proxied");
+        let _ = client.write_all(reply.as_bytes()).await;
+        return;
+    };
+    let now = chrono::Local::now();
+
+    match request {
+        ProxyRequest::Tunnel { host, port } => {
+            // Rules name domains; a bare IP is judged by its ClientHello below
+            let blocked = match host.parse::<IpAddr>() {
+                Ok(_) => None,
+                Err(_) => gate.blocking_rule(&host, &now),
+            };
+            if let Some(blocked_by) = blocked {
+                gate.refused(&host, &blocked_by, "CONNECT");
+                let _ = client.write_all(response("403 Forbidden", "Blocked by NEU").as_bytes()).await;
+                return;
+            }
+            // Accept the tunnel before connecting: the ClientHello only comes
+            // after the 200, and a refused site must never see a connection
+            if client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").await.is_err() {
+                return;
+            }
+
+            let first = match tokio::time::timeout(FIRST_BYTES_TIMEOUT, read_first_bytes(&mut client, rest)).await {
+                Ok(Ok(first)) => first,
+                Ok(Err(_)) => return,
+                Err(_) => Vec::new(),
+            };
+            // The ClientHello may name another site than the CONNECT (domain
+            // fronting, or a tunnel to a bare IP)
+            let inner_host = parse_sni(&first).or_else(|| {
+                block_page::parse_request(&String::from_utf8_lossy(&first)).map(|request| request.host)
+            });
+            if let Some(inner_host) = inner_host.filter(|inner| *inner != host) {
+                if let Some(blocked_by) = gate.blocking_rule(&inner_host, &now) {
+                    gate.refused(&inner_host, &blocked_by, "TLS SNI");
+                    return;
+                }
+            }
+
+            // Too late for a 502; closing the tunnel reports the failure
+            let Ok(mut upstream) = connect(&host, port).await else {
+                return;
+            };
+            if upstream.write_all(&first).await.is_ok() {
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+            }
+        }
+        ProxyRequest::Http {
+            host,
+            port,
+            head,
+            host_header,
+        } => {
+            let blocked = [Some(&host), host_header.as_ref()]
+                .into_iter()
+                .flatten()
+                .find_map(|name| Some((name, gate.blocking_rule(name, &now)?)));
+            if let Some((name, blocked_by)) = blocked {
+                gate.refused(name, &blocked_by, "HTTP");
+                let page = block_page::render_page(name, &blocked_by, &now);
+                let reply = format!(
+                    "HTTP/1.1 403 Forbidden\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+                    page.len(),
+                    page
+                );
+                let _ = client.write_all(reply.as_bytes()).await;
+                return;
+            }
+            let Ok(mut upstream) = connect(&host, port).await else {
+                let _ = client.write_all(response("502 Bad Gateway", "Connection failed").as_bytes()).await;
+                return;
+            };
+            if upstream.write_all(head.as_bytes()).await.is_ok() && upstream.write_all(&rest).await.is_ok() {
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+            }
+        }
+    }
+}
+
+// Proxy connections from an already bound listener until the task is
+// dropped. Split from `SniProxy` so it can run with any `Gate`.
+pub async fn serve<G: Gate>(gate: G, listener: TcpListener) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        tokio::spawn(serve_client(gate.clone(), stream));
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SniProxyStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub listen: String,
+    // The system proxy settings point here; `None` if they can't be read on
+    // this desktop
+    pub system_proxy: Option<bool>,
+    // Why the proxy isn't running (bad address, port in use, ...)
+    pub error: Option<String>,
+}
+
+// Local HTTP proxy refusing blocked sites. Started and stopped from the
+// settings; decisions use the same rules as the block page and sinkhole.
+#[derive(Clone)]
+pub struct SniProxy {
+    server: LoopbackServer<()>,
+    refusals: HostThrottle,
+}
+
+impl SniProxy {
+    pub fn new() -> Self {
+        Self {
+            server: LoopbackServer::new("SNI proxy"),
+            refusals: HostThrottle::new(LOG_INTERVAL),
+        }
+    }
+
+    // Spawn the refresh loop. Called once from the `setup` hook.
+    pub fn start(&self, app: AppHandle) {
+        loopback_service::start(self, app, REFRESH_INTERVAL);
+    }
+
+    fn log_refusal(&self, app: &AppHandle, host: &str, blocked_by: &BlockedBy, via: &str) {
+        let Some(held_back) = self.refusals.hit(host) else {
+            return;
+        };
+        let message = format!(
+            "Refused proxy connection to {} via {} ({}){}",
+            host,
+            via,
+            blocked_by.describe(),
+            loopback_service::held_back_note(held_back)
+        );
+        if let Err(e) = app.state::<Store>().log_event("violation", host, message) {
+            eprintln!("Failed to log proxy refusal: {}", e);
+        }
+    }
+}
+
+impl Refresh for SniProxy {
+    // Start, stop or move the proxy to match the settings
+    async fn refresh(&self, app: &AppHandle) {
+        let settings = app.state::<Store>().read(|data| data.settings.clone());
+        let desired = if settings.sni_proxy_enabled {
+            loopback_service::parse_listen(&settings.sni_proxy_listen).map(|listen| Some((listen, ())))
+        } else {
+            Ok(None)
+        };
+        self.server
+            .reconcile(desired, |listen, ()| async move {
+                let listener = loopback_service::bind_tcp(listen).await?;
+                let gate = AppGate {
+                    app: app.clone(),
+                    proxy: self.clone(),
+                };
+                Ok(tauri::async_runtime::spawn(serve(gate, listener)))
+            })
+            .await;
+    }
+}
+
+impl Default for SniProxy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Host and port other programs should use for the proxy
+fn proxy_address(listen: &str) -> Result<(String, u16), String> {
+    let listen: SocketAddr = listen
+        .parse()
+        .map_err(|_| format!("Invalid listen address '{}'", listen))?;
+    let host = if listen.ip().is_unspecified() {
+        "127.0.0.1".to_string()
+    } else {
+        listen.ip().to_string()
+    };
+    Ok((host, listen.port()))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+const WINDOWS_INTERNET_SETTINGS: &str = "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings";
+
+// Whether the desktop's proxy settings send HTTP and HTTPS here
+fn system_proxy_points_at(host: &str, port: u16) -> Option<bool> {
+    if cfg!(target_os = "windows") {
+        let output = run("reg", &["query", WINDOWS_INTERNET_SETTINGS]).ok()?;
+        let value = |name: &str| {
+            output
+                .lines()
+                .find(|line| line.split_whitespace().next() == Some(name))
+                .and_then(|line| line.split_whitespace().last())
+                .map(str::to_string)
+        };
+        Some(value("ProxyEnable").as_deref() == Some("0x1") && value("ProxyServer") == Some(format!("{}:{}", host, port)))
+    } else if cfg!(target_os = "macos") {
+        // Effective settings of the primary network service
+        let output = run("scutil", &["--proxy"]).ok()?;
+        let value = |name: &str| {
+            output
+                .lines()
+                .filter_map(|line| line.split_once(" : "))
+                .find(|(key, _)| key.trim() == name)
+                .map(|(_, value)| value.trim().to_string())
+        };
+        Some(
+            value("HTTPSEnable").as_deref() == Some("1")
+                && value("HTTPSProxy").as_deref() == Some(host)
+                && value("HTTPSPort") == Some(port.to_string()),
+        )
+    } else {
+        // GNOME, and desktops that follow its settings
+        let get = |key: &[&str]| {
+            let mut args = vec!["get"];
+            args.extend_from_slice(key);
+            run("gsettings", &args).ok().map(|value| value.trim().trim_matches('\'').to_string())
+        };
+        let mode = get(&["org.gnome.system.proxy", "mode"])?;
+        Some(
+            mode == "manual"
+                && get(&["org.gnome.system.proxy.https", "host"]).as_deref() == Some(host)
+                && get(&["org.gnome.system.proxy.https", "port"]) == Some(port.to_string()),
+        )
+    }
+}
+
+// Point the desktop's HTTP and HTTPS proxy settings at the proxy, or switch
+// them off again if they point here. Programs read these when they start.
+fn configure_system_proxy(host: &str, port: u16, enabled: bool) -> Result<(), String> {
+    let address = format!("{}:{}", host, port);
+    let port = port.to_string();
+
+    if cfg!(target_os = "windows") {
+        let enable = if enabled { "1" } else { "0" };
+        run("reg", &["add", WINDOWS_INTERNET_SETTINGS, "/v", "ProxyEnable", "/t", "REG_DWORD", "/d", enable, "/f"])?;
+        if enabled {
+            run("reg", &["add", WINDOWS_INTERNET_SETTINGS, "/v", "ProxyServer", "/t", "REG_SZ", "/d", &address, "/f"])?;
+        }
+    } else if cfg!(target_os = "macos") {
+        let services = run("networksetup", &["-listallnetworkservices"])?;
+        // The first line is a legend; disabled services start with `*`
+        for service in services.lines().skip(1).filter(|line| !line.starts_with('*')) {
+            if enabled {
+                run("networksetup", &["-setwebproxy", service, host, &port])?;
+                run("networksetup", &["-setsecurewebproxy", service, host, &port])?;
+            } else {
+                run("networksetup", &["-setwebproxystate", service, "off"])?;
+                run("networksetup", &["-setsecurewebproxystate", service, "off"])?;
+            }
+        }
+    } else {
+        let set = |schema: &str, key: &str, value: &str| run("gsettings", &["set", schema, key, value]);
+        if enabled {
+            for schema in ["org.gnome.system.proxy.http", "org.gnome.system.proxy.https"] {
+                set(schema, "host", host)?;
+                set(schema, "port", &port)?;
+            }
+            set("org.gnome.system.proxy", "mode", "manual")?;
+        } else {
+            set("org.gnome.system.proxy", "mode", "none")?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_sni_proxy_status(
+    app: AppHandle,
+    proxy: State<'_, SniProxy>,
+    store: State<'_, Store>,
+) -> Result<SniProxyStatus, String> {
+    // Apply settings saved since the last refresh before reporting
+    proxy.refresh(&app).await;
+
+    let settings = store.read(|data| data.settings.clone());
+    let running = proxy.server.is_running().await;
+    let listen = settings.sni_proxy_listen.clone();
+    let system_proxy = tauri::async_runtime::spawn_blocking(move || {
+        let (host, port) = proxy_address(&listen).ok()?;
+        system_proxy_points_at(&host, port)
+    })
+    .await
+    .map_err(|e| format!("System proxy check failed: {}", e))?;
+    Ok(SniProxyStatus {
+        enabled: settings.sni_proxy_enabled,
+        running,
+        listen: settings.sni_proxy_listen,
+        system_proxy,
+        error: proxy.server.error(),
+    })
+}
+
+// Make the proxy the desktop's HTTP(S) proxy, or stop using it
+#[tauri::command]
+pub async fn set_system_proxy(enabled: bool, store: State<'_, Store>) -> Result<(), String> {
+    let listen = store.read(|data| data.settings.sni_proxy_listen.clone());
+    let (host, port) = proxy_address(&listen)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if !enabled && system_proxy_points_at(&host, port) == Some(false) {
+            // Someone else's proxy; leave it
+            return Ok(());
+        }
+        configure_system_proxy(&host, port, enabled)
+    })
+    .await
+    .map_err(|e| format!("System proxy task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{RuleTiming, WebsiteBlockRule};
+    use crate::store::StoreData;
+    use std::sync::{Arc, Mutex};
+
+    // ClientHello recorded from curl for https://blocked.example
+    const CLIENT_HELLO: &[u8] = include_bytes!("../tests/fixtures/client_hello.bin");
+
+    // The fixture with its server name replaced by one of the same length
+    fn client_hello_for(name: &str) -> Vec<u8> {
+        let pos = sni_position();
+        let mut hello = CLIENT_HELLO.to_vec();
+        hello[pos..pos + name.len()].copy_from_slice(name.as_bytes());
+        hello
+    }
+
+    fn sni_position() -> usize {
+        let name = b"blocked.example";
+        CLIENT_HELLO.windows(name.len()).position(|w| w == name).unwrap()
+    }
+
+    #[test]
+    fn parses_sni_of_a_recorded_client_hello() {
+        assert_eq!(parse_sni(CLIENT_HELLO).as_deref(), Some("blocked.example"));
+        // Whatever follows the record doesn't matter
+        let mut longer = CLIENT_HELLO.to_vec();
+        longer.extend_from_slice(b"more data");
+        assert_eq!(parse_sni(&longer).as_deref(), Some("blocked.example"));
+    }
+
+    #[test]
+    fn truncated_client_hello_has_no_sni() {
+        for len in [0, 3, 5, 50, sni_position(), CLIENT_HELLO.len() - 1] {
+            assert_eq!(parse_sni(&CLIENT_HELLO[..len]), None, "cut at {}", len);
+        }
+    }
+
+    #[test]
+    fn client_hello_without_server_name_has_no_sni() {
+        // Extension type, extension length, list length, name type, name length
+        let extension = sni_position() - 9;
+        assert_eq!(&CLIENT_HELLO[extension..extension + 2], &[0, 0]);
+        let mut hello = CLIENT_HELLO.to_vec();
+        // An unassigned extension type
+        hello[extension..extension + 2].copy_from_slice(&[0xfe, 0xfe]);
+        assert_eq!(parse_sni(&hello), None);
+
+        assert_eq!(parse_sni(b"GET / HTTP/1.1\r\nHost: blocked.example\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn parses_connect_to_ipv6_authority() {
+        assert_eq!(
+            parse_request("CONNECT [2001:db8::1]:8443 HTTP/1.1\r\nHost: [2001:db8::1]:8443\r\n\r\n"),
+            Some(ProxyRequest::Tunnel {
+                host: "2001:db8::1".to_string(),
+                port: 8443,
+            })
+        );
+        assert_eq!(
+            parse_request("CONNECT [::1] HTTP/1.1\r\n\r\n"),
+            Some(ProxyRequest::Tunnel {
+                host: "::1".to_string(),
+                port: 443,
+            })
+        );
+        assert_eq!(parse_request("CONNECT [::1:443 HTTP/1.1\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn parses_absolute_form_get_with_mismatched_host() {
+        let head = "GET http://allowed.example/watch?v=1 HTTP/1.1\r\nHost: Blocked.Example\r\nProxy-Connection: keep-alive\r\nAccept: */*\r\n\r\n";
+        assert_eq!(
+            parse_request(head),
+            Some(ProxyRequest::Http {
+                host: "allowed.example".to_string(),
+                port: 80,
+                head: "GET /watch?v=1 HTTP/1.1\r\nHost: Blocked.Example\r\nAccept: */*\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+                host_header: Some("blocked.example".to_string()),
+            })
+        );
+
+        // A matching Host header isn't reported
+        let head = "GET http://allowed.example:8080 HTTP/1.1\r\nHost: allowed.example:8080\r\n\r\n";
+        let Some(ProxyRequest::Http { port, host_header, .. }) = parse_request(head) else {
+            panic!("not parsed as HTTP");
+        };
+        assert_eq!((port, host_header), (8080, None));
+    }
+
+    // Blocks blocked.example and its subdomains, and remembers refusals
+    #[derive(Clone)]
+    struct TestGate {
+        data: Arc<StoreData>,
+        refused: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl TestGate {
+        fn new() -> Self {
+            let mut data = StoreData::default();
+            data.website_rules.push(WebsiteBlockRule {
+                id: "rule-1".to_string(),
+                domain: "blocked.example".to_string(),
+                is_active: true,
+                created_at: 0,
+                timing: RuleTiming::permanent(),
+                include_subdomains: true,
+                domains: Vec::new(),
+            });
+            Self {
+                data: Arc::new(data),
+                refused: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        fn refused(&self) -> Vec<(String, String)> {
+            self.refused.lock().unwrap().clone()
+        }
+    }
+
+    impl Gate for TestGate {
+        fn blocking_rule(&self, host: &str, now: &chrono::DateTime<chrono::Local>) -> Option<BlockedBy> {
+            block_page::blocking_rule_in(&self.data, host, now)
+        }
+
+        fn refused(&self, host: &str, _blocked_by: &BlockedBy, via: &str) {
+            self.refused.lock().unwrap().push((host.to_string(), via.to_string()));
+        }
+    }
+
+    async fn start_proxy(gate: TestGate) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(gate, listener));
+        addr
+    }
+
+    // A destination that reports everything it receives once the proxy
+    // closes the connection. The receiver errors if nothing ever connected.
+    async fn start_upstream() -> (SocketAddr, tokio::sync::oneshot::Receiver<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            let _ = stream.read_to_end(&mut received).await;
+            let _ = sender.send(received);
+        });
+        (addr, receiver)
+    }
+
+    async fn read_until_closed(stream: &mut TcpStream) -> Vec<u8> {
+        let mut data = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut data))
+            .await
+            .expect("connection not closed")
+            .unwrap();
+        data
+    }
+
+    // Opens a tunnel through the proxy to `upstream`
+    async fn connect_through(proxy: SocketAddr, upstream: SocketAddr) -> TcpStream {
+        let mut client = TcpStream::connect(proxy).await.unwrap();
+        let request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n\r\n", upstream, upstream);
+        client.write_all(request.as_bytes()).await.unwrap();
+        let mut reply = [0u8; 64];
+        let len = client.read(&mut reply).await.unwrap();
+        assert!(reply[..len].starts_with(b"HTTP/1.1 200"));
+        client
+    }
+
+    #[tokio::test]
+    async fn refuses_blocked_hosts() {
+        let gate = TestGate::new();
+        let proxy = start_proxy(gate.clone()).await;
+
+        // CONNECT to a blocked name
+        let mut client = TcpStream::connect(proxy).await.unwrap();
+        client
+            .write_all(b"CONNECT www.blocked.example:443 HTTP/1.1\r\nHost: www.blocked.example:443\r\n\r\n")
+            .await
+            .unwrap();
+        assert!(read_until_closed(&mut client).await.starts_with(b"HTTP/1.1 403"));
+
+        // A tunnel to an allowed IP whose ClientHello names a blocked site is
+        // closed without ever connecting to the destination
+        let (upstream, received) = start_upstream().await;
+        let mut client = connect_through(proxy, upstream).await;
+        client.write_all(CLIENT_HELLO).await.unwrap();
+        assert_eq!(read_until_closed(&mut client).await, b"");
+        let connected = tokio::time::timeout(Duration::from_millis(200), received).await;
+        assert!(connected.is_err(), "the destination was connected to");
+
+        assert_eq!(
+            gate.refused(),
+            vec![
+                ("www.blocked.example".to_string(), "CONNECT".to_string()),
+                ("blocked.example".to_string(), "TLS SNI".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn tunnels_allowed_client_hellos() {
+        let gate = TestGate::new();
+        let proxy = start_proxy(gate.clone()).await;
+
+        let (upstream, received) = start_upstream().await;
+        let mut client = connect_through(proxy, upstream).await;
+        let hello = client_hello_for("allowed.example");
+        client.write_all(&hello).await.unwrap();
+        client.shutdown().await.unwrap();
+        assert_eq!(received.await.unwrap(), hello);
+        assert!(gate.refused().is_empty());
+    }
+}
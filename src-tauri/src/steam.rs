@@ -0,0 +1,304 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{vdf, AppInfo};
+
+// Steam tools installed like games but never worth blocking: Steamworks
+// redistributables and the Linux runtimes Proton runs in
+const TOOL_APP_IDS: &[&str] = &["228980", "1070560", "1391110", "1628350", "1493710"];
+
+// Executables that ship next to games but aren't the game
+const NON_GAME_EXECUTABLES: &[&str] = &[
+    "uninstall",
+    "unins000",
+    "setup",
+    "installer",
+    "redist",
+    "vcredist",
+    "vc_redist",
+    "dxsetup",
+    "crashhandler",
+    "crashreport",
+    "crashpad",
+    "launcherhelper",
+];
+
+// How deep below a game's install dir to look for its executable
+const MAX_EXECUTABLE_DEPTH: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct SteamGame {
+    pub app_id: String,
+    pub name: String,
+    pub install_dir: PathBuf,
+}
+
+// Installed games from every Steam library, each with its main executable
+pub fn installed_apps() -> Vec<AppInfo> {
+    installed_games()
+        .into_iter()
+        .filter_map(|game| {
+            let executable = main_executable(&game)?;
+            Some(AppInfo {
+                name: game.name,
+                path: executable.to_string_lossy().to_string(),
+                pid: None,
+                icon: None,
+            })
+        })
+        .collect()
+}
+
+pub fn installed_games() -> Vec<SteamGame> {
+    let mut games = Vec::new();
+    let mut seen = HashSet::new();
+
+    for library in steam_roots().iter().flat_map(|root| library_folders(root)) {
+        let steamapps = library.join("steamapps");
+        let Ok(entries) = fs::read_dir(&steamapps) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !file_name.starts_with("appmanifest_") || !file_name.ends_with(".acf") {
+                continue;
+            }
+
+            match read_manifest(&entry.path(), &steamapps) {
+                Ok(Some(game)) if seen.insert(game.app_id.clone()) => games.push(game),
+                Ok(_) => {}
+                Err(e) => eprintln!("Skipping Steam manifest {}: {}", entry.path().display(), e),
+            }
+        }
+    }
+
+    games
+}
+
+// Steam installations on this machine. Several of the Linux paths are usually
+// symlinks to the same directory, so they are deduplicated after resolving.
+fn steam_roots() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Ok(program_files) = std::env::var("ProgramFiles(x86)") {
+            candidates.push(Path::new(&program_files).join("Steam"));
+        }
+        candidates.push(PathBuf::from("C:\\Program Files (x86)\\Steam"));
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        candidates.push(home.join(".steam/steam"));
+        candidates.push(home.join(".steam/root"));
+        candidates.push(home.join(".local/share/Steam"));
+        // Flatpak
+        candidates.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        candidates.push(home.join(".var/app/com.valvesoftware.Steam/.steam/steam"));
+    }
+
+    #[cfg(target_os = "macos")]
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        candidates.push(home.join("Library/Application Support/Steam"));
+    }
+
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .filter(|path| path.join("steamapps").is_dir())
+        .filter(|path| seen.insert(path.clone()))
+        .collect()
+}
+
+// The Steam root itself plus every library listed in libraryfolders.vdf
+fn library_folders(root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![root.to_path_buf()];
+
+    let config = root.join("steamapps").join("libraryfolders.vdf");
+    let Ok(content) = fs::read_to_string(&config) else {
+        return libraries;
+    };
+    let folders = match vdf::parse(&content) {
+        Ok((_, folders)) => folders,
+        Err(e) => {
+            eprintln!("Failed to parse {}: {}", config.display(), e);
+            return libraries;
+        }
+    };
+
+    for (key, value) in folders.entries() {
+        // Libraries are numbered; other keys (e.g. "contentstatsid") aren't
+        if !key.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        // Current format: "0" { "path" "..." }; older clients: "1" "D:\\Library"
+        let path = match value {
+            vdf::Value::String(path) => Some(path.as_str()),
+            vdf::Value::Object(_) => value.get_str("path"),
+        };
+        if let Some(path) = path {
+            let path = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+            if !libraries.contains(&path) {
+                libraries.push(path);
+            }
+        }
+    }
+
+    libraries
+}
+
+// A game from its appmanifest_<appid>.acf. Tools and games that aren't fully
+// installed yet are skipped.
+fn read_manifest(path: &Path, steamapps: &Path) -> Result<Option<SteamGame>, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let (_, state) = vdf::parse(&content)?;
+
+    let (Some(app_id), Some(name), Some(install_dir)) = (
+        state.get_str("appid"),
+        state.get_str("name"),
+        state.get_str("installdir"),
+    ) else {
+        return Err("missing appid, name or installdir".to_string());
+    };
+
+    if TOOL_APP_IDS.contains(&app_id) || name.starts_with("Proton ") {
+        return Ok(None);
+    }
+
+    let install_dir = steamapps.join("common").join(install_dir);
+    if !install_dir.is_dir() {
+        return Ok(None);
+    }
+
+    Ok(Some(SteamGame {
+        app_id: app_id.to_string(),
+        name: name.to_string(),
+        install_dir,
+    }))
+}
+
+// Best guess at the binary Steam launches: an executable whose name resembles
+// the game's, preferring shallower and then larger files
+fn main_executable(game: &SteamGame) -> Option<PathBuf> {
+    let wanted = simplify(&game.name);
+
+    let mut candidates = Vec::new();
+    collect_executables(&game.install_dir, 0, &mut candidates);
+
+    candidates
+        .into_iter()
+        .max_by_key(|(path, depth, size)| {
+            let stem = simplify(&path.file_stem().unwrap_or_default().to_string_lossy());
+            let similar = !stem.is_empty() && (wanted.contains(&stem) || stem.contains(&wanted));
+            (similar, std::cmp::Reverse(*depth), *size)
+        })
+        .map(|(path, _, _)| path)
+}
+
+fn collect_executables(dir: &Path, depth: usize, found: &mut Vec<(PathBuf, usize, u64)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            if depth < MAX_EXECUTABLE_DEPTH {
+                collect_executables(&path, depth + 1, found);
+            }
+        } else if metadata.is_file() && is_game_executable(&path, &metadata) {
+            found.push((path, depth, metadata.len()));
+        }
+    }
+}
+
+// Windows games (also run through Proton on Linux) are `.exe`; native Linux
+// games are ELF binaries, often named `*.x86_64`
+fn is_game_executable(path: &Path, metadata: &fs::Metadata) -> bool {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    if NON_GAME_EXECUTABLES.iter().any(|skip| name.contains(skip)) {
+        return false;
+    }
+    if name.ends_with(".exe") {
+        return true;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::io::Read;
+        use std::os::unix::fs::PermissionsExt;
+
+        // Shared libraries are ELF and often executable too
+        if name.contains(".so") || metadata.permissions().mode() & 0o111 == 0 {
+            return false;
+        }
+        let mut magic = [0u8; 4];
+        fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .is_ok_and(|_| magic == *b"\x7fELF")
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+// Lowercase letters and digits only, so "Half-Life 2" matches "HalfLife2.exe"
+fn simplify(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steam_root(name: &str, libraryfolders: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("neu-steam-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("steamapps")).unwrap();
+        fs::write(root.join("steamapps").join("libraryfolders.vdf"), libraryfolders).unwrap();
+        root
+    }
+
+    #[test]
+    fn libraries_from_both_libraryfolders_formats() {
+        let old = steam_root(
+            "old",
+            r#""LibraryFolders" { "ContentStatsID" "-1" "1" "/mnt/old/SteamLibrary" }"#,
+        );
+        assert_eq!(library_folders(&old), [old.clone(), PathBuf::from("/mnt/old/SteamLibrary")]);
+
+        // The root lists itself as library "0" in the current format
+        let new = steam_root("new", "");
+        let content = format!(
+            r#""libraryfolders" {{ "0" {{ "path" "{}" }} "1" {{ "path" "/mnt/new/SteamLibrary" }} }}"#,
+            new.display()
+        );
+        fs::write(new.join("steamapps").join("libraryfolders.vdf"), content).unwrap();
+        let canonical = fs::canonicalize(&new).unwrap();
+        assert_eq!(
+            library_folders(&canonical),
+            [canonical.clone(), PathBuf::from("/mnt/new/SteamLibrary")]
+        );
+
+        fs::remove_dir_all(&old).unwrap();
+        fs::remove_dir_all(&new).unwrap();
+    }
+}
@@ -0,0 +1,618 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::domain;
+use crate::rules::{AllowlistRule, BlockRule, UrlBlockRule, WebsiteBlockRule};
+use crate::url_pattern::UrlPattern;
+use crate::website_backend::BackendKind;
+
+// File name of the store inside the app data dir
+pub const STORE_FILE_NAME: &str = "neu_store.json";
+
+// Bump this and add a step to `migrate_schema` whenever the on-disk layout changes
+const SCHEMA_VERSION: u32 = 1;
+
+// Keep only the most recent events (same limit the frontend used)
+const MAX_EVENTS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pin_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    pub webhook_enabled: bool,
+    pub send_block_notifications: bool,
+    pub send_unblock_notifications: bool,
+    pub send_killswitch_notifications: bool,
+    pub is_setup_complete: bool,
+    pub blocking_enabled: bool,
+    pub website_blocking_enabled: bool,
+    // Time a blocked app gets to exit after SIGTERM before it is SIGKILLed
+    pub kill_grace_period_ms: u64,
+    pub send_tamper_notifications: bool,
+    // Built-in DNS resolver that sinkholes blocked domains and their subdomains
+    pub dns_sinkhole_enabled: bool,
+    pub dns_sinkhole_listen: String,
+    pub dns_upstream: String,
+    // Where website blocks are written (hosts file, dnsmasq, ...)
+    pub website_block_backend: BackendKind,
+    // Loopback HTTP server showing a block page for blocked domains
+    pub block_page_enabled: bool,
+    pub block_page_listen: String,
+    // Add known DNS-over-HTTPS providers to the block set so browsers fall
+    // back to the system resolver
+    pub block_doh_providers: bool,
+    // Write website and URL rules into Firefox and Chromium enterprise policies
+    pub browser_policies_enabled: bool,
+    // Companion extension the policies force-install: its Chrome Web Store id
+    // and its Firefox add-on id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chromium_extension_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub firefox_extension_id: Option<String>,
+    // Local HTTP(S) proxy that refuses blocked sites by CONNECT host and TLS SNI
+    pub sni_proxy_enabled: bool,
+    pub sni_proxy_listen: String,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            pin_hash: None,
+            webhook_url: None,
+            webhook_enabled: false,
+            send_block_notifications: true,
+            send_unblock_notifications: true,
+            send_killswitch_notifications: true,
+            is_setup_complete: false,
+            blocking_enabled: false,
+            website_blocking_enabled: true, // Enabled by default
+            kill_grace_period_ms: 3000,
+            send_tamper_notifications: true,
+            dns_sinkhole_enabled: false,
+            dns_sinkhole_listen: "127.0.0.1:53".to_string(),
+            dns_upstream: "1.1.1.1:53".to_string(),
+            website_block_backend: BackendKind::Hosts,
+            block_page_enabled: false,
+            block_page_listen: "127.0.0.1:80".to_string(),
+            block_doh_providers: true,
+            browser_policies_enabled: false,
+            chromium_extension_id: None,
+            firefox_extension_id: None,
+            sni_proxy_enabled: false,
+            sni_proxy_listen: "127.0.0.1:3128".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub target: String,
+    pub timestamp: i64,
+    pub message: String,
+}
+
+impl BlockEvent {
+    // A new event stamped with the current time
+    pub fn new(event_type: &str, target: &str, message: String) -> Self {
+        use rand_core::RngCore;
+
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        Self {
+            id: format!("{}-{:x}", timestamp, rand_core::OsRng.next_u32()),
+            event_type: event_type.to_string(),
+            target: target.to_string(),
+            timestamp,
+            message,
+        }
+    }
+}
+
+// Everything the store persists
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StoreData {
+    pub schema_version: u32,
+    pub migrated_from_local_storage: bool,
+    pub settings: AppSettings,
+    pub block_rules: Vec<BlockRule>,
+    pub website_rules: Vec<WebsiteBlockRule>,
+    pub url_rules: Vec<UrlBlockRule>,
+    pub allowlist_rules: Vec<AllowlistRule>,
+    pub events: Vec<BlockEvent>,
+}
+
+// Values read from the legacy localStorage keys (`neu_settings`,
+// `neu_block_rules`, `neu_website_rules`, `neu_events`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyStorage {
+    pub settings: Option<AppSettings>,
+    #[serde(default)]
+    pub block_rules: Vec<BlockRule>,
+    #[serde(default)]
+    pub website_rules: Vec<WebsiteBlockRule>,
+    #[serde(default)]
+    pub events: Vec<BlockEvent>,
+}
+
+// JSON file store in the app data dir. All reads go through the in-memory
+// copy; every mutation is written back to disk before returning.
+pub struct Store {
+    path: PathBuf,
+    data: Mutex<StoreData>,
+}
+
+impl Store {
+    // A store file that can't be parsed is set aside and replaced with
+    // defaults, so a corrupt file doesn't keep the app from starting. The
+    // reset is logged, since wiping the store also wipes every rule.
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let (data, reset) = match read_optional(&path)? {
+            Some(content) => match parse(&content) {
+                Ok(data) => (data, None),
+                // Written by a newer build; starting over would throw its
+                // rules away once that build runs again
+                Err(e) if is_newer_schema(&content) => return Err(e),
+                Err(e) => {
+                    let backup = set_aside(&path)?;
+                    eprintln!("{}; moved it to {} and starting over", e, backup.display());
+                    // The webview's localStorage holds older data than the
+                    // file did; importing it now would bring back stale rules
+                    let data = StoreData {
+                        migrated_from_local_storage: true,
+                        ..empty()
+                    };
+                    (data, Some((e, backup)))
+                }
+            },
+            None => (empty(), None),
+        };
+
+        let store = Self {
+            path,
+            data: Mutex::new(data),
+        };
+        store.persist(&store.data.lock().unwrap())?;
+        if let Some((error, backup)) = reset {
+            let message = format!(
+                "{}. The file was saved as {} and all settings and rules were reset.",
+                error,
+                backup.display()
+            );
+            if let Err(e) = store.log_event("violation", "Store file", message) {
+                eprintln!("Failed to log store reset: {}", e);
+            }
+        }
+        Ok(store)
+    }
+
+    // Parse the store file without taking ownership of it. For other
+    // processes (the native messaging host) that only need to read rules.
+    pub fn read_snapshot(path: &Path) -> Result<StoreData, String> {
+        match read_optional(path)? {
+            Some(content) => parse(&content),
+            None => Ok(empty()),
+        }
+    }
+
+    pub fn read<T>(&self, f: impl FnOnce(&StoreData) -> T) -> T {
+        f(&self.data.lock().unwrap())
+    }
+
+    // Apply a change and write it to disk. The in-memory copy is only updated
+    // if the write succeeds.
+    pub fn update<T>(&self, f: impl FnOnce(&mut StoreData) -> T) -> Result<T, String> {
+        let mut data = self.data.lock().unwrap();
+        let mut updated = data.clone();
+        let result = f(&mut updated);
+        self.persist(&updated)?;
+        *data = updated;
+        Ok(result)
+    }
+
+    // Record an event raised on the Rust side (same shape the frontend logs)
+    pub fn log_event(&self, event_type: &str, target: &str, message: String) -> Result<(), String> {
+        let event = BlockEvent::new(event_type, target, message);
+        self.update(|data| push_event(data, event))
+    }
+
+    // Record events that happened elsewhere (e.g. in the native messaging host)
+    pub fn append_events(&self, events: Vec<BlockEvent>) -> Result<(), String> {
+        self.update(|data| {
+            for event in events {
+                push_event(data, event);
+            }
+        })
+    }
+
+    fn persist(&self, data: &StoreData) -> Result<(), String> {
+        write_atomically(&self.path, data)
+    }
+}
+
+// Contents of the store file, or `None` if there isn't one yet
+fn read_optional(path: &Path) -> Result<Option<String>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read store file: {}", e)),
+    }
+}
+
+fn parse(content: &str) -> Result<StoreData, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse store file: {}", e))?;
+    migrate_schema(value)
+}
+
+fn empty() -> StoreData {
+    StoreData {
+        schema_version: SCHEMA_VERSION,
+        ..Default::default()
+    }
+}
+
+// Rename an unusable store file to a timestamped sibling and return its path
+fn set_aside(path: &Path) -> Result<PathBuf, String> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".invalid-{}", chrono::Local::now().format("%Y%m%dT%H%M%S")));
+    let backup = path.with_file_name(name);
+    fs::rename(path, &backup).map_err(|e| format!("Failed to move aside the invalid store file: {}", e))?;
+    Ok(backup)
+}
+
+fn is_newer_schema(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|value| value.get("schemaVersion")?.as_u64())
+        .is_some_and(|version| version > SCHEMA_VERSION as u64)
+}
+
+// Upgrade an on-disk store of any known schema version to the current layout
+fn migrate_schema(mut value: serde_json::Value) -> Result<StoreData, String> {
+    let version = value
+        .get("schemaVersion")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Store file has schema version {} but this build only understands up to {}",
+            version, SCHEMA_VERSION
+        ));
+    }
+
+    // Version 0: written before the version field existed, same shape as v1
+    if version == 0 {
+        value["schemaVersion"] = SCHEMA_VERSION.into();
+    }
+
+    serde_json::from_value(value).map_err(|e| format!("Invalid store file: {}", e))
+}
+
+// Write to a sibling temp file and rename it over the original so a crash
+// never leaves a half-written store
+fn write_atomically(path: &Path, data: &StoreData) -> Result<(), String> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+
+    let json = serde_json::to_vec_pretty(data)
+        .map_err(|e| format!("Failed to serialize store: {}", e))?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp_path)
+        .map_err(|e| format!("Failed to create store temp file: {}", e))?;
+    file.write_all(&json)
+        .map_err(|e| format!("Failed to write store: {}", e))?;
+    file.sync_all()
+        .map_err(|e| format!("Failed to sync store: {}", e))?;
+
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to replace store file: {}", e))
+}
+
+fn push_event(data: &mut StoreData, event: BlockEvent) {
+    data.events.insert(0, event); // Newest first
+    data.events.truncate(MAX_EVENTS);
+}
+
+#[tauri::command]
+pub async fn get_rules(store: State<'_, Store>) -> Result<Vec<BlockRule>, String> {
+    Ok(store.read(|data| data.block_rules.clone()))
+}
+
+// Record the executable hash so renaming the binary doesn't evade the rule
+fn record_exe_hash(rule: &mut BlockRule) {
+    if rule.exe_sha256.is_none() && !rule.app_path.is_empty() {
+        rule.exe_sha256 = crate::matcher::sha256_file(Path::new(&rule.app_path)).ok();
+    }
+}
+
+// Insert a rule, or replace the existing rule with the same id
+#[tauri::command]
+pub async fn save_rule(mut rule: BlockRule, store: State<'_, Store>) -> Result<(), String> {
+    record_exe_hash(&mut rule);
+
+    store.update(|data| match data.block_rules.iter_mut().find(|r| r.id == rule.id) {
+        Some(existing) => *existing = rule,
+        None => data.block_rules.push(rule),
+    })
+}
+
+#[tauri::command]
+pub async fn delete_rule(rule_id: String, store: State<'_, Store>) -> Result<bool, String> {
+    store.update(|data| {
+        let before = data.block_rules.len();
+        data.block_rules.retain(|r| r.id != rule_id);
+        data.block_rules.len() != before
+    })
+}
+
+#[tauri::command]
+pub async fn get_website_rules(store: State<'_, Store>) -> Result<Vec<WebsiteBlockRule>, String> {
+    Ok(store.read(|data| data.website_rules.clone()))
+}
+
+#[tauri::command]
+pub async fn save_website_rule(mut rule: WebsiteBlockRule, store: State<'_, Store>) -> Result<(), String> {
+    if rule.domains.is_empty() {
+        rule.domain = domain::normalize(&rule.domain).map_err(|e| e.to_string())?;
+    } else {
+        // An imported blocklist; `domain` is its name
+        let (domains, invalid) = domain::normalize_all(&rule.domains);
+        if let Some(e) = invalid.first() {
+            return Err(e.to_string());
+        }
+        rule.domains = domains;
+    }
+    store.update(|data| match data.website_rules.iter_mut().find(|r| r.id == rule.id) {
+        Some(existing) => *existing = rule,
+        None => data.website_rules.push(rule),
+    })
+}
+
+#[tauri::command]
+pub async fn delete_website_rule(rule_id: String, store: State<'_, Store>) -> Result<bool, String> {
+    store.update(|data| {
+        let before = data.website_rules.len();
+        data.website_rules.retain(|r| r.id != rule_id);
+        data.website_rules.len() != before
+    })
+}
+
+#[tauri::command]
+pub async fn get_url_rules(store: State<'_, Store>) -> Result<Vec<UrlBlockRule>, String> {
+    Ok(store.read(|data| data.url_rules.clone()))
+}
+
+#[tauri::command]
+pub async fn save_url_rule(rule: UrlBlockRule, store: State<'_, Store>) -> Result<(), String> {
+    UrlPattern::parse(&rule.pattern)?;
+    store.update(|data| match data.url_rules.iter_mut().find(|r| r.id == rule.id) {
+        Some(existing) => *existing = rule,
+        None => data.url_rules.push(rule),
+    })
+}
+
+#[tauri::command]
+pub async fn delete_url_rule(rule_id: String, store: State<'_, Store>) -> Result<bool, String> {
+    store.update(|data| {
+        let before = data.url_rules.len();
+        data.url_rules.retain(|r| r.id != rule_id);
+        data.url_rules.len() != before
+    })
+}
+
+#[tauri::command]
+pub async fn get_allowlist_rules(store: State<'_, Store>) -> Result<Vec<AllowlistRule>, String> {
+    Ok(store.read(|data| data.allowlist_rules.clone()))
+}
+
+#[tauri::command]
+pub async fn save_allowlist_rule(mut rule: AllowlistRule, store: State<'_, Store>) -> Result<(), String> {
+    let (domains, invalid) = domain::normalize_all(&rule.domains);
+    if let Some(e) = invalid.first() {
+        return Err(e.to_string());
+    }
+    // An empty allowlist would take every site offline
+    if domains.is_empty() {
+        return Err("An allowlist needs at least one domain".to_string());
+    }
+    rule.domains = domains;
+    store.update(|data| match data.allowlist_rules.iter_mut().find(|r| r.id == rule.id) {
+        Some(existing) => *existing = rule,
+        None => data.allowlist_rules.push(rule),
+    })
+}
+
+#[tauri::command]
+pub async fn delete_allowlist_rule(rule_id: String, store: State<'_, Store>) -> Result<bool, String> {
+    store.update(|data| {
+        let before = data.allowlist_rules.len();
+        data.allowlist_rules.retain(|r| r.id != rule_id);
+        data.allowlist_rules.len() != before
+    })
+}
+
+#[tauri::command]
+pub async fn get_settings(store: State<'_, Store>) -> Result<AppSettings, String> {
+    Ok(store.read(|data| data.settings.clone()))
+}
+
+#[tauri::command]
+pub async fn save_settings(settings: AppSettings, store: State<'_, Store>) -> Result<(), String> {
+    store.update(|data| data.settings = settings)
+}
+
+#[tauri::command]
+pub async fn get_events(store: State<'_, Store>) -> Result<Vec<BlockEvent>, String> {
+    Ok(store.read(|data| data.events.clone()))
+}
+
+#[tauri::command]
+pub async fn append_event(event: BlockEvent, store: State<'_, Store>) -> Result<(), String> {
+    store.update(|data| push_event(data, event))
+}
+
+#[tauri::command]
+pub async fn clear_events(store: State<'_, Store>) -> Result<(), String> {
+    store.update(|data| data.events.clear())
+}
+
+// One-time import of the data the webview used to keep in localStorage.
+// Returns false if the store was already migrated, so the legacy keys are
+// never applied twice.
+#[tauri::command]
+pub async fn migrate_local_storage(
+    mut legacy: LegacyStorage,
+    store: State<'_, Store>,
+) -> Result<bool, String> {
+    if store.read(|data| data.migrated_from_local_storage) {
+        return Ok(false);
+    }
+    legacy.block_rules.iter_mut().for_each(record_exe_hash);
+
+    store.update(|data| {
+        if data.migrated_from_local_storage {
+            return false;
+        }
+        merge_legacy(data, legacy);
+        data.migrated_from_local_storage = true;
+        true
+    })
+}
+
+// Add the legacy rules and events to whatever the store already has. Rules
+// already in the store win over legacy ones with the same id.
+fn merge_legacy(data: &mut StoreData, legacy: LegacyStorage) {
+    if let Some(settings) = legacy.settings {
+        data.settings = settings;
+    }
+    for rule in legacy.block_rules {
+        if !data.block_rules.iter().any(|existing| existing.id == rule.id) {
+            data.block_rules.push(rule);
+        }
+    }
+    for rule in legacy.website_rules {
+        if !data.website_rules.iter().any(|existing| existing.id == rule.id) {
+            data.website_rules.push(rule);
+        }
+    }
+    for event in legacy.events {
+        if !data.events.iter().any(|existing| existing.id == event.id) {
+            data.events.push(event);
+        }
+    }
+    data.events.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    data.events.truncate(MAX_EVENTS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neu-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn invalid_store_is_set_aside() {
+        let dir = temp_dir("invalid");
+        let path = dir.join(STORE_FILE_NAME);
+        fs::write(&path, "{ not json").unwrap();
+
+        let store = Store::load(path.clone()).unwrap();
+        assert!(store.read(|data| data.block_rules.is_empty()));
+        let events = store.read(|data| data.events.clone());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "violation");
+        // The stale localStorage copy must not be imported over the reset
+        assert!(store.read(|data| data.migrated_from_local_storage));
+
+        let names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        let backup = names
+            .iter()
+            .find(|name| name.starts_with("neu_store.json.invalid-"))
+            .expect("invalid store kept");
+        assert_eq!(fs::read_to_string(dir.join(backup)).unwrap(), "{ not json");
+        assert!(Store::read_snapshot(&path).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_store_starts_empty() {
+        let dir = temp_dir("missing");
+        let store = Store::load(dir.join(STORE_FILE_NAME)).unwrap();
+        assert!(store.read(|data| data.events.is_empty()));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn newer_schema_is_refused() {
+        let dir = temp_dir("newer");
+        let path = dir.join(STORE_FILE_NAME);
+        let content = format!(r#"{{"schemaVersion": {}, "blockRules": []}}"#, SCHEMA_VERSION + 1);
+        fs::write(&path, &content).unwrap();
+
+        assert!(Store::load(path.clone()).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn legacy_data_is_merged() {
+        let rule = |id: &str, domain: &str| -> WebsiteBlockRule {
+            serde_json::from_value(serde_json::json!({
+                "id": id, "domain": domain, "isActive": true, "createdAt": 0, "type": "permanent"
+            }))
+            .unwrap()
+        };
+        let event = |id: &str, timestamp: i64| BlockEvent {
+            id: id.to_string(),
+            event_type: "block".to_string(),
+            target: "x".to_string(),
+            timestamp,
+            message: String::new(),
+        };
+
+        let mut data = empty();
+        data.website_rules = vec![rule("a", "saved.com")];
+        data.events = vec![event("e2", 2)];
+        let legacy = LegacyStorage {
+            settings: None,
+            block_rules: Vec::new(),
+            website_rules: vec![rule("a", "legacy.com"), rule("b", "other.com")],
+            events: vec![event("e1", 1), event("e2", 2), event("e3", 3)],
+        };
+        merge_legacy(&mut data, legacy);
+
+        let domains: Vec<&str> = data.website_rules.iter().map(|r| r.domain.as_str()).collect();
+        assert_eq!(domains, ["saved.com", "other.com"]);
+        let events: Vec<&str> = data.events.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(events, ["e3", "e2", "e1"]);
+    }
+}
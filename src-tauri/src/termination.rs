@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+#[cfg(unix)]
+use crate::helper::HelperClient;
+use crate::ProcessInfo;
+
+// Default time a process gets to exit after SIGTERM before it is SIGKILLed
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+// How often we check whether a signalled process has exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// How long we wait for SIGKILL to take effect before giving up
+const KILL_CONFIRM_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TerminationOutcome {
+    // The process was running and is now gone
+    Terminated,
+    // The process was already gone when we tried
+    AlreadyExited,
+    // The OS refused (process owned by another user, elevated, ...)
+    PermissionDenied,
+    // PID 1, NEU itself or NEU's parent; never killed
+    ProtectedProcess,
+    // SIGKILLed but still present when we stopped waiting, usually stuck in
+    // the kernel (uninterruptible I/O); it exits once that returns
+    KillPending,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminationResult {
+    pub pid: u32,
+    pub outcome: TerminationOutcome,
+    // True if the process ignored the graceful request and had to be force-killed
+    pub forced: bool,
+}
+
+impl TerminationResult {
+    fn new(pid: u32, outcome: TerminationOutcome, forced: bool) -> Self {
+        Self { pid, outcome, forced }
+    }
+}
+
+// PIDs that must never be terminated: init, ourselves and whoever launched us
+pub fn is_protected_pid(pid: u32) -> bool {
+    pid == 0 || pid == 1 || pid == std::process::id() || Some(pid) == parent_pid()
+}
+
+#[cfg(unix)]
+fn parent_pid() -> Option<u32> {
+    Some(std::os::unix::process::parent_id())
+}
+
+#[cfg(not(unix))]
+fn parent_pid() -> Option<u32> {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+    let own = Pid::from_u32(std::process::id());
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[own]),
+        true,
+        ProcessRefreshKind::new(),
+    );
+    sys.process(own).and_then(|p| p.parent()).map(|p| p.as_u32())
+}
+
+// Descendants of `root` grouped by depth, deepest level first, with `root`
+// itself as the last level. Uses the parent PIDs from the process snapshot.
+pub fn tree_levels_bottom_up(root: u32, processes: &[ProcessInfo]) -> Vec<Vec<u32>> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for process in processes {
+        if let Some(parent) = process.parent_pid {
+            children.entry(parent).or_default().push(process.pid);
+        }
+    }
+
+    // Breadth-first walk; `seen` guards against cycles from PID reuse
+    let mut levels = vec![vec![root]];
+    let mut seen = HashSet::from([root]);
+    loop {
+        let next: Vec<u32> = levels
+            .last()
+            .unwrap()
+            .iter()
+            .flat_map(|pid| children.get(pid).into_iter().flatten())
+            .copied()
+            .filter(|pid| seen.insert(*pid))
+            .collect();
+        if next.is_empty() {
+            break;
+        }
+        levels.push(next);
+    }
+
+    levels.reverse();
+    levels
+}
+
+// Terminate `root` and every descendant, children first
+pub async fn terminate_tree(
+    root: u32,
+    processes: &[ProcessInfo],
+    grace_period: Duration,
+) -> Vec<TerminationResult> {
+    terminate_levels(tree_levels_bottom_up(root, processes), grace_period).await
+}
+
+// Each level is terminated concurrently, deepest first, so helpers are gone
+// before the parent that would otherwise respawn them
+pub async fn terminate_levels(levels: Vec<Vec<u32>>, grace_period: Duration) -> Vec<TerminationResult> {
+    let mut results = Vec::new();
+
+    for level in levels {
+        let mut kills = tokio::task::JoinSet::new();
+        for pid in level {
+            kills.spawn(terminate_process(pid, grace_period));
+        }
+        while let Some(joined) = kills.join_next().await {
+            if let Ok(result) = joined {
+                results.push(result);
+            }
+        }
+    }
+
+    results
+}
+
+// Terminate a process: SIGTERM, wait up to `grace_period`, then SIGKILL.
+// Shared by the `kill_process` command and the enforcement engine.
+#[cfg(unix)]
+pub async fn terminate_process(pid: u32, grace_period: Duration) -> TerminationResult {
+    use TerminationOutcome::*;
+
+    if is_protected_pid(pid) {
+        return TerminationResult::new(pid, ProtectedProcess, false);
+    }
+
+    match send_signal(pid, libc::SIGTERM) {
+        Ok(()) => {}
+        // Another user's, or the signal was denied by a sandbox or security
+        // module; the privileged helper can still kill our own user's processes
+        Err(PermissionDenied) => {
+            return kill_with_helper(pid, grace_period)
+                .await
+                .unwrap_or_else(|| TerminationResult::new(pid, PermissionDenied, false));
+        }
+        Err(outcome) => return TerminationResult::new(pid, outcome, false),
+    }
+
+    if wait_for_exit(pid, grace_period).await {
+        return TerminationResult::new(pid, Terminated, false);
+    }
+
+    match send_signal(pid, libc::SIGKILL) {
+        // Exited between the last poll and SIGKILL
+        Ok(()) | Err(AlreadyExited) => {}
+        Err(outcome) => return TerminationResult::new(pid, outcome, true),
+    }
+
+    // SIGKILL can't be ignored, but it only takes effect once the process
+    // leaves the kernel
+    let outcome = if wait_for_exit(pid, KILL_CONFIRM_TIMEOUT).await {
+        Terminated
+    } else {
+        KillPending
+    };
+    TerminationResult::new(pid, outcome, true)
+}
+
+// `None` if no helper is running or it refused
+#[cfg(unix)]
+async fn kill_with_helper(pid: u32, grace_period: Duration) -> Option<TerminationResult> {
+    tokio::task::spawn_blocking(move || {
+        let helper = HelperClient::connect()?;
+        helper
+            .kill_process(pid, grace_period)
+            .map_err(|e| eprintln!("Privileged helper could not kill {}: {}", pid, e))
+            .ok()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: libc::c_int) -> Result<(), TerminationOutcome> {
+    let Ok(raw_pid) = libc::pid_t::try_from(pid) else {
+        return Err(TerminationOutcome::AlreadyExited);
+    };
+
+    // SAFETY: kill(2) has no memory-safety preconditions
+    if unsafe { libc::kill(raw_pid, signal) } == 0 {
+        return Ok(());
+    }
+
+    match std::io::Error::last_os_error().raw_os_error() {
+        Some(libc::ESRCH) => Err(TerminationOutcome::AlreadyExited),
+        _ => Err(TerminationOutcome::PermissionDenied),
+    }
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    if send_signal(pid, 0).is_err_and(|outcome| outcome == TerminationOutcome::AlreadyExited) {
+        return false;
+    }
+
+    // A zombie still answers signal 0 but has already exited
+    #[cfg(target_os = "linux")]
+    if let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        // Format: "pid (comm) state ..."; comm may contain spaces and parens
+        if let Some(state) = stat.rsplit_once(')').and_then(|(_, rest)| rest.split_whitespace().next()) {
+            return state != "Z" && state != "X";
+        }
+    }
+
+    true
+}
+
+#[cfg(unix)]
+async fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        if !is_running(pid) {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+    }
+}
+
+// Windows has no graceful equivalent to SIGTERM for arbitrary processes, so
+// the grace period is unused and the process is terminated immediately
+#[cfg(target_os = "windows")]
+pub async fn terminate_process(pid: u32, _grace_period: Duration) -> TerminationResult {
+    use windows::Win32::Foundation::{CloseHandle, ERROR_ACCESS_DENIED};
+    use windows::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+    use TerminationOutcome::*;
+
+    if is_protected_pid(pid) {
+        return TerminationResult::new(pid, ProtectedProcess, false);
+    }
+
+    unsafe {
+        let handle = match OpenProcess(PROCESS_TERMINATE, false, pid) {
+            Ok(handle) if !handle.is_invalid() => handle,
+            Err(e) if e.code() == ERROR_ACCESS_DENIED.to_hresult() => {
+                return TerminationResult::new(pid, PermissionDenied, false);
+            }
+            _ => return TerminationResult::new(pid, AlreadyExited, false),
+        };
+
+        let result = TerminateProcess(handle, 1);
+        let _ = CloseHandle(handle);
+
+        match result {
+            Ok(()) => TerminationResult::new(pid, Terminated, true),
+            Err(_) => TerminationResult::new(pid, PermissionDenied, true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent_pid: Option<u32>) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: format!("proc-{}", pid),
+            path: String::new(),
+            cmd: String::new(),
+            parent_pid,
+            parent_name: None,
+        }
+    }
+
+    #[test]
+    fn tree_levels_put_the_deepest_descendants_first() {
+        let processes = [
+            process(10, Some(1)),
+            process(11, Some(10)),
+            process(12, Some(10)),
+            process(13, Some(11)),
+            // Unrelated
+            process(20, Some(1)),
+            process(21, Some(20)),
+        ];
+        let mut levels = tree_levels_bottom_up(10, &processes);
+        levels.iter_mut().for_each(|level| level.sort());
+        assert_eq!(levels, [vec![13], vec![11, 12], vec![10]]);
+
+        assert_eq!(tree_levels_bottom_up(99, &processes), [vec![99]]);
+    }
+
+    #[test]
+    fn tree_levels_survive_a_parent_cycle() {
+        // A reused PID can make a process look like its own ancestor
+        let processes = [process(10, Some(12)), process(11, Some(10)), process(12, Some(11))];
+        assert_eq!(tree_levels_bottom_up(10, &processes), [vec![12], vec![11], vec![10]]);
+    }
+
+    #[cfg(unix)]
+    fn spawn_shell(script: &str) -> std::process::Child {
+        std::process::Command::new("sh").args(["-c", script]).spawn().unwrap()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sigterm_ends_a_normal_process() {
+        let mut child = spawn_shell("exec sleep 30");
+        let result = terminate_process(child.id(), Duration::from_secs(3)).await;
+        let _ = child.wait();
+        assert_eq!(result.outcome, TerminationOutcome::Terminated);
+        assert!(!result.forced);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn process_ignoring_sigterm_is_forced() {
+        // `sleep` inherits the ignored SIGTERM through exec
+        let mut child = spawn_shell("trap '' TERM; exec sleep 30");
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let result = terminate_process(child.id(), Duration::from_millis(300)).await;
+        let _ = child.wait();
+        assert_eq!(result.outcome, TerminationOutcome::Terminated);
+        assert!(result.forced);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exited_and_protected_processes_are_left_alone() {
+        let mut child = spawn_shell("exit 0");
+        let pid = child.id();
+        child.wait().unwrap();
+        let result = terminate_process(pid, Duration::from_secs(1)).await;
+        assert_eq!(result.outcome, TerminationOutcome::AlreadyExited);
+
+        for pid in [1, std::process::id()] {
+            let result = terminate_process(pid, Duration::from_secs(1)).await;
+            assert_eq!(result.outcome, TerminationOutcome::ProtectedProcess);
+        }
+    }
+}
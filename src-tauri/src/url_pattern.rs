@@ -0,0 +1,162 @@
+// URL patterns for path-level website rules:
+//
+//     youtube.com/shorts      youtube.com and its subdomains, /shorts and below
+//     reddit.com/r/all        /r/all and /r/all/..., but not /r/allergies
+//     example.com/*/comments  `*` matches any run of characters
+//     news.example.com        the whole site
+//
+// The host matches itself and every subdomain. The path is a prefix that must
+// end at a segment boundary, unless it ends in `/` or `*`. A leading
+// `http://`/`https://` or `*.` in the pattern is ignored.
+
+use regex::Regex;
+
+#[derive(Debug, Clone)]
+pub struct UrlPattern {
+    host: String,
+    path: Option<Regex>,
+}
+
+impl UrlPattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let (host, path) = split_pattern(pattern)?;
+
+        let path = match path {
+            "" | "/" | "/*" => None,
+            path => {
+                let body = path
+                    .split('*')
+                    .map(regex::escape)
+                    .collect::<Vec<_>>()
+                    .join(".*");
+                let boundary = if path.ends_with('/') || path.ends_with('*') {
+                    ""
+                } else {
+                    "(?:[/?#]|$)"
+                };
+                let regex = Regex::new(&format!("^{}{}", body, boundary))
+                    .map_err(|e| format!("Invalid URL pattern '{}': {}", pattern, e))?;
+                Some(regex)
+            }
+        };
+
+        Ok(Self { host, path })
+    }
+
+    // Only http(s) URLs can match; browser-internal pages never do
+    pub fn matches(&self, url: &str) -> bool {
+        let Some((host, path)) = split_url(url) else {
+            return false;
+        };
+        let host_matches = host == self.host
+            || host
+                .strip_suffix(self.host.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'));
+        host_matches && self.path.as_ref().is_none_or(|regex| regex.is_match(&path))
+    }
+}
+
+// Lowercased host of a pattern and its path (empty if none), still with any
+// `*` in it
+pub fn split_pattern(pattern: &str) -> Result<(String, &str), String> {
+    let trimmed = pattern.trim();
+    let without_scheme = trimmed
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(trimmed);
+    let (host, path) = match without_scheme.find('/') {
+        Some(slash) => without_scheme.split_at(slash),
+        None => (without_scheme, ""),
+    };
+
+    let host = host.trim_start_matches("*.").trim_end_matches('.').to_lowercase();
+    if host.is_empty()
+        || !host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'))
+    {
+        return Err(format!("Invalid host in URL pattern '{}'", pattern));
+    }
+    Ok((host, path))
+}
+
+// Lowercased host and the rest of the URL from the path on (`/` if empty)
+pub fn split_url(url: &str) -> Option<(String, String)> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    // Drop `user:password@` and the port
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = match host_port.rsplit_once(':') {
+        Some((host, port)) if !host.ends_with(':') && port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host_port,
+    };
+    if host.is_empty() {
+        return None;
+    }
+
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+    Some((host.trim_end_matches('.').to_lowercase(), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, url: &str) -> bool {
+        UrlPattern::parse(pattern).unwrap().matches(url)
+    }
+
+    #[test]
+    fn paths_end_at_a_segment_boundary() {
+        assert!(matches("reddit.com/r/all", "https://reddit.com/r/all"));
+        assert!(matches("reddit.com/r/all", "https://reddit.com/r/all/top"));
+        assert!(matches("reddit.com/r/all", "https://reddit.com/r/all?sort=new"));
+        assert!(matches("reddit.com/r/all", "https://reddit.com/r/all#top"));
+        assert!(!matches("reddit.com/r/all", "https://reddit.com/r/allergies"));
+        assert!(!matches("reddit.com/r/all", "https://reddit.com/r"));
+        // A trailing `/` is a plain prefix of everything below it
+        assert!(matches("example.com/docs/", "https://example.com/docs/intro"));
+        assert!(!matches("example.com/docs/", "https://example.com/docs"));
+    }
+
+    #[test]
+    fn stars_match_any_run_of_characters() {
+        assert!(matches("example.com/*/comments", "https://example.com/r/rust/comments/1"));
+        assert!(!matches("example.com/*/comments", "https://example.com/r/rust/commentsx"));
+        assert!(matches("example.com/watch*", "https://example.com/watchlist"));
+        assert!(matches("example.com/*", "http://example.com/"));
+        // Everything else in a pattern is taken literally
+        assert!(matches("example.com/a.b", "https://example.com/a.b"));
+        assert!(!matches("example.com/a.b", "https://example.com/axb"));
+    }
+
+    #[test]
+    fn hosts_match_themselves_and_subdomains() {
+        assert!(matches("youtube.com/shorts", "https://www.youtube.com/shorts/abc"));
+        assert!(matches("youtube.com/shorts", "https://m.youtube.com/shorts"));
+        assert!(matches("YouTube.com/shorts", "https://YOUTUBE.COM./shorts"));
+        assert!(!matches("youtube.com/shorts", "https://notyoutube.com/shorts"));
+        assert!(!matches("youtube.com/shorts", "https://youtube.com.evil.example/shorts"));
+        assert!(matches("https://*.example.com", "https://user:pw@a.example.com:8443/x"));
+        // Only web pages can match
+        assert!(!matches("example.com", "ftp://example.com/"));
+        assert!(!matches("example.com", "chrome://example.com/"));
+    }
+
+    #[test]
+    fn invalid_hosts_are_rejected() {
+        assert!(UrlPattern::parse("").is_err());
+        assert!(UrlPattern::parse("/r/all").is_err());
+        assert!(UrlPattern::parse("exa mple.com/x").is_err());
+        assert_eq!(split_pattern(" https://Example.com./a*").unwrap(), ("example.com".to_string(), "/a*"));
+    }
+}
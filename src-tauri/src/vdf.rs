@@ -0,0 +1,315 @@
+// Parser for Valve's KeyValues text format (`.vdf`, `.acf`):
+//
+//     "AppState"
+//     {
+//         "appid"      "440"
+//         "name"       "Team Fortress 2"
+//     }
+//
+// Keys and values may be quoted or bare, `//` starts a comment, and a
+// trailing `[$WIN32]`-style conditional after a value is ignored. Keys are
+// compared case-insensitively, as Steam does.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            Value::Object(_) => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, Value)] {
+        match self {
+            Value::Object(entries) => entries,
+            Value::String(_) => &[],
+        }
+    }
+
+    // First child with the given key
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries()
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+}
+
+// Parse a document into its top-level key and value. Files hold a single root
+// object, e.g. `"libraryfolders" { ... }`.
+pub fn parse(input: &str) -> Result<(String, Value), String> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+
+    let key = match parser.next_token()? {
+        Some(Token::Text(key)) => key,
+        Some(_) => return Err(parser.error("expected a key")),
+        None => return Err(parser.error("empty document")),
+    };
+    let value = parser.parse_value()?;
+    Ok((key, value))
+}
+
+enum Token {
+    Text(String),
+    Open,
+    Close,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        format!("VDF parse error on line {}: {}", self.line, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.bump();
+            } else if c == '/' && self.chars.get(self.pos + 1) == Some(&'/') {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, String> {
+        self.skip_whitespace_and_comments();
+
+        let Some(c) = self.peek() else {
+            return Ok(None);
+        };
+        match c {
+            '{' => {
+                self.bump();
+                Ok(Some(Token::Open))
+            }
+            '}' => {
+                self.bump();
+                Ok(Some(Token::Close))
+            }
+            '"' => {
+                self.bump();
+                self.quoted().map(|s| Some(Token::Text(s)))
+            }
+            _ => Ok(Some(Token::Text(self.bare()))),
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(text),
+                Some('\\') => match self.bump() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some(c) => text.push(c),
+                    None => break,
+                },
+                Some(c) => text.push(c),
+                None => break,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn bare(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == '{' || c == '}' || c == '"' {
+                break;
+            }
+            text.push(c);
+            self.bump();
+        }
+        text
+    }
+
+    // `[$WIN32]`, `[!$X360]`, ... directly after a value
+    fn skip_conditional(&mut self) {
+        let start = self.pos;
+        let line = self.line;
+        while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+            self.bump();
+        }
+        if self.peek() == Some('[') {
+            while let Some(c) = self.bump() {
+                if c == ']' || c == '\n' {
+                    break;
+                }
+            }
+        } else {
+            self.pos = start;
+            self.line = line;
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        match self.next_token()? {
+            Some(Token::Text(text)) => {
+                self.skip_conditional();
+                Ok(Value::String(text))
+            }
+            Some(Token::Open) => self.parse_object(),
+            Some(Token::Close) => Err(self.error("unexpected '}'")),
+            None => Err(self.error("missing value")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value, String> {
+        let mut entries = Vec::new();
+        loop {
+            match self.next_token()? {
+                Some(Token::Close) => return Ok(Value::Object(entries)),
+                Some(Token::Text(key)) => {
+                    let value = self.parse_value()?;
+                    entries.push((key, value));
+                }
+                Some(Token::Open) => return Err(self.error("expected a key, found '{'")),
+                None => return Err(self.error("missing '}'")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn escapes_in_quoted_strings() {
+        let (_, root) = parse(r#""root" { "path" "C:\\Games\\Steam" "quote" "say \"hi\"" "lines" "a\nb\tc" }"#).unwrap();
+        assert_eq!(root.get_str("path"), Some(r"C:\Games\Steam"));
+        assert_eq!(root.get_str("quote"), Some(r#"say "hi""#));
+        assert_eq!(root.get_str("lines"), Some("a\nb\tc"));
+    }
+
+    #[test]
+    fn nested_objects_bare_tokens_and_comments() {
+        let input = r#"
+            // leading comment
+            "AppState"
+            {
+                appid   440 // trailing comment
+                "UserConfig"
+                {
+                    "language"  "english"   [$WIN32]
+                    Deeper { key value }
+                }
+                "Name"  "Team Fortress 2"
+            }
+        "#;
+        let (key, state) = parse(input).unwrap();
+        assert_eq!(key, "AppState");
+        assert_eq!(state.get_str("APPID"), Some("440"));
+        assert_eq!(state.get_str("name"), Some("Team Fortress 2"));
+
+        let config = state.get("userconfig").unwrap();
+        assert_eq!(config.get_str("language"), Some("english"));
+        assert_eq!(
+            config.get("deeper"),
+            Some(&Value::Object(vec![("key".to_string(), string("value"))]))
+        );
+        assert_eq!(state.get("UserConfig").unwrap().as_str(), None);
+    }
+
+    #[test]
+    fn old_libraryfolders_format() {
+        let input = r#"
+            "LibraryFolders"
+            {
+                "TimeNextStatsReport"   "1700000000"
+                "ContentStatsID"        "-1234"
+                "1"     "D:\\SteamLibrary"
+                "2"     "/mnt/games/SteamLibrary"
+            }
+        "#;
+        let (key, folders) = parse(input).unwrap();
+        assert_eq!(key, "LibraryFolders");
+        assert_eq!(folders.get_str("1"), Some(r"D:\SteamLibrary"));
+        assert_eq!(folders.get_str("2"), Some("/mnt/games/SteamLibrary"));
+        assert_eq!(folders.entries().len(), 4);
+    }
+
+    #[test]
+    fn new_libraryfolders_format() {
+        let input = r#"
+            "libraryfolders"
+            {
+                "0"
+                {
+                    "path"      "/home/user/.local/share/Steam"
+                    "label"     ""
+                    "apps"
+                    {
+                        "228980"    "361494443"
+                    }
+                }
+                "1"
+                {
+                    "path"      "/mnt/games/SteamLibrary"
+                    "apps" {}
+                }
+            }
+        "#;
+        let (_, folders) = parse(input).unwrap();
+        let first = folders.get("0").unwrap();
+        assert_eq!(first.get_str("path"), Some("/home/user/.local/share/Steam"));
+        assert_eq!(first.get_str("label"), Some(""));
+        assert_eq!(first.get("apps").unwrap().get_str("228980"), Some("361494443"));
+        let second = folders.get("1").unwrap();
+        assert_eq!(second.get_str("path"), Some("/mnt/games/SteamLibrary"));
+        assert_eq!(second.get("apps"), Some(&Value::Object(Vec::new())));
+    }
+
+    #[test]
+    fn malformed_documents_are_errors() {
+        assert!(parse("").is_err());
+        assert!(parse(r#""root" { "key" "value""#).is_err());
+        assert!(parse(r#""root" { "key" "unterminated }"#).is_err());
+        assert!(parse(r#""root" { { } }"#).is_err());
+        assert!(parse(r#""root" { "key" }"#).is_err());
+        assert_eq!(
+            parse("\"root\"\n{\n\"key\" \"value\"\n").unwrap_err(),
+            "VDF parse error on line 4: missing '}'"
+        );
+    }
+}
@@ -0,0 +1,1331 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::doh::{self, DohStatus};
+use crate::domain::{self, DomainError};
+use crate::helper::HelperClient;
+use crate::hosts::{self, HostsEntry, HostsFile};
+use crate::rules;
+use crate::store::{AppSettings, Store, StoreData};
+
+// First line of every file NEU generates for a backend
+const GENERATED_HEADER: &str = "# Generated by NEU - Do not edit this file manually";
+
+// dnsmasq drop-in directories, each with the service that runs the dnsmasq
+// reading it: a standalone dnsmasq, or the one NetworkManager runs
+const DNSMASQ_CONF_DIRS: &[(&str, &str)] = &[
+    ("/etc/dnsmasq.d", "dnsmasq"),
+    ("/etc/NetworkManager/dnsmasq.d", "NetworkManager"),
+];
+const DNSMASQ_CONF_NAME: &str = "neu-block.conf";
+
+const RESOLVED_DROP_IN_DIR: &str = "/etc/systemd/resolved.conf.d";
+const RESOLVED_DROP_IN_NAME: &str = "neu-block.conf";
+// Only exists while systemd-resolved is running
+const RESOLVED_RUNTIME_DIR: &str = "/run/systemd/resolve";
+
+// nscd only answers (and caches) while its socket exists
+const NSCD_SOCKETS: &[&str] = &["/run/nscd/socket", "/var/run/nscd/socket"];
+
+const NFT_TABLE: &str = "neu_block";
+// Concurrent DNS lookups when resolving blocked domains
+const RESOLVE_THREADS: usize = 16;
+
+// Domains checked through the system resolver after an apply. Imported lists
+// can hold tens of thousands, and every lookup re-reads the hosts file.
+const MAX_VERIFIED_DOMAINS: usize = 200;
+// Services restarted by the apply may take a moment to answer again
+const VERIFY_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+// How often the active rules are compared with what was last applied
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+// nftables blocks the addresses the domains resolved to when applied; resolve
+// them again this often to follow sites that move
+const NFT_RESOLVE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// Where website blocks are written. The hosts file is the default; the others
+// exist for systems where it can't be edited (e.g. NixOS, where /etc/hosts is
+// a read-only link into the Nix store).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackendKind {
+    #[default]
+    Hosts,
+    Dnsmasq,
+    SystemdResolved,
+    Nftables,
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 4] = [
+        BackendKind::Hosts,
+        BackendKind::Dnsmasq,
+        BackendKind::SystemdResolved,
+        BackendKind::Nftables,
+    ];
+}
+
+pub trait WebsiteBlockBackend {
+    fn kind(&self) -> BackendKind;
+
+    // Ok if this machine supports the backend, otherwise why it doesn't
+    fn detect(&self) -> Result<(), String>;
+
+    // Block exactly `domains` (with their subdomains or www. variants) and
+    // nothing else. An empty list removes every block.
+    fn set_domains(&self, domains: &[String]) -> Result<(), String>;
+
+    // Domains currently blocked, read back from the system
+    fn blocked_domains(&self) -> Result<Vec<String>, String>;
+
+    // Ok if the blocks in place are actually enforced
+    fn health(&self) -> Result<(), String> {
+        self.blocked_domains().map(|_| ())
+    }
+
+    // Addresses the backend blocks outright. `None` for DNS based backends,
+    // where blocked names resolve to an unspecified or loopback address.
+    fn blocked_addresses(&self) -> Option<BTreeSet<IpAddr>> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendStatus {
+    pub kind: BackendKind,
+    // Selected in the settings
+    pub active: bool,
+    pub available: bool,
+    pub unavailable_reason: Option<String>,
+    pub healthy: bool,
+    pub health_error: Option<String>,
+    pub blocked_domains: Vec<String>,
+}
+
+// Outcome of applying a list of domains. Entries that aren't valid domains are
+// reported instead of failing the whole list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyResult {
+    // Normalized names now blocked
+    pub applied: Vec<String>,
+    pub invalid: Vec<DomainError>,
+    // DNS caches flushed after writing the blocks
+    pub flushed_caches: Vec<String>,
+    // What the system resolver returns for the applied domains now
+    pub verification: Vec<DomainVerification>,
+    // Applied domains beyond `MAX_VERIFIED_DOMAINS`, not looked up
+    pub unverified: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VerificationStatus {
+    Blocked,
+    // No addresses at all, which keeps the site out of reach as well
+    Unresolved,
+    // Still resolves to addresses the backend doesn't block
+    NotBlocked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainVerification {
+    pub domain: String,
+    pub status: VerificationStatus,
+    // Addresses of the domain and its www. variant
+    pub addresses: Vec<IpAddr>,
+}
+
+// How far the blocks in place are from what the active rules require
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebsiteBlockStatus {
+    pub backend: BackendKind,
+    pub website_blocking_enabled: bool,
+    // Domains of the active website rules
+    pub required: Vec<String>,
+    // Domains the backend actually blocks
+    pub blocked: Vec<String>,
+    pub missing: Vec<String>,
+    // Blocked without an active rule asking for it
+    pub extra: Vec<String>,
+    // Hosts file lines outside the NEU block that map a required domain
+    pub external: Vec<ExternalHostsEntry>,
+    pub in_sync: bool,
+    // Whether browsers can still get past the blocks over DNS-over-HTTPS
+    pub doh: DohStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalHostsEntry {
+    pub domain: String,
+    // `domain` or its `www.` variant
+    pub name: String,
+    pub address: IpAddr,
+    pub line: usize,
+    // Lookups use the first line listing a name, so this one wins over the
+    // NEU block and every DNS based backend
+    pub effective: bool,
+    // Points at 0.0.0.0, :: or loopback, so it blocks the name anyway
+    pub sinkhole: bool,
+}
+
+// Run `f` with the backend of the given kind
+pub fn with_backend<T>(
+    app: &AppHandle,
+    kind: BackendKind,
+    f: impl FnOnce(&dyn WebsiteBlockBackend) -> T,
+) -> T {
+    backend_for(kind, app.state::<HostsFile>().inner(), configured_sinkhole(app), f)
+}
+
+// Same, with the hosts file and sinkhole given rather than taken from the
+// app, for the privileged helper
+pub fn backend_for<T>(
+    kind: BackendKind,
+    hosts: &HostsFile,
+    sinkhole: Option<SocketAddr>,
+    f: impl FnOnce(&dyn WebsiteBlockBackend) -> T,
+) -> T {
+    match kind {
+        BackendKind::Hosts => f(hosts),
+        BackendKind::Dnsmasq => f(&Dnsmasq),
+        BackendKind::SystemdResolved => f(&SystemdResolved { sinkhole }),
+        BackendKind::Nftables => f(&Nftables),
+    }
+}
+
+fn configured_sinkhole(app: &AppHandle) -> Option<SocketAddr> {
+    app.state::<Store>()
+        .read(|data| SystemdResolved::from_settings(&data.settings).sinkhole)
+}
+
+pub fn selected_kind(app: &AppHandle) -> BackendKind {
+    app.state::<Store>()
+        .read(|data| data.settings.website_block_backend)
+}
+
+// Normalized domains the active website rules want blocked at `now`, plus
+// the DoH providers when those are blocked too
+pub fn required_domains(data: &StoreData, now: &chrono::DateTime<chrono::Local>) -> Vec<String> {
+    if !data.settings.website_blocking_enabled {
+        return Vec::new();
+    }
+    let domains: Vec<String> = rules::active_at(&data.website_rules, now)
+        .iter()
+        .flat_map(|rule| rule.blocked_domains().iter().cloned())
+        .collect();
+    clean_domains(&doh::with_providers(&data.settings, domains))
+}
+
+// Compare the active rules with what the selected backend blocks and with
+// the rest of the hosts file, which takes precedence over DNS
+fn block_status(app: &AppHandle) -> Result<WebsiteBlockStatus, String> {
+    let backend = selected_kind(app);
+    let now = chrono::Local::now();
+    let (settings, required) = app
+        .state::<Store>()
+        .read(|data| (data.settings.clone(), required_domains(data, &now)));
+    let blocked = clean_domains(&with_backend(app, backend, |backend| backend.blocked_domains())?);
+    let hosts = app.state::<HostsFile>().read()?;
+    Ok(compare(backend, &settings, required, blocked, &hosts))
+}
+
+// `required` against what `backend` blocks and the hosts file content `hosts`
+fn compare(
+    backend: BackendKind,
+    settings: &AppSettings,
+    required: Vec<String>,
+    blocked: Vec<String>,
+    hosts: &str,
+) -> WebsiteBlockStatus {
+    let required_set: BTreeSet<&String> = required.iter().collect();
+    let blocked_set: BTreeSet<&String> = blocked.iter().collect();
+    let missing: Vec<String> = required_set.difference(&blocked_set).map(|d| d.to_string()).collect();
+    let extra: Vec<String> = blocked_set.difference(&required_set).map(|d| d.to_string()).collect();
+    let external = external_entries(hosts, &required);
+
+    let in_sync = missing.is_empty()
+        && extra.is_empty()
+        && !external.iter().any(|entry| entry.effective && !entry.sinkhole);
+    WebsiteBlockStatus {
+        backend,
+        website_blocking_enabled: settings.website_blocking_enabled,
+        required,
+        blocked,
+        missing,
+        extra,
+        external,
+        in_sync,
+        doh: doh::status(settings),
+    }
+}
+
+// Lines outside the NEU block mapping one of `domains` (or its `www.` variant),
+// in file order
+pub fn external_entries(content: &str, domains: &[String]) -> Vec<ExternalHostsEntry> {
+    let entries = hosts::entries(content);
+    // Every line listing each name, in file order
+    let mut by_name: HashMap<&str, Vec<&HostsEntry>> = HashMap::new();
+    for entry in &entries {
+        for name in &entry.names {
+            let listing = by_name.entry(name).or_default();
+            // A name repeated on one line counts once
+            if listing.last().is_none_or(|last| last.line != entry.line) {
+                listing.push(entry);
+            }
+        }
+    }
+    let mut found = Vec::new();
+
+    for domain in domains {
+        let mut names = vec![domain.clone()];
+        if !domain.starts_with("www.") {
+            names.push(format!("www.{}", domain));
+        }
+        for name in names {
+            let listing = by_name.get(name.as_str()).map(Vec::as_slice).unwrap_or_default();
+            for (index, entry) in listing.iter().enumerate() {
+                if entry.in_block {
+                    continue;
+                }
+                found.push(ExternalHostsEntry {
+                    domain: domain.clone(),
+                    name: name.clone(),
+                    address: entry.address,
+                    line: entry.line,
+                    effective: index == 0,
+                    sinkhole: entry.address.is_unspecified() || entry.address.is_loopback(),
+                });
+            }
+        }
+    }
+
+    found.sort_by_key(|entry| entry.line);
+    found
+}
+
+fn status(backend: &dyn WebsiteBlockBackend, active: bool) -> BackendStatus {
+    let detected = backend.detect();
+    let health = detected.as_ref().map_err(Clone::clone).and_then(|_| backend.health());
+    BackendStatus {
+        kind: backend.kind(),
+        active,
+        available: detected.is_ok(),
+        unavailable_reason: detected.err(),
+        healthy: health.is_ok(),
+        health_error: health.err(),
+        blocked_domains: backend.blocked_domains().unwrap_or_default(),
+    }
+}
+
+// Apply `domains` (URLs or host names) through the selected backend, by way
+// of the privileged helper when one is running. The DoH providers are added
+// here so every caller blocks them.
+pub fn apply(app: &AppHandle, domains: &[String]) -> Result<ApplyResult, String> {
+    let settings = app.state::<Store>().read(|data| data.settings.clone());
+    let domains = &doh::with_providers(&settings, domains.to_vec())[..];
+    let kind = selected_kind(app);
+    let sinkhole = configured_sinkhole(app);
+    let hosts = app.state::<HostsFile>();
+    let Some(helper) = HelperClient::connect() else {
+        return apply_with(kind, hosts.inner(), sinkhole, domains);
+    };
+
+    // The helper's write to the hosts file isn't tampering
+    let expected = if kind == BackendKind::Hosts {
+        clean_domains(domains)
+    } else {
+        Vec::new()
+    };
+    hosts.expect_domains(&expected);
+    let result = helper.set_website_domains(kind, domains, sinkhole);
+    if result.is_err() {
+        let _ = hosts.resync();
+    }
+    result
+}
+
+// Apply `domains` through the backend of the given kind and clear whatever the
+// other backends still block from before a switch
+pub fn apply_with(
+    kind: BackendKind,
+    hosts: &HostsFile,
+    sinkhole: Option<SocketAddr>,
+    domains: &[String],
+) -> Result<ApplyResult, String> {
+    let (applied, invalid) = domain::normalize_all(domains);
+    backend_for(kind, hosts, sinkhole, |backend| {
+        backend.detect()?;
+        backend.set_domains(&applied)
+    })?;
+
+    for other in BackendKind::ALL.into_iter().filter(|other| *other != kind) {
+        backend_for(other, hosts, sinkhole, |backend| {
+            let leftover = backend.detect().is_ok()
+                && backend.blocked_domains().is_ok_and(|domains| !domains.is_empty());
+            if leftover {
+                if let Err(e) = backend.set_domains(&[]) {
+                    eprintln!("Failed to clear blocks from the {:?} backend: {}", other, e);
+                }
+            }
+        });
+    }
+
+    let flushed_caches = flush_dns_caches();
+    let checked = &applied[..applied.len().min(MAX_VERIFIED_DOMAINS)];
+    let verification = backend_for(kind, hosts, sinkhole, |backend| verify(backend, checked));
+    let unverified = applied.len() - checked.len();
+    Ok(ApplyResult {
+        applied,
+        invalid,
+        flushed_caches,
+        verification,
+        unverified,
+    })
+}
+
+// Look `domains` up through the system resolver, the way browsers would, and
+// check the answers against the backend. Domains that still resolve normally
+// are looked up once more after a short delay.
+fn verify(backend: &dyn WebsiteBlockBackend, domains: &[String]) -> Vec<DomainVerification> {
+    let blocked_addresses = backend.blocked_addresses();
+    let check = |domains: &[String]| -> Vec<DomainVerification> {
+        domains
+            .iter()
+            .zip(resolve_all(domains))
+            .map(|(domain, addresses)| DomainVerification {
+                domain: domain.clone(),
+                status: classify(&addresses, &blocked_addresses),
+                addresses,
+            })
+            .collect()
+    };
+
+    let mut results = check(domains);
+    let retry: Vec<String> = results
+        .iter()
+        .filter(|result| result.status == VerificationStatus::NotBlocked)
+        .map(|result| result.domain.clone())
+        .collect();
+    if !retry.is_empty() {
+        std::thread::sleep(VERIFY_RETRY_DELAY);
+        for retried in check(&retry) {
+            if let Some(result) = results.iter_mut().find(|result| result.domain == retried.domain) {
+                *result = retried;
+            }
+        }
+    }
+    results
+}
+
+// Whether a domain resolving to `addresses` is blocked. `blocked` is the
+// backend's own address set, if it blocks by address rather than by name.
+fn classify(addresses: &[IpAddr], blocked: &Option<BTreeSet<IpAddr>>) -> VerificationStatus {
+    let is_blocked = |ip: &IpAddr| match blocked {
+        Some(blocked) => blocked.contains(ip),
+        None => ip.is_unspecified() || ip.is_loopback(),
+    };
+    if addresses.is_empty() {
+        VerificationStatus::Unresolved
+    } else if addresses.iter().all(is_blocked) {
+        VerificationStatus::Blocked
+    } else {
+        VerificationStatus::NotBlocked
+    }
+}
+
+// Drop cached answers, so lookups made before the blocks changed don't keep
+// resolving. Returns the caches that were flushed.
+fn flush_dns_caches() -> Vec<String> {
+    let mut flushed = Vec::new();
+    let mut record = |cache: &str, result: Result<String, String>| match result {
+        Ok(_) => flushed.push(cache.to_string()),
+        Err(e) => eprintln!("Failed to flush the {} cache: {}", cache, e),
+    };
+
+    if cfg!(target_os = "windows") {
+        record("Windows DNS client", run("ipconfig", &["/flushdns"]));
+    }
+    if cfg!(target_os = "macos") {
+        record("Directory Service", run("dscacheutil", &["-flushcache"]));
+        record("mDNSResponder", run("killall", &["-HUP", "mDNSResponder"]));
+    }
+    if cfg!(target_os = "linux") {
+        if Path::new(RESOLVED_RUNTIME_DIR).is_dir() {
+            // `resolvectl` replaced `systemd-resolve` in systemd 239
+            let result = if find_program("resolvectl").is_some() {
+                run("resolvectl", &["flush-caches"])
+            } else {
+                run("systemd-resolve", &["--flush-caches"])
+            };
+            record("systemd-resolved", result);
+        }
+        if find_program("nscd").is_some() && NSCD_SOCKETS.iter().any(|socket| Path::new(socket).exists()) {
+            record("nscd", run("nscd", &["--invalidate=hosts"]));
+        }
+        // Standalone and NetworkManager's dnsmasq both clear their cache on
+        // SIGHUP
+        if run("pgrep", &["-x", "dnsmasq"]).is_ok() {
+            record("dnsmasq", run("pkill", &["-HUP", "-x", "dnsmasq"]));
+        }
+    }
+
+    flushed
+}
+
+impl WebsiteBlockBackend for HostsFile {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Hosts
+    }
+
+    fn detect(&self) -> Result<(), String> {
+        let path = self.path();
+        if let Ok(target) = fs::canonicalize(path) {
+            if target.starts_with("/nix/store") {
+                return Err(format!(
+                    "{} is a read-only link into the Nix store",
+                    path.display()
+                ));
+            }
+        }
+        // Opening for append checks write access without changing anything
+        fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map(|_| ())
+            .map_err(|e| format!("{} is not writable: {}", path.display(), e))
+    }
+
+    fn set_domains(&self, domains: &[String]) -> Result<(), String> {
+        HostsFile::set_domains(self, &clean_domains(domains))
+    }
+
+    fn blocked_domains(&self) -> Result<Vec<String>, String> {
+        HostsFile::blocked_domains(self)
+    }
+}
+
+// `address=/example.com/0.0.0.0` entries in a dnsmasq drop-in. dnsmasq applies
+// them to every subdomain as well.
+pub struct Dnsmasq;
+
+impl Dnsmasq {
+    // The first drop-in directory present, with the service that reads it
+    fn target(&self) -> Option<(PathBuf, &'static str)> {
+        DNSMASQ_CONF_DIRS
+            .iter()
+            .map(|(dir, service)| (PathBuf::from(dir), *service))
+            .find(|(dir, _)| dir.is_dir())
+    }
+
+    // The drop-in blocking `domains`
+    fn config(domains: &[String]) -> String {
+        let mut content = format!("{}\n", GENERATED_HEADER);
+        for domain in domains {
+            content.push_str(&format!("address=/{}/0.0.0.0\n", domain));
+            content.push_str(&format!("address=/{}/::\n", domain));
+        }
+        content
+    }
+
+    // The domains a drop-in blocks, sorted
+    fn parse_config(content: &str) -> Vec<String> {
+        let domains: BTreeSet<String> = content
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("address=/"))
+            .filter_map(|rest| rest.split('/').next())
+            .map(str::to_string)
+            .collect();
+        domains.into_iter().collect()
+    }
+}
+
+// dnsmasq only re-reads `address=` lines on a restart (SIGHUP just reloads
+// hosts files). NetworkManager restarts the dnsmasq it runs on a DNS plugin
+// reload, without taking every connection down the way restarting
+// NetworkManager itself would.
+fn reload_dnsmasq(service: &str) -> Result<(), String> {
+    if service == "NetworkManager" {
+        run("nmcli", &["general", "reload", "dns-full"]).map(|_| ())
+    } else {
+        run("systemctl", &["restart", service]).map(|_| ())
+    }
+}
+
+impl WebsiteBlockBackend for Dnsmasq {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Dnsmasq
+    }
+
+    fn detect(&self) -> Result<(), String> {
+        require_linux()?;
+        if find_program("dnsmasq").is_none() {
+            return Err("dnsmasq is not installed".to_string());
+        }
+        let (dir, _) = self
+            .target()
+            .ok_or("No dnsmasq drop-in directory (/etc/dnsmasq.d) found")?;
+        require_writable(&dir)
+    }
+
+    fn set_domains(&self, domains: &[String]) -> Result<(), String> {
+        let (dir, service) = self.target().ok_or("No dnsmasq drop-in directory found")?;
+        let path = dir.join(DNSMASQ_CONF_NAME);
+
+        let domains = clean_domains(domains);
+        if !write_drop_in(&path, &Self::config(&domains), domains.is_empty())? {
+            return Ok(());
+        }
+        reload_dnsmasq(service)
+    }
+
+    fn blocked_domains(&self) -> Result<Vec<String>, String> {
+        let Some((dir, _)) = self.target() else {
+            return Ok(Vec::new());
+        };
+        Ok(Self::parse_config(&read_optional(&dir.join(DNSMASQ_CONF_NAME))?))
+    }
+
+    fn health(&self) -> Result<(), String> {
+        let (_, service) = self.target().ok_or("No dnsmasq drop-in directory found")?;
+        require_service_active(service)?;
+        self.blocked_domains().map(|_| ())
+    }
+}
+
+// A systemd-resolved drop-in that routes lookups for blocked domains (and
+// their subdomains, `~domain`) to NEU's DNS sinkhole, which answers them with
+// 0.0.0.0. resolved has no way to block a name on its own.
+pub struct SystemdResolved {
+    sinkhole: Option<SocketAddr>,
+}
+
+impl SystemdResolved {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        let sinkhole = settings
+            .dns_sinkhole_enabled
+            .then(|| settings.dns_sinkhole_listen.parse().ok())
+            .flatten();
+        Self { sinkhole }
+    }
+
+    fn drop_in_path(&self) -> PathBuf {
+        Path::new(RESOLVED_DROP_IN_DIR).join(RESOLVED_DROP_IN_NAME)
+    }
+
+    // `DNS=` from the drop-in on disk
+    fn routed_to(&self) -> Result<Option<String>, String> {
+        let content = read_optional(&self.drop_in_path())?;
+        Ok(content
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("DNS="))
+            .map(|dns| dns.trim().to_string()))
+    }
+
+    // The drop-in routing `domains` to the sinkhole
+    fn drop_in(&self, domains: &[String]) -> Result<String, String> {
+        let mut content = format!("{}\n[Resolve]\n", GENERATED_HEADER);
+        if let Some(sinkhole) = self.sinkhole.filter(|_| !domains.is_empty()) {
+            // resolved takes `ip:port` for servers off port 53
+            let server = if sinkhole.port() == 53 {
+                sinkhole.ip().to_string()
+            } else {
+                sinkhole.to_string()
+            };
+            content.push_str(&format!("DNS={}\n", server));
+            let routes: Vec<String> = domains.iter().map(|domain| format!("~{}", domain)).collect();
+            content.push_str(&format!("Domains={}\n", routes.join(" ")));
+        } else if !domains.is_empty() {
+            return Err("Requires the DNS sinkhole: blocked domains are routed to it".to_string());
+        }
+        Ok(content)
+    }
+
+    // The `~domain` routes in a drop-in
+    fn parse_routes(content: &str) -> Vec<String> {
+        content
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("Domains="))
+            .flat_map(str::split_whitespace)
+            .filter_map(|route| route.strip_prefix('~'))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl WebsiteBlockBackend for SystemdResolved {
+    fn kind(&self) -> BackendKind {
+        BackendKind::SystemdResolved
+    }
+
+    fn detect(&self) -> Result<(), String> {
+        require_linux()?;
+        if !Path::new(RESOLVED_RUNTIME_DIR).is_dir() {
+            return Err("systemd-resolved is not running".to_string());
+        }
+        if self.sinkhole.is_none() {
+            return Err(
+                "Requires the DNS sinkhole: blocked domains are routed to it".to_string(),
+            );
+        }
+        let dir = Path::new(RESOLVED_DROP_IN_DIR);
+        require_writable(if dir.is_dir() { dir } else { Path::new("/etc/systemd") })
+    }
+
+    fn set_domains(&self, domains: &[String]) -> Result<(), String> {
+        let domains = clean_domains(domains);
+        let content = self.drop_in(&domains)?;
+
+        fs::create_dir_all(RESOLVED_DROP_IN_DIR)
+            .map_err(|e| format!("Failed to create {}: {}", RESOLVED_DROP_IN_DIR, e))?;
+        if !write_drop_in(&self.drop_in_path(), &content, domains.is_empty())? {
+            return Ok(());
+        }
+        // Also drops resolved's cache, so old answers don't linger
+        run("systemctl", &["restart", "systemd-resolved"]).map(|_| ())
+    }
+
+    fn blocked_domains(&self) -> Result<Vec<String>, String> {
+        Ok(Self::parse_routes(&read_optional(&self.drop_in_path())?))
+    }
+
+    fn health(&self) -> Result<(), String> {
+        require_service_active("systemd-resolved")?;
+        if self.blocked_domains()?.is_empty() {
+            return Ok(());
+        }
+        // Blocks routed to a sinkhole that's been disabled or moved resolve
+        // normally (or not at all)
+        let Some(sinkhole) = self.sinkhole else {
+            return Err("Blocked domains are routed to the DNS sinkhole, which is disabled".to_string());
+        };
+        let routed = self.routed_to()?.unwrap_or_default();
+        if routed != sinkhole.to_string() && routed != sinkhole.ip().to_string() {
+            return Err(format!(
+                "Blocked domains are routed to {}, but the DNS sinkhole listens on {}",
+                routed, sinkhole
+            ));
+        }
+        Ok(())
+    }
+}
+
+// An nftables table that rejects outgoing traffic to the blocked domains'
+// addresses. The addresses are resolved when the blocks are applied, so sites
+// that move to new IPs slip through until the next apply, and unrelated sites
+// sharing a CDN address get blocked too.
+pub struct Nftables;
+
+impl Nftables {
+    fn script(addresses: &BTreeMap<IpAddr, String>) -> String {
+        let elements = |v4: bool| -> Vec<String> {
+            addresses
+                .iter()
+                .filter(|(ip, _)| ip.is_ipv4() == v4)
+                .map(|(ip, domain)| format!("{} comment \"{}\"", ip, domain))
+                .collect()
+        };
+        let set = |name: &str, ty: &str, elements: Vec<String>| {
+            let mut set = format!("    set {} {{\n        type {}\n", name, ty);
+            if !elements.is_empty() {
+                set.push_str(&format!("        elements = {{ {} }}\n", elements.join(", ")));
+            }
+            set.push_str("    }\n");
+            set
+        };
+
+        // Declaring the table first makes the delete succeed on a fresh system
+        let mut script = format!("table inet {t}\ndelete table inet {t}\ntable inet {t} {{\n", t = NFT_TABLE);
+        script.push_str(&set("blocked_v4", "ipv4_addr", elements(true)));
+        script.push_str(&set("blocked_v6", "ipv6_addr", elements(false)));
+        script.push_str(
+            "    chain output {\n        type filter hook output priority 0; policy accept;\n        ip daddr @blocked_v4 reject\n        ip6 daddr @blocked_v6 reject\n    }\n}\n",
+        );
+        script
+    }
+
+    // The domains tagging the set elements of `nft list table` output, sorted
+    fn listed_domains(listing: &str) -> Vec<String> {
+        let domains: BTreeSet<String> = listing
+            .split("comment \"")
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .map(str::to_string)
+            .collect();
+        domains.into_iter().collect()
+    }
+
+    // The set elements themselves, each listed as `<ip> comment "<domain>"`
+    fn listed_addresses(listing: &str) -> BTreeSet<IpAddr> {
+        listing
+            .split(" comment \"")
+            .filter_map(|before| before.rsplit([' ', '\t', '\n', '{', ',']).next()?.parse().ok())
+            .collect()
+    }
+}
+
+impl WebsiteBlockBackend for Nftables {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Nftables
+    }
+
+    fn detect(&self) -> Result<(), String> {
+        require_linux()?;
+        if find_program("nft").is_none() {
+            return Err("nftables (nft) is not installed".to_string());
+        }
+        // Listing needs the same privileges as changing the ruleset
+        run("nft", &["list", "tables"]).map(|_| ())
+    }
+
+    fn set_domains(&self, domains: &[String]) -> Result<(), String> {
+        let domains = clean_domains(domains);
+        if domains.is_empty() {
+            if !table_exists() {
+                return Ok(());
+            }
+            return run("nft", &["delete", "table", "inet", NFT_TABLE]).map(|_| ());
+        }
+
+        // Each address is tagged with the domain it was resolved for, which is
+        // how the block list is read back
+        let mut addresses = BTreeMap::new();
+        let mut unresolved = 0;
+        for (domain, found) in domains.iter().zip(resolve_all(&domains)) {
+            unresolved += usize::from(found.is_empty());
+            for ip in found {
+                addresses.entry(ip).or_insert_with(|| domain.clone());
+            }
+        }
+        if unresolved > 0 {
+            eprintln!("nftables backend: {} domain(s) did not resolve and are not blocked", unresolved);
+        }
+
+        run_with_input("nft", &["-f", "-"], &Self::script(&addresses)).map(|_| ())
+    }
+
+    fn blocked_domains(&self) -> Result<Vec<String>, String> {
+        if !table_exists() {
+            return Ok(Vec::new());
+        }
+        let listing = run("nft", &["list", "table", "inet", NFT_TABLE])?;
+        Ok(Self::listed_domains(&listing))
+    }
+
+    // Names keep resolving normally; the set elements, listed as
+    // `<ip> comment "<domain>"`, are what gets rejected
+    fn blocked_addresses(&self) -> Option<BTreeSet<IpAddr>> {
+        let listing = if table_exists() {
+            run("nft", &["list", "table", "inet", NFT_TABLE]).unwrap_or_default()
+        } else {
+            String::new()
+        };
+        Some(Self::listed_addresses(&listing))
+    }
+}
+
+// Addresses of each domain and its www. variant, in the order given. Lookups
+// run on a few threads at once, since imported lists can be very long.
+fn resolve_all(domains: &[String]) -> Vec<Vec<IpAddr>> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![Vec::new(); domains.len()]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..RESOLVE_THREADS.min(domains.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(domain) = domains.get(index) else {
+                    break;
+                };
+                let found: BTreeSet<IpAddr> = [domain.clone(), format!("www.{}", domain)]
+                    .iter()
+                    .filter_map(|host| (host.as_str(), 443).to_socket_addrs().ok())
+                    .flatten()
+                    .map(|address| address.ip())
+                    .collect();
+                results.lock().unwrap()[index] = found.into_iter().collect();
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+fn table_exists() -> bool {
+    run("nft", &["list", "table", "inet", NFT_TABLE]).is_ok()
+}
+
+fn require_linux() -> Result<(), String> {
+    if cfg!(target_os = "linux") {
+        Ok(())
+    } else {
+        Err("Only available on Linux".to_string())
+    }
+}
+
+fn require_service_active(service: &str) -> Result<(), String> {
+    run("systemctl", &["is-active", "--quiet", service])
+        .map(|_| ())
+        .map_err(|_| format!("{} is not running", service))
+}
+
+fn require_writable(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| format!("Invalid path {}", path.display()))?;
+        if unsafe { libc::access(c_path.as_ptr(), libc::W_OK) } == 0 {
+            return Ok(());
+        }
+        Err(format!(
+            "{} is not writable (requires admin): {}",
+            path.display(),
+            std::io::Error::last_os_error()
+        ))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(())
+    }
+}
+
+fn find_program(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .chain(["/usr/sbin", "/sbin"].map(PathBuf::from))
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+// Normalized and without duplicates, in a stable order. Anything that isn't a
+// valid domain is dropped, since the names end up in config files and nft
+// scripts.
+fn clean_domains(domains: &[String]) -> Vec<String> {
+    let domains: BTreeSet<String> = domains
+        .iter()
+        .filter_map(|input| domain::normalize(input).ok())
+        .collect();
+    domains.into_iter().collect()
+}
+
+fn read_optional(path: &Path) -> Result<String, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+// Write a generated config file, or delete it when `remove` is set. Returns
+// whether anything changed, so callers only restart services when needed.
+fn write_drop_in(path: &Path, content: &str, remove: bool) -> Result<bool, String> {
+    let current = fs::read_to_string(path).ok();
+    if remove {
+        if current.is_none() {
+            return Ok(false);
+        }
+        fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        return Ok(true);
+    }
+    if current.as_deref() == Some(content) {
+        return Ok(false);
+    }
+    fs::write(path, content)
+        .map_err(|e| format!("Failed to write {} (requires admin): {}", path.display(), e))?;
+    Ok(true)
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    command_result(program, output)
+}
+
+fn run_with_input(program: &str, args: &[&str], input: &str) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    command_result(program, output)
+}
+
+fn command_result(program: &str, output: std::process::Output) -> Result<String, String> {
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(format!("{} failed: {}", program, stderr.trim()))
+}
+
+// What the refresh loop last applied without an error
+#[derive(PartialEq)]
+struct AppliedBlocks {
+    kind: BackendKind,
+    sinkhole: Option<SocketAddr>,
+    domains: Vec<String>,
+}
+
+// Keeps the selected backend in line with the active website rules as they
+// are saved, deleted, or start and end on their schedules and timers
+#[derive(Clone)]
+pub struct WebsiteBlocker {
+    applied: Arc<tokio::sync::Mutex<Option<(AppliedBlocks, Instant)>>>,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl WebsiteBlocker {
+    pub fn new() -> Self {
+        Self {
+            applied: Arc::new(tokio::sync::Mutex::new(None)),
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Spawn the refresh loop. Called once from the `setup` hook.
+    pub fn start(&self, app: AppHandle) {
+        let blocker = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut ticker = tokio::time::interval(REFRESH_INTERVAL);
+            loop {
+                ticker.tick().await;
+                blocker.refresh(&app).await;
+            }
+        });
+    }
+
+    // Apply the active rules if they, the backend or the sinkhole changed,
+    // and re-resolve the nftables addresses once they are old enough
+    async fn refresh(&self, app: &AppHandle) {
+        let now = chrono::Local::now();
+        let desired = AppliedBlocks {
+            kind: selected_kind(app),
+            sinkhole: configured_sinkhole(app),
+            domains: app.state::<Store>().read(|data| required_domains(data, &now)),
+        };
+
+        let mut applied = self.applied.lock().await;
+        if let Some((blocks, at)) = applied.as_ref() {
+            let stale = blocks.kind == BackendKind::Nftables && at.elapsed() >= NFT_RESOLVE_INTERVAL;
+            if *blocks == desired && !stale {
+                return;
+            }
+        }
+        let domains = desired.domains.clone();
+        let result = blocking(app.clone(), move |app| apply(app, &domains))
+            .await
+            .and_then(|result| result);
+
+        let error = match result {
+            Ok(_) => {
+                *applied = Some((desired, Instant::now()));
+                None
+            }
+            Err(e) => Some(e),
+        };
+        self.set_error(error);
+    }
+
+    // Blocks were changed outside the loop; apply the active rules again on
+    // the next refresh
+    async fn invalidate(&self) {
+        *self.applied.lock().await = None;
+    }
+
+    fn set_error(&self, error: Option<String>) {
+        let mut current = self.error.lock().unwrap();
+        if error.is_some() && *current != error {
+            eprintln!("Website blocks: {}", error.as_deref().unwrap_or_default());
+        }
+        *current = error;
+    }
+}
+
+impl Default for WebsiteBlocker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Backends run external tools and resolve names, so keep them off the async
+// runtime
+async fn blocking<T: Send + 'static>(
+    app: AppHandle,
+    f: impl FnOnce(&AppHandle) -> T + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(move || f(&app))
+        .await
+        .map_err(|e| format!("Website block backend task failed: {}", e))
+}
+
+// Apply `domains` now. The refresh loop puts the active rules back once they
+// differ.
+#[tauri::command]
+pub async fn apply_website_blocks(
+    domains: Vec<String>,
+    app: AppHandle,
+    blocker: State<'_, WebsiteBlocker>,
+) -> Result<ApplyResult, String> {
+    let result = blocking(app, move |app| apply(app, &domains)).await?;
+    blocker.invalidate().await;
+    result
+}
+
+#[tauri::command]
+pub async fn remove_website_blocks(
+    app: AppHandle,
+    blocker: State<'_, WebsiteBlocker>,
+) -> Result<(), String> {
+    apply_website_blocks(vec![], app, blocker).await.map(|_| ())
+}
+
+#[tauri::command]
+pub async fn get_blocked_domains(app: AppHandle) -> Result<Vec<String>, String> {
+    blocking(app, |app| {
+        with_backend(app, selected_kind(app), |backend| backend.blocked_domains())
+    })
+    .await?
+}
+
+// Drift between the active rules and the blocks actually in place
+#[tauri::command]
+pub async fn website_block_status(
+    app: AppHandle,
+    blocker: State<'_, WebsiteBlocker>,
+) -> Result<WebsiteBlockStatus, String> {
+    // Apply rules and settings saved since the last refresh before reporting
+    blocker.refresh(&app).await;
+    blocking(app, block_status).await?
+}
+
+// Capability and health of every backend, for the settings page
+#[tauri::command]
+pub async fn get_website_block_backends(app: AppHandle) -> Result<Vec<BackendStatus>, String> {
+    blocking(app, |app| {
+        let selected = selected_kind(app);
+        BackendKind::ALL
+            .into_iter()
+            .map(|kind| with_backend(app, kind, |backend| status(backend, kind == selected)))
+            .collect()
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hosts::{NEU_MARKER_END, NEU_MARKER_START};
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    // A hosts file with a NEU block and lines of its own around it
+    fn fixture() -> String {
+        format!(
+            "127.0.0.1 localhost\n\
+             10.0.0.5 reddit.com\n\
+             0.0.0.0 www.youtube.com\n\
+             {start}\n\
+             0.0.0.0 reddit.com\n\
+             :: reddit.com\n\
+             0.0.0.0 example.org\n\
+             {end}\n\
+             10.0.0.6 www.reddit.com reddit.com\n",
+            start = NEU_MARKER_START,
+            end = NEU_MARKER_END
+        )
+    }
+
+    #[test]
+    fn external_entries_are_found_in_file_order() {
+        let found = external_entries(&fixture(), &strings(&["reddit.com", "youtube.com"]));
+        let summary: Vec<(usize, &str, bool, bool)> = found
+            .iter()
+            .map(|entry| (entry.line, entry.name.as_str(), entry.effective, entry.sinkhole))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                // The first line for a name is the one lookups use
+                (2, "reddit.com", true, false),
+                (3, "www.youtube.com", true, true),
+                (9, "reddit.com", false, false),
+                (9, "www.reddit.com", true, false),
+            ]
+        );
+        assert_eq!(found[1].domain, "youtube.com");
+    }
+
+    #[test]
+    fn drift_against_the_hosts_file_is_reported() {
+        let settings = AppSettings::default();
+        let required = strings(&["reddit.com", "twitter.com"]);
+        let blocked = strings(&["example.org", "reddit.com"]);
+        let status = compare(BackendKind::Hosts, &settings, required, blocked, &fixture());
+        assert_eq!(status.missing, ["twitter.com"]);
+        assert_eq!(status.extra, ["example.org"]);
+        assert!(!status.in_sync);
+
+        // A line of the user's own that blocks a name anyway is no drift
+        let required = strings(&["youtube.com"]);
+        let hosts = format!(
+            "0.0.0.0 www.youtube.com\n{}\n0.0.0.0 youtube.com\n{}\n",
+            NEU_MARKER_START, NEU_MARKER_END
+        );
+        let status = compare(BackendKind::Hosts, &settings, required.clone(), required.clone(), &hosts);
+        assert_eq!(status.external.len(), 1);
+        assert!(status.in_sync);
+
+        // One that points a required name elsewhere overrides the block
+        let hosts = format!(
+            "10.0.0.5 youtube.com\n{}\n0.0.0.0 youtube.com\n{}\n",
+            NEU_MARKER_START, NEU_MARKER_END
+        );
+        let status = compare(BackendKind::Hosts, &settings, required.clone(), required, &hosts);
+        assert!(status.missing.is_empty() && status.extra.is_empty());
+        assert!(!status.in_sync);
+    }
+    #[test]
+    fn dnsmasq_config_round_trips() {
+        let domains = strings(&["reddit.com", "example.org"]);
+        let config = Dnsmasq::config(&domains);
+        let lines: Vec<&str> = config.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                GENERATED_HEADER,
+                "address=/reddit.com/0.0.0.0",
+                "address=/reddit.com/::",
+                "address=/example.org/0.0.0.0",
+                "address=/example.org/::",
+            ]
+        );
+        assert_eq!(Dnsmasq::parse_config(&config), ["example.org", "reddit.com"]);
+        assert!(Dnsmasq::parse_config(&Dnsmasq::config(&[])).is_empty());
+    }
+
+    #[test]
+    fn resolved_drop_in_routes_to_the_sinkhole() {
+        let domains = strings(&["reddit.com", "example.org"]);
+        let on = |listen: &str| SystemdResolved { sinkhole: Some(listen.parse().unwrap()) };
+
+        let drop_in = on("127.0.0.53:53").drop_in(&domains).unwrap();
+        assert_eq!(
+            drop_in,
+            format!("{}\n[Resolve]\nDNS=127.0.0.53\nDomains=~reddit.com ~example.org\n", GENERATED_HEADER)
+        );
+        assert_eq!(SystemdResolved::parse_routes(&drop_in), domains);
+
+        // Off port 53 the port is given too
+        let drop_in = on("127.0.0.1:5353").drop_in(&domains).unwrap();
+        assert!(drop_in.contains("DNS=127.0.0.1:5353\n"));
+
+        // Nothing to route leaves an empty section
+        let drop_in = on("127.0.0.1:5353").drop_in(&[]).unwrap();
+        assert_eq!(drop_in, format!("{}\n[Resolve]\n", GENERATED_HEADER));
+        assert!(SystemdResolved::parse_routes(&drop_in).is_empty());
+
+        let off = SystemdResolved { sinkhole: None };
+        assert!(off.drop_in(&domains).is_err());
+        assert!(off.drop_in(&[]).is_ok());
+    }
+
+    #[test]
+    fn nft_script_tags_each_address_with_its_domain() {
+        let addresses: BTreeMap<IpAddr, String> = [
+            ("151.101.1.140", "reddit.com"),
+            ("93.184.215.14", "example.org"),
+            ("2a04:4e42::396", "reddit.com"),
+        ]
+        .into_iter()
+        .map(|(ip, domain)| (ip.parse().unwrap(), domain.to_string()))
+        .collect();
+        let script = Nftables::script(&addresses);
+        assert!(script.starts_with("table inet neu_block\ndelete table inet neu_block\ntable inet neu_block {\n"));
+        assert!(script.contains(
+            "        elements = { 93.184.215.14 comment \"example.org\", 151.101.1.140 comment \"reddit.com\" }\n"
+        ));
+        assert!(script.contains("        elements = { 2a04:4e42::396 comment \"reddit.com\" }\n"));
+        assert_eq!(Nftables::listed_domains(&script), ["example.org", "reddit.com"]);
+        assert_eq!(Nftables::listed_addresses(&script), addresses.keys().copied().collect());
+
+        // Empty sets have no elements line at all
+        let script = Nftables::script(&BTreeMap::new());
+        assert!(!script.contains("elements"));
+        assert!(script.contains("        ip6 daddr @blocked_v6 reject\n"));
+    }
+
+    #[test]
+    fn nft_listing_is_read_back() {
+        // `nft list table inet neu_block` wraps long element lists
+        let listing = "table inet neu_block {
+\tset blocked_v4 {
+\t\ttype ipv4_addr
+\t\telements = { 93.184.215.14 comment \"example.org\", 151.101.1.140 comment \"reddit.com\",
+\t\t\t     151.101.65.140 comment \"reddit.com\" }
+\t}
+
+\tset blocked_v6 {
+\t\ttype ipv6_addr
+\t\telements = { 2a04:4e42::396 comment \"reddit.com\" }
+\t}
+
+\tchain output {
+\t\ttype filter hook output priority filter; policy accept;
+\t\tip daddr @blocked_v4 reject
+\t\tip6 daddr @blocked_v6 reject
+\t}
+}
+";
+        assert_eq!(Nftables::listed_domains(listing), ["example.org", "reddit.com"]);
+        let addresses: Vec<String> = Nftables::listed_addresses(listing).iter().map(IpAddr::to_string).collect();
+        assert_eq!(addresses, ["93.184.215.14", "151.101.1.140", "151.101.65.140", "2a04:4e42::396"]);
+        assert!(Nftables::listed_addresses("table inet neu_block {\n}\n").is_empty());
+    }
+    #[test]
+    fn lookups_are_classified() {
+        let ips = |values: &[&str]| -> Vec<IpAddr> { values.iter().map(|ip| ip.parse().unwrap()).collect() };
+        let sinkholed = None;
+        assert_eq!(classify(&ips(&["0.0.0.0", "::"]), &sinkholed), VerificationStatus::Blocked);
+        assert_eq!(classify(&ips(&["127.0.0.1", "::1"]), &sinkholed), VerificationStatus::Blocked);
+        assert_eq!(classify(&ips(&["0.0.0.0", "151.101.1.140"]), &sinkholed), VerificationStatus::NotBlocked);
+        assert_eq!(classify(&[], &sinkholed), VerificationStatus::Unresolved);
+
+        // nftables blocks the addresses in its set, and names resolve as usual
+        let set = Some(ips(&["151.101.1.140", "2a04:4e42::396"]).into_iter().collect());
+        assert_eq!(classify(&ips(&["151.101.1.140", "2a04:4e42::396"]), &set), VerificationStatus::Blocked);
+        assert_eq!(classify(&ips(&["151.101.1.140", "151.101.65.140"]), &set), VerificationStatus::NotBlocked);
+        assert_eq!(classify(&ips(&["0.0.0.0"]), &set), VerificationStatus::NotBlocked);
+        assert_eq!(classify(&[], &set), VerificationStatus::Unresolved);
+        assert_eq!(classify(&ips(&["151.101.1.140"]), &Some(BTreeSet::new())), VerificationStatus::NotBlocked);
+    }
+}
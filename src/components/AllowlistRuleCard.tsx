@@ -0,0 +1,123 @@
+import { invoke } from "@tauri-apps/api/core";
+import { Clock, Calendar, Infinity, Trash2, ListChecks } from "lucide-react";
+import { Card, CardContent } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Switch } from "@/components/ui/switch";
+import { Badge } from "@/components/ui/badge";
+import { AllowlistRule } from "@/types";
+import {
+  describeRuleTiming,
+  generateId,
+  isRuleActive,
+} from "@/lib/helpers";
+import { useSettings } from "@/hooks/useSettings";
+import { storage } from "@/lib/storage";
+
+interface AllowlistRuleCardProps {
+  rule: AllowlistRule;
+  onRemove: () => void;
+  onToggle: (active: boolean) => void;
+}
+
+export default function AllowlistRuleCard({
+  rule,
+  onRemove,
+  onToggle,
+}: AllowlistRuleCardProps) {
+  const { settings } = useSettings();
+  const active = isRuleActive(rule);
+
+  const handleToggle = async (checked: boolean) => {
+    onToggle(checked);
+
+    // Send webhook notification if enabled
+    if (
+      settings.webhookEnabled &&
+      settings.webhookUrl &&
+      ((checked && settings.sendBlockNotifications) ||
+        (!checked && settings.sendUnblockNotifications))
+    ) {
+      try {
+        const action = checked ? "Enabled" : "Disabled";
+        await invoke("send_discord_webhook", {
+          webhookUrl: settings.webhookUrl,
+          message: `${checked ? "✅" : "🔓"} **Allowlist ${action}**\n\n**Name:** ${
+            rule.name
+          }\n**Allowed domains:** ${rule.domains.join(", ")}\n**Type:** ${
+            rule.type
+          }`,
+        });
+      } catch (error) {
+        console.error("Failed to send webhook:", error);
+      }
+    }
+
+    // Log event
+    storage.addEvent({
+      id: generateId(),
+      type: checked ? "block" : "unblock",
+      target: rule.name,
+      timestamp: Date.now(),
+      message: `${checked ? "Enabled" : "Disabled"} allowlist ${rule.name}`,
+    });
+  };
+
+  const getIcon = () => {
+    if (rule.type === "permanent") return <Infinity className="h-4 w-4" />;
+    if (rule.type === "timer") return <Clock className="h-4 w-4" />;
+    if (rule.type === "schedule") return <Calendar className="h-4 w-4" />;
+    return null;
+  };
+
+  const shownDomains = rule.domains.slice(0, 3).join(", ");
+  const moreDomains = rule.domains.length - 3;
+
+  return (
+    <Card>
+      <CardContent className="flex items-center justify-between p-4">
+        <div className="flex items-center gap-3 flex-1">
+          <div
+            className={`p-2 rounded-lg ${
+              active ? "bg-green-500/10" : "bg-muted"
+            }`}
+          >
+            <ListChecks
+              className={`h-4 w-4 ${active ? "text-green-500" : ""}`}
+            />
+          </div>
+
+          <div className="flex-1 min-w-0">
+            <div className="flex items-center gap-2 mb-1">
+              <h3 className="font-semibold truncate">{rule.name}</h3>
+              <Badge
+                variant={active ? "default" : "secondary"}
+                className="capitalize"
+              >
+                {rule.type}
+              </Badge>
+            </div>
+            <p className="text-sm text-muted-foreground truncate">
+              Allows {shownDomains}
+              {moreDomains > 0 && ` +${moreDomains} more`}
+            </p>
+            <p className="text-sm text-muted-foreground flex items-center gap-1">
+              {getIcon()}
+              <span>{describeRuleTiming(rule)}</span>
+            </p>
+          </div>
+
+          <Badge variant={active ? "default" : "secondary"}>
+            {active ? "Active" : "Inactive"}
+          </Badge>
+        </div>
+
+        <div className="flex items-center gap-3 ml-4 pl-4 border-l border-border">
+          <Switch checked={rule.isActive} onCheckedChange={handleToggle} />
+          <Button variant="ghost" size="icon" onClick={onRemove}>
+            <Trash2 className="h-4 w-4 text-destructive" />
+          </Button>
+        </div>
+      </CardContent>
+    </Card>
+  );
+}
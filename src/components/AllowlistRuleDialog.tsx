@@ -0,0 +1,183 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Globe, Plus, X } from "lucide-react";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Badge } from "@/components/ui/badge";
+import { useBlockerContext } from "@/contexts/BlockerContext";
+import { useSettings } from "@/hooks/useSettings";
+import { AllowlistRule } from "@/types";
+import { generateId } from "@/lib/helpers";
+import { normalizeDomain } from "@/lib/websiteCategories";
+import RuleTimingTabs, {
+  DEFAULT_RULE_TIMING,
+  RuleTimingInput,
+  describeTimingForWebhook,
+  ruleTimingFields,
+} from "./RuleTimingTabs";
+import SinkholeRequiredWarning from "./SinkholeRequiredWarning";
+
+interface AllowlistRuleDialogProps {
+  open: boolean;
+  onOpenChange: (open: boolean) => void;
+}
+
+export default function AllowlistRuleDialog({
+  open,
+  onOpenChange,
+}: AllowlistRuleDialogProps) {
+  const { addAllowlistRule } = useBlockerContext();
+  const { settings } = useSettings();
+
+  const [name, setName] = useState("");
+  const [domains, setDomains] = useState<string[]>([]);
+  const [domainInput, setDomainInput] = useState("");
+  const [timing, setTiming] = useState<RuleTimingInput>(DEFAULT_RULE_TIMING);
+
+  const handleClose = () => {
+    setName("");
+    setDomains([]);
+    setDomainInput("");
+    setTiming(DEFAULT_RULE_TIMING);
+    onOpenChange(false);
+  };
+
+  const addDomain = () => {
+    if (!domainInput.trim()) return;
+
+    const normalized = normalizeDomain(domainInput.trim());
+    if (normalized && !domains.includes(normalized)) {
+      setDomains([...domains, normalized]);
+    }
+    setDomainInput("");
+  };
+
+  const handleAddRule = async () => {
+    if (domains.length === 0) return;
+
+    const rule: AllowlistRule = {
+      id: generateId(),
+      name: name.trim() || "Allowlist",
+      domains,
+      isActive: true,
+      createdAt: Date.now(),
+      ...ruleTimingFields(timing),
+    };
+    addAllowlistRule(rule);
+
+    // Send webhook notification if enabled
+    if (
+      settings.webhookEnabled &&
+      settings.webhookUrl &&
+      settings.sendBlockNotifications
+    ) {
+      try {
+        const message = `✅ **Allowlist Added**\n\n**Name:** ${
+          rule.name
+        }\n**Allowed domains:** ${domains.join(", ")}${describeTimingForWebhook(
+          timing
+        )}`;
+        await invoke("send_discord_webhook", {
+          webhookUrl: settings.webhookUrl,
+          message,
+        });
+      } catch (error) {
+        console.error("Failed to send webhook:", error);
+      }
+    }
+
+    handleClose();
+  };
+
+  return (
+    <Dialog open={open} onOpenChange={handleClose}>
+      <DialogContent className="max-w-3xl max-h-[90vh] overflow-y-auto">
+        <DialogHeader>
+          <DialogTitle>Add Allowlist</DialogTitle>
+          <DialogDescription>
+            While an allowlist is active, every website except these domains
+            and their subdomains is blocked
+          </DialogDescription>
+          {!settings.dnsSinkholeEnabled && <SinkholeRequiredWarning />}
+        </DialogHeader>
+
+        <div className="space-y-6">
+          <div>
+            <Label className="text-base font-semibold mb-3 block">Name</Label>
+            <Input
+              placeholder="Work"
+              value={name}
+              onChange={(e) => setName(e.target.value)}
+            />
+          </div>
+
+          <div>
+            <Label className="text-base font-semibold mb-3 block">
+              Allowed Domains
+            </Label>
+            <div className="flex gap-2">
+              <Input
+                placeholder="example.com"
+                value={domainInput}
+                onChange={(e) => setDomainInput(e.target.value)}
+                onKeyDown={(e) => {
+                  if (e.key === "Enter") {
+                    e.preventDefault();
+                    addDomain();
+                  }
+                }}
+              />
+              <Button onClick={addDomain} size="icon">
+                <Plus className="h-4 w-4" />
+              </Button>
+            </div>
+            {domains.length > 0 && (
+              <div className="flex flex-wrap gap-2 mt-3">
+                {domains.map((domain) => (
+                  <Badge key={domain} variant="secondary" className="px-3 py-1">
+                    <Globe className="h-3 w-3 mr-1" />
+                    {domain}
+                    <button
+                      onClick={() =>
+                        setDomains(domains.filter((d) => d !== domain))
+                      }
+                      className="ml-2 hover:text-destructive"
+                    >
+                      <X className="h-3 w-3" />
+                    </button>
+                  </Badge>
+                ))}
+              </div>
+            )}
+          </div>
+
+          <RuleTimingTabs
+            value={timing}
+            onChange={setTiming}
+            permanentDescription="Only these domains can be reached until you remove the allowlist."
+            timerDescription={(minutes) =>
+              `Only these domains can be reached for ${minutes} minutes starting now.`
+            }
+          />
+
+          <div className="flex justify-between pt-4">
+            <Button variant="outline" onClick={handleClose}>
+              Cancel
+            </Button>
+            <Button onClick={handleAddRule} disabled={domains.length === 0}>
+              Add Allowlist
+            </Button>
+          </div>
+        </div>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -70,14 +70,15 @@ export default function BlockRuleDialog({
         invoke<AppInfo[]>("get_installed_apps"),
       ]);
 
-      // Filter to only include valid executable paths (.exe files)
+      // Filter to only include valid executable paths (.exe files on Windows,
+      // absolute paths elsewhere)
       // This prevents adding installer/uninstaller paths or other non-executable items
       const filterValidExecutables = (apps: AppInfo[]) => {
         return apps.filter((app) => {
           const pathLower = app.path.toLowerCase();
-          // Must have .exe extension and not be an uninstaller
+          // Must be an executable and not be an uninstaller
           return (
-            pathLower.endsWith(".exe") &&
+            (pathLower.endsWith(".exe") || app.path.startsWith("/")) &&
             !pathLower.includes("uninstall") &&
             !pathLower.includes("uninst") &&
             app.path.trim() !== ""
@@ -322,12 +323,23 @@ export default function BlockRuleDialog({
                               : "border-l-4 border-transparent"
                           }`}
                         >
-                          <p className="font-semibold text-base mb-2">
-                            {app.name}
-                          </p>
-                          <p className="text-sm text-muted-foreground truncate">
-                            {app.path}
-                          </p>
+                          <div className="flex items-center gap-4">
+                            {app.icon && (
+                              <img
+                                src={app.icon}
+                                alt=""
+                                className="h-8 w-8 shrink-0"
+                              />
+                            )}
+                            <div className="min-w-0">
+                              <p className="font-semibold text-base mb-2">
+                                {app.name}
+                              </p>
+                              <p className="text-sm text-muted-foreground truncate">
+                                {app.path}
+                              </p>
+                            </div>
+                          </div>
                         </button>
                       ))}
                     </div>
@@ -7,6 +7,8 @@ import {
   AlertTriangle,
   Globe,
   Activity,
+  ListChecks,
+  Link,
 } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import {
@@ -27,10 +29,15 @@ import { isRuleActive } from "@/lib/helpers";
 import { storage } from "@/lib/storage";
 import BlockRuleDialog from "./BlockRuleDialog";
 import WebsiteRuleDialog from "./WebsiteRuleDialog";
+import UrlRuleDialog from "./UrlRuleDialog";
+import AllowlistRuleDialog from "./AllowlistRuleDialog";
 import PinDialog from "./PinDialog";
 import KillswitchDialog from "./KillswitchDialog";
 import BlockRuleCard from "./BlockRuleCard";
 import WebsiteRuleCard from "./WebsiteRuleCard";
+import UrlRuleCard from "./UrlRuleCard";
+import AllowlistRuleCard from "./AllowlistRuleCard";
+import SinkholeRequiredWarning from "./SinkholeRequiredWarning";
 import EventsTab from "./EventsTab";
 
 export default function Dashboard() {
@@ -43,16 +50,20 @@ export default function Dashboard() {
     removeWebsiteRule,
     updateRule,
     updateWebsiteRule,
+    urlRules,
+    removeUrlRule,
+    updateUrlRule,
+    allowlistRules,
+    removeAllowlistRule,
+    updateAllowlistRule,
     cleanupExpiredTimers,
     cleanupExpiredWebsiteTimers,
   } = useBlockerContext();
-  const blockingStatus = useBlockingStatus(
-    rules,
-    websiteRules,
-    settings.blockingEnabled
-  );
+  const blockingStatus = useBlockingStatus(settings.blockingEnabled);
   const [showAddDialog, setShowAddDialog] = useState(false);
   const [showAddWebsiteDialog, setShowAddWebsiteDialog] = useState(false);
+  const [showAddUrlDialog, setShowAddUrlDialog] = useState(false);
+  const [showAddAllowlistDialog, setShowAddAllowlistDialog] = useState(false);
   const [showPinDialog, setShowPinDialog] = useState(false);
   const [showKillswitch, setShowKillswitch] = useState(false);
   const [pendingAction, setPendingAction] = useState<(() => void) | null>(null);
@@ -197,6 +208,60 @@ export default function Dashboard() {
     });
   };
 
+  const handleRemoveUrlRule = (ruleId: string) => {
+    const rule = urlRules.find((r) => r.id === ruleId);
+    executeWithPinCheck(async () => {
+      removeUrlRule(ruleId);
+
+      // Send webhook notification if enabled
+      if (
+        rule &&
+        settings.webhookEnabled &&
+        settings.webhookUrl &&
+        settings.sendUnblockNotifications
+      ) {
+        try {
+          await invoke("send_discord_webhook", {
+            webhookUrl: settings.webhookUrl,
+            message: `🗑️ **URL Rule Deleted**\n\n**Pattern:** ${
+              rule.pattern
+            }\n**Type:** ${rule.type}`,
+          });
+        } catch (error) {
+          console.error("Failed to send webhook:", error);
+        }
+      }
+    });
+  };
+
+  const handleRemoveAllowlistRule = (ruleId: string) => {
+    const rule = allowlistRules.find((r) => r.id === ruleId);
+    executeWithPinCheck(async () => {
+      removeAllowlistRule(ruleId);
+
+      // Send webhook notification if enabled
+      if (
+        rule &&
+        settings.webhookEnabled &&
+        settings.webhookUrl &&
+        settings.sendUnblockNotifications
+      ) {
+        try {
+          await invoke("send_discord_webhook", {
+            webhookUrl: settings.webhookUrl,
+            message: `🗑️ **Allowlist Deleted**\n\n**Name:** ${
+              rule.name
+            }\n**Allowed domains:** ${rule.domains.join(", ")}\n**Type:** ${
+              rule.type
+            }`,
+          });
+        } catch (error) {
+          console.error("Failed to send webhook:", error);
+        }
+      }
+    });
+  };
+
   const handlePinVerified = () => {
     if (pendingAction) {
       pendingAction();
@@ -276,37 +341,34 @@ export default function Dashboard() {
         </CardHeader>
       </Card>
 
-      {/* Currently Blocking Status */}
+      {/* Recently Blocked Status */}
       {settings.blockingEnabled && blockingStatus.totalBlocked > 0 && (
         <Card className="border-warning/50 bg-warning/5">
           <CardHeader>
             <div className="flex items-center gap-3">
               <Activity className="h-5 w-5 text-warning animate-pulse" />
               <div>
-                <CardTitle className="text-lg">Currently Blocking</CardTitle>
+                <CardTitle className="text-lg">Recently Blocked</CardTitle>
                 <CardDescription>
-                  {blockingStatus.totalBlocked} process
-                  {blockingStatus.totalBlocked !== 1 ? "es" : ""} blocked right
-                  now
+                  {blockingStatus.totalBlocked} app
+                  {blockingStatus.totalBlocked !== 1 ? "s" : ""} closed in the
+                  last minute
                 </CardDescription>
               </div>
             </div>
           </CardHeader>
-          <CardContent className="space-y-3">
-            {blockingStatus.blockedApps.length > 0 && (
-              <div>
-                <div className="text-sm font-medium mb-2">
-                  Applications ({blockingStatus.blockedApps.length}):
-                </div>
-                <div className="flex flex-wrap gap-2">
-                  {blockingStatus.blockedApps.map((app, idx) => (
-                    <Badge key={idx} variant="destructive">
-                      {app.name}
-                    </Badge>
-                  ))}
-                </div>
-              </div>
-            )}
+          <CardContent>
+            <div className="flex flex-wrap gap-2">
+              {blockingStatus.blockedApps.map((app) => (
+                <Badge key={app.path} variant="destructive">
+                  {app.name}
+                  {app.count > 1 && ` ×${app.count}`}
+                </Badge>
+              ))}
+            </div>
+          </CardContent>
+        </Card>
+      )}
             {blockingStatus.blockedBrowsers.length > 0 && (
               <div>
                 <div className="text-sm font-medium mb-2">
@@ -373,6 +435,10 @@ export default function Dashboard() {
           <TabsTrigger value="websites">
             Websites ({websiteRules.length})
           </TabsTrigger>
+          <TabsTrigger value="urls">URLs ({urlRules.length})</TabsTrigger>
+          <TabsTrigger value="allowlists">
+            Allowlists ({allowlistRules.length})
+          </TabsTrigger>
           <TabsTrigger value="events">Events</TabsTrigger>
         </TabsList>
 
@@ -461,6 +527,94 @@ export default function Dashboard() {
           )}
         </TabsContent>
 
+        <TabsContent value="urls" className="space-y-4">
+          <div className="flex justify-end">
+            <Button onClick={() => setShowAddUrlDialog(true)}>
+              <Plus className="mr-2 h-4 w-4" />
+              Add URL Rule
+            </Button>
+          </div>
+
+          {urlRules.length === 0 ? (
+            <Card>
+              <CardContent className="flex flex-col items-center justify-center py-12">
+                <div className="p-4 rounded-full bg-purple-500/10 mb-4">
+                  <Link className="h-12 w-12 text-purple-500" />
+                </div>
+                <h3 className="text-lg font-semibold mb-2">No URL rules yet</h3>
+                <p className="text-sm text-muted-foreground text-center mb-6 max-w-md">
+                  Block the distracting parts of a site, like youtube.com/shorts,
+                  and keep the rest. Needs the companion browser extension.
+                </p>
+                <Button onClick={() => setShowAddUrlDialog(true)}>
+                  <Plus className="mr-2 h-4 w-4" />
+                  Add Your First URL Rule
+                </Button>
+              </CardContent>
+            </Card>
+          ) : (
+            <div className="space-y-3">
+              {urlRules.map((rule) => (
+                <UrlRuleCard
+                  key={rule.id}
+                  rule={rule}
+                  onRemove={() => handleRemoveUrlRule(rule.id)}
+                  onToggle={(active: boolean) =>
+                    updateUrlRule(rule.id, { isActive: active })
+                  }
+                />
+              ))}
+            </div>
+          )}
+        </TabsContent>
+
+        <TabsContent value="allowlists" className="space-y-4">
+          <div className="flex justify-end">
+            <Button onClick={() => setShowAddAllowlistDialog(true)}>
+              <Plus className="mr-2 h-4 w-4" />
+              Add Allowlist
+            </Button>
+          </div>
+
+          {!settings.dnsSinkholeEnabled && allowlistRules.length > 0 && (
+            <SinkholeRequiredWarning />
+          )}
+
+          {allowlistRules.length === 0 ? (
+            <Card>
+              <CardContent className="flex flex-col items-center justify-center py-12">
+                <div className="p-4 rounded-full bg-green-500/10 mb-4">
+                  <ListChecks className="h-12 w-12 text-green-500" />
+                </div>
+                <h3 className="text-lg font-semibold mb-2">
+                  No allowlists yet
+                </h3>
+                <p className="text-sm text-muted-foreground text-center mb-6 max-w-md">
+                  Allow only the websites you need, for a while or on a
+                  schedule, and block everything else
+                </p>
+                <Button onClick={() => setShowAddAllowlistDialog(true)}>
+                  <Plus className="mr-2 h-4 w-4" />
+                  Add Your First Allowlist
+                </Button>
+              </CardContent>
+            </Card>
+          ) : (
+            <div className="space-y-3">
+              {allowlistRules.map((rule) => (
+                <AllowlistRuleCard
+                  key={rule.id}
+                  rule={rule}
+                  onRemove={() => handleRemoveAllowlistRule(rule.id)}
+                  onToggle={(active: boolean) =>
+                    updateAllowlistRule(rule.id, { isActive: active })
+                  }
+                />
+              ))}
+            </div>
+          )}
+        </TabsContent>
+
         <TabsContent value="events" className="space-y-4">
           <EventsTab />
         </TabsContent>
@@ -472,6 +626,11 @@ export default function Dashboard() {
         open={showAddWebsiteDialog}
         onOpenChange={setShowAddWebsiteDialog}
       />
+      <UrlRuleDialog open={showAddUrlDialog} onOpenChange={setShowAddUrlDialog} />
+      <AllowlistRuleDialog
+        open={showAddAllowlistDialog}
+        onOpenChange={setShowAddAllowlistDialog}
+      />
       <PinDialog
         open={showPinDialog}
         onOpenChange={setShowPinDialog}
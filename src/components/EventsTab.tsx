@@ -1,5 +1,11 @@
 import { useState, useEffect } from "react";
-import { Trash2, Clock, Shield, AlertTriangle, XCircle } from "lucide-react";
+import {
+  Trash2,
+  Clock,
+  Shield,
+  AlertTriangle,
+  XCircle,
+} from "lucide-react";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
@@ -17,8 +23,8 @@ export default function EventsTab() {
     return () => clearInterval(interval);
   }, []);
 
-  const loadEvents = () => {
-    const storedEvents = storage.getEvents();
+  const loadEvents = async () => {
+    const storedEvents = await storage.getEvents();
     // Sort by timestamp descending (newest first)
     const sorted = storedEvents.sort((a, b) => b.timestamp - a.timestamp);
     // Limit to last 100 events
@@ -27,8 +33,10 @@ export default function EventsTab() {
 
   const clearEvents = () => {
     if (confirm("Clear all event logs? This cannot be undone.")) {
-      storage.clearEvents();
-      setEvents([]);
+      storage
+        .clearEvents()
+        .then(() => setEvents([]))
+        .catch((error) => console.error("Failed to clear events:", error));
     }
   };
 
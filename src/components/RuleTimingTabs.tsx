@@ -0,0 +1,198 @@
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
+import { isOvernightSchedule } from "@/lib/helpers";
+
+// Timer/schedule/permanent inputs as typed, before they become rule fields
+export interface RuleTimingInput {
+  type: "timer" | "schedule" | "permanent";
+  duration: string;
+  days: number[];
+  startHour: string;
+  startMinute: string;
+  endHour: string;
+  endMinute: string;
+}
+
+export const DEFAULT_RULE_TIMING: RuleTimingInput = {
+  type: "permanent",
+  duration: "30",
+  days: [],
+  startHour: "9",
+  startMinute: "0",
+  endHour: "17",
+  endMinute: "0",
+};
+
+const DAY_NAMES = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+// The timing fields of a rule created now
+export function ruleTimingFields(timing: RuleTimingInput) {
+  if (timing.type === "timer") {
+    return {
+      type: timing.type,
+      duration: parseInt(timing.duration),
+      startTime: Date.now(),
+    };
+  }
+  if (timing.type === "schedule") {
+    return {
+      type: timing.type,
+      days: timing.days,
+      startHour: parseInt(timing.startHour),
+      startMinute: parseInt(timing.startMinute),
+      endHour: parseInt(timing.endHour),
+      endMinute: parseInt(timing.endMinute),
+    };
+  }
+  return { type: timing.type };
+}
+
+// Webhook lines describing the timing
+export function describeTimingForWebhook(timing: RuleTimingInput): string {
+  let message = `\n**Type:** ${timing.type}`;
+  if (timing.type === "timer") {
+    message += `\n**Duration:** ${timing.duration} minutes`;
+  } else if (timing.type === "schedule") {
+    const dayNames = timing.days.map((d) => DAY_NAMES[d]).join(", ");
+    message += `\n**Days:** ${dayNames}\n**Time:** ${
+      timing.startHour
+    }:${timing.startMinute.padStart(2, "0")} - ${
+      timing.endHour
+    }:${timing.endMinute.padStart(2, "0")}`;
+  }
+  return message;
+}
+
+interface RuleTimingTabsProps {
+  value: RuleTimingInput;
+  onChange: (value: RuleTimingInput) => void;
+  // What "Always" means for this kind of rule
+  permanentDescription: string;
+  timerDescription: (minutes: string) => string;
+}
+
+export default function RuleTimingTabs({
+  value,
+  onChange,
+  permanentDescription,
+  timerDescription,
+}: RuleTimingTabsProps) {
+  const update = (updates: Partial<RuleTimingInput>) =>
+    onChange({ ...value, ...updates });
+
+  const isOvernight =
+    value.type === "schedule" &&
+    isOvernightSchedule(
+      parseInt(value.startHour) || 0,
+      parseInt(value.startMinute) || 0,
+      parseInt(value.endHour) || 0,
+      parseInt(value.endMinute) || 0
+    );
+
+  const toggleDay = (day: number) =>
+    update({
+      days: value.days.includes(day)
+        ? value.days.filter((d) => d !== day)
+        : [...value.days, day],
+    });
+
+  const timeInput = (
+    label: string,
+    field: "startHour" | "startMinute" | "endHour" | "endMinute",
+    max: string
+  ) => (
+    <div>
+      <Label className="text-xs">{label}</Label>
+      <Input
+        type="number"
+        value={value[field]}
+        onChange={(e) => {
+          const next = { ...value };
+          next[field] = e.target.value;
+          onChange(next);
+        }}
+        min="0"
+        max={max}
+      />
+    </div>
+  );
+
+  return (
+    <Tabs
+      value={value.type}
+      onValueChange={(v) => update({ type: v as RuleTimingInput["type"] })}
+    >
+      <TabsList className="grid w-full grid-cols-3">
+        <TabsTrigger value="permanent">Always</TabsTrigger>
+        <TabsTrigger value="timer">Timed</TabsTrigger>
+        <TabsTrigger value="schedule">Scheduled</TabsTrigger>
+      </TabsList>
+
+      <TabsContent value="permanent" className="space-y-4">
+        <p className="text-sm text-muted-foreground">{permanentDescription}</p>
+      </TabsContent>
+
+      <TabsContent value="timer" className="space-y-4">
+        <div>
+          <Label>Duration (minutes)</Label>
+          <Input
+            type="number"
+            value={value.duration}
+            onChange={(e) => update({ duration: e.target.value })}
+            min="1"
+          />
+          <p className="text-sm text-muted-foreground mt-2">
+            {timerDescription(value.duration)}
+          </p>
+        </div>
+      </TabsContent>
+
+      <TabsContent value="schedule" className="space-y-4">
+        <div>
+          <Label>Days of the week</Label>
+          <div className="grid grid-cols-7 gap-2 mt-2">
+            {DAY_NAMES.map((day, index) => (
+              <Button
+                key={index}
+                variant={value.days.includes(index) ? "default" : "outline"}
+                onClick={() => toggleDay(index)}
+                className="h-10"
+              >
+                {day}
+              </Button>
+            ))}
+          </div>
+        </div>
+
+        <div className="grid grid-cols-2 gap-4">
+          <div>
+            <Label>Start Time</Label>
+            <div className="grid grid-cols-2 gap-2 mt-2">
+              {timeInput("Hour", "startHour", "23")}
+              {timeInput("Minute", "startMinute", "59")}
+            </div>
+          </div>
+          <div>
+            <Label>End Time</Label>
+            <div className="grid grid-cols-2 gap-2 mt-2">
+              {timeInput("Hour", "endHour", "23")}
+              {timeInput("Minute", "endMinute", "59")}
+            </div>
+          </div>
+        </div>
+
+        {isOvernight && (
+          <div className="rounded-lg bg-amber-500/10 border border-amber-500/30 p-3 mt-4">
+            <p className="text-sm text-amber-700 dark:text-amber-400 font-medium">
+              🌙 Overnight schedule: Active from {value.startHour}:
+              {value.startMinute.padStart(2, "0")} until {value.endHour}:
+              {value.endMinute.padStart(2, "0")} the next day
+            </p>
+          </div>
+        )}
+      </TabsContent>
+    </Tabs>
+  );
+}
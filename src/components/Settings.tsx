@@ -1,4 +1,4 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { Save, Key, Webhook, Bell, Globe } from "lucide-react";
 import {
@@ -12,8 +12,31 @@ import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Switch } from "@/components/ui/switch";
+import { Badge } from "@/components/ui/badge";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
 import { useSettings } from "@/hooks/useSettings";
 import PinDialog from "./PinDialog";
+import {
+  BrowserDohPolicy,
+  BrowserPolicyStatus,
+  SniProxyStatus,
+  WebsiteBlockBackendKind,
+  WebsiteBlockBackendStatus,
+  WebsiteBlockStatus,
+} from "@/types";
+
+const BACKEND_LABELS: Record<WebsiteBlockBackendKind, string> = {
+  hosts: "Hosts file",
+  dnsmasq: "dnsmasq",
+  systemdResolved: "systemd-resolved",
+  nftables: "nftables",
+};
 
 export default function Settings() {
   const { settings, updateSettings } = useSettings();
@@ -30,6 +53,89 @@ export default function Settings() {
   const [confirmNewPin, setConfirmNewPin] = useState("");
   const [pinError, setPinError] = useState("");
   const [webhookError, setWebhookError] = useState("");
+  const [backends, setBackends] = useState<WebsiteBlockBackendStatus[]>([]);
+  const [blockStatus, setBlockStatus] = useState<WebsiteBlockStatus | null>(
+    null
+  );
+  const [policyStatus, setPolicyStatus] =
+    useState<BrowserPolicyStatus | null>(null);
+  const [proxyStatus, setProxyStatus] = useState<SniProxyStatus | null>(null);
+  const [proxyError, setProxyError] = useState("");
+  const [updatingDohPolicies, setUpdatingDohPolicies] = useState(false);
+  const [dohPolicyErrors, setDohPolicyErrors] = useState<string[]>([]);
+
+  const loadBlockStatus = () =>
+    invoke<WebsiteBlockStatus>("website_block_status")
+      .then(setBlockStatus)
+      .catch((error) => {
+        setBlockStatus(null);
+        console.error("Failed to load website block status:", error);
+      });
+
+  useEffect(() => {
+    invoke<WebsiteBlockBackendStatus[]>("get_website_block_backends")
+      .then(setBackends)
+      .catch((error) =>
+        console.error("Failed to load website block backends:", error)
+      );
+    loadBlockStatus();
+  }, [
+    settings.websiteBlockBackend,
+    settings.dnsSinkholeEnabled,
+    settings.websiteBlockingEnabled,
+    settings.blockDohProviders,
+  ]);
+
+  useEffect(() => {
+    invoke<BrowserPolicyStatus>("get_browser_policy_status")
+      .then(setPolicyStatus)
+      .catch((error) => {
+        setPolicyStatus(null);
+        console.error("Failed to load browser policy status:", error);
+      });
+  }, [settings.browserPoliciesEnabled, settings.websiteBlockingEnabled]);
+
+  const loadProxyStatus = () =>
+    invoke<SniProxyStatus>("get_sni_proxy_status")
+      .then(setProxyStatus)
+      .catch((error) => {
+        setProxyStatus(null);
+        console.error("Failed to load proxy status:", error);
+      });
+
+  useEffect(() => {
+    loadProxyStatus();
+  }, [settings.sniProxyEnabled, settings.sniProxyListen]);
+
+  const handleSetSystemProxy = async (enabled: boolean) => {
+    setProxyError("");
+    try {
+      await invoke("set_system_proxy", { enabled });
+    } catch (error) {
+      setProxyError(String(error));
+    } finally {
+      loadProxyStatus();
+    }
+  };
+
+  const handleSetDohPolicies = async (disable: boolean) => {
+    setUpdatingDohPolicies(true);
+    try {
+      const browsers = await invoke<BrowserDohPolicy[]>("set_doh_policies", {
+        disable,
+      });
+      setDohPolicyErrors(
+        browsers.flatMap((browser) =>
+          browser.error ? [`${browser.browser}: ${browser.error}`] : []
+        )
+      );
+    } catch (error) {
+      setDohPolicyErrors([String(error)]);
+    } finally {
+      setUpdatingDohPolicies(false);
+      loadBlockStatus();
+    }
+  };
 
   const validateWebhookUrl = (url: string): boolean => {
     if (!url) return false;
@@ -334,6 +440,362 @@ export default function Settings() {
                 className="scale-125 shadow-md"
               />
             </div>
+            <div className="flex items-center justify-between p-5 rounded-xl border-2 bg-gradient-to-br from-muted/20 to-background shadow-sm hover:shadow-md transition-shadow duration-200">
+              <div className="space-y-1">
+                <Label className="text-base font-semibold">
+                  DNS Sinkhole
+                </Label>
+                <p className="text-sm text-muted-foreground">
+                  Run a local resolver on {settings.dnsSinkholeListen ?? "127.0.0.1:53"}{" "}
+                  that blocks website rules including their subdomains
+                </p>
+              </div>
+              <Switch
+                checked={settings.dnsSinkholeEnabled ?? false}
+                onCheckedChange={(checked) =>
+                  updateSettings({ dnsSinkholeEnabled: checked })
+                }
+                className="scale-125 shadow-md"
+              />
+            </div>
+            <div className="flex items-center justify-between p-5 rounded-xl border-2 bg-gradient-to-br from-muted/20 to-background shadow-sm hover:shadow-md transition-shadow duration-200">
+              <div className="space-y-1">
+                <Label className="text-base font-semibold">Block Page</Label>
+                <p className="text-sm text-muted-foreground">
+                  Show a block page on {settings.blockPageListen ?? "127.0.0.1:80"}{" "}
+                  for blocked sites and log visit attempts
+                </p>
+              </div>
+              <Switch
+                checked={settings.blockPageEnabled ?? false}
+                onCheckedChange={(checked) =>
+                  updateSettings({ blockPageEnabled: checked })
+                }
+                className="scale-125 shadow-md"
+              />
+            </div>
+            <div className="flex items-center justify-between p-5 rounded-xl border-2 bg-gradient-to-br from-muted/20 to-background shadow-sm hover:shadow-md transition-shadow duration-200">
+              <div className="space-y-1">
+                <Label className="text-base font-semibold">
+                  Block DNS-over-HTTPS Providers
+                </Label>
+                <p className="text-sm text-muted-foreground">
+                  Block known DoH resolvers so browsers fall back to the
+                  system resolver and see blocked domains
+                </p>
+              </div>
+              <Switch
+                checked={settings.blockDohProviders ?? true}
+                onCheckedChange={(checked) =>
+                  updateSettings({ blockDohProviders: checked })
+                }
+                className="scale-125 shadow-md"
+              />
+            </div>
+            <div className="p-5 rounded-xl border-2 bg-gradient-to-br from-muted/20 to-background shadow-sm hover:shadow-md transition-shadow duration-200 space-y-3">
+              <div className="flex items-center justify-between">
+                <div className="space-y-1">
+                  <Label className="text-base font-semibold">HTTPS Proxy</Label>
+                  <p className="text-sm text-muted-foreground">
+                    Run a proxy on {settings.sniProxyListen ?? "127.0.0.1:3128"}{" "}
+                    that refuses blocked sites by host name and TLS SNI, even
+                    when they are reached by IP or another resolver
+                  </p>
+                </div>
+                <Switch
+                  checked={settings.sniProxyEnabled ?? false}
+                  onCheckedChange={(checked) =>
+                    updateSettings({ sniProxyEnabled: checked })
+                  }
+                  className="scale-125 shadow-md"
+                />
+              </div>
+              {proxyStatus?.enabled && (
+                <div className="space-y-1 text-sm border-t pt-3">
+                  <div className="flex items-center justify-between gap-4">
+                    <p className="font-medium">System proxy</p>
+                    {proxyStatus.systemProxy == null ? (
+                      <Badge variant="outline">Unknown</Badge>
+                    ) : proxyStatus.systemProxy ? (
+                      <Badge variant="secondary">In use</Badge>
+                    ) : (
+                      <Badge variant="destructive">Not in use</Badge>
+                    )}
+                  </div>
+                  {proxyStatus.error && (
+                    <p className="text-xs text-destructive">
+                      {proxyStatus.error}
+                    </p>
+                  )}
+                  {proxyError && (
+                    <p className="text-xs text-destructive">{proxyError}</p>
+                  )}
+                  <div className="flex gap-2 pt-2">
+                    <Button
+                      size="sm"
+                      variant="outline"
+                      disabled={!proxyStatus.running}
+                      onClick={() => handleSetSystemProxy(true)}
+                    >
+                      Use as system proxy
+                    </Button>
+                    <Button
+                      size="sm"
+                      variant="ghost"
+                      onClick={() => handleSetSystemProxy(false)}
+                    >
+                      Stop using
+                    </Button>
+                  </div>
+                </div>
+              )}
+            </div>
+            <div className="p-5 rounded-xl border-2 bg-gradient-to-br from-muted/20 to-background shadow-sm hover:shadow-md transition-shadow duration-200 space-y-3">
+              <div className="flex items-center justify-between">
+                <div className="space-y-1">
+                  <Label className="text-base font-semibold">
+                    Browser Policies
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    Write website and URL rules into Firefox and Chromium
+                    enterprise policies and force-install the companion
+                    extension; browsers apply them on restart
+                  </p>
+                </div>
+                <Switch
+                  checked={settings.browserPoliciesEnabled ?? false}
+                  onCheckedChange={(checked) =>
+                    updateSettings({ browserPoliciesEnabled: checked })
+                  }
+                  className="scale-125 shadow-md"
+                />
+              </div>
+              {settings.browserPoliciesEnabled && (
+                <div className="grid grid-cols-2 gap-3 border-t pt-3">
+                  <div className="space-y-1">
+                    <Label htmlFor="chromium-extension-id" className="text-xs">
+                      Chrome Web Store extension ID
+                    </Label>
+                    <Input
+                      id="chromium-extension-id"
+                      defaultValue={settings.chromiumExtensionId ?? ""}
+                      onBlur={(e) =>
+                        updateSettings({
+                          chromiumExtensionId: e.target.value.trim() || undefined,
+                        })
+                      }
+                      placeholder="32 letters, a to p"
+                    />
+                  </div>
+                  <div className="space-y-1">
+                    <Label htmlFor="firefox-extension-id" className="text-xs">
+                      Firefox add-on ID
+                    </Label>
+                    <Input
+                      id="firefox-extension-id"
+                      defaultValue={settings.firefoxExtensionId ?? ""}
+                      onBlur={(e) =>
+                        updateSettings({
+                          firefoxExtensionId: e.target.value.trim() || undefined,
+                        })
+                      }
+                      placeholder="name@example.org"
+                    />
+                  </div>
+                </div>
+              )}
+              {policyStatus?.enabled && (
+                <div className="space-y-1 text-sm border-t pt-3">
+                  {policyStatus.browsers
+                    .filter((browser) => browser.installed)
+                    .map((browser) => (
+                      <p
+                        key={browser.browser}
+                        className="text-xs text-muted-foreground"
+                      >
+                        {browser.browser}:{" "}
+                        {!browser.supported
+                          ? "not supported on this platform"
+                          : `${browser.entries} entr${browser.entries === 1 ? "y" : "ies"}`}
+                        {browser.conflict
+                          ? " (another policy also sets URLBlocklist)"
+                          : ""}
+                        {browser.extensionForced
+                          ? ", companion extension forced"
+                          : ""}
+                      </p>
+                    ))}
+                  {policyStatus.chromiumUnsupported.length > 0 && (
+                    <p className="text-xs text-muted-foreground">
+                      Not expressible for Chromium:{" "}
+                      {policyStatus.chromiumUnsupported.join(", ")}
+                    </p>
+                  )}
+                  {policyStatus.error && (
+                    <p className="text-xs text-destructive">
+                      {policyStatus.error}
+                    </p>
+                  )}
+                </div>
+              )}
+            </div>
+            <div className="p-5 rounded-xl border-2 bg-gradient-to-br from-muted/20 to-background shadow-sm hover:shadow-md transition-shadow duration-200 space-y-4">
+              <div className="flex items-center justify-between gap-4">
+                <div className="space-y-1">
+                  <Label className="text-base font-semibold">
+                    Blocking Backend
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    Where blocked domains are written on this system
+                  </p>
+                </div>
+                <Select
+                  value={settings.websiteBlockBackend ?? "hosts"}
+                  onValueChange={(value) =>
+                    updateSettings({
+                      websiteBlockBackend: value as WebsiteBlockBackendKind,
+                    })
+                  }
+                >
+                  <SelectTrigger className="w-48">
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent>
+                    {backends.map((backend) => (
+                      <SelectItem
+                        key={backend.kind}
+                        value={backend.kind}
+                        disabled={!backend.available && !backend.active}
+                      >
+                        {BACKEND_LABELS[backend.kind]}
+                      </SelectItem>
+                    ))}
+                  </SelectContent>
+                </Select>
+              </div>
+              <div className="space-y-2">
+                {backends.map((backend) => (
+                  <div
+                    key={backend.kind}
+                    className="flex items-start justify-between gap-4 text-sm"
+                  >
+                    <div className="min-w-0">
+                      <p className="font-medium">
+                        {BACKEND_LABELS[backend.kind]}
+                      </p>
+                      {(backend.unavailableReason || backend.healthError) && (
+                        <p className="text-xs text-muted-foreground">
+                          {backend.unavailableReason ?? backend.healthError}
+                        </p>
+                      )}
+                    </div>
+                    <div className="flex gap-2 shrink-0">
+                      {backend.active && <Badge>Active</Badge>}
+                      {!backend.available ? (
+                        <Badge variant="outline">Unavailable</Badge>
+                      ) : backend.healthy ? (
+                        <Badge variant="secondary">Healthy</Badge>
+                      ) : (
+                        <Badge variant="destructive">Unhealthy</Badge>
+                      )}
+                    </div>
+                  </div>
+                ))}
+              </div>
+              {blockStatus && (
+                <div className="space-y-1 text-sm border-t pt-3">
+                  <div className="flex items-center justify-between gap-4">
+                    <p className="font-medium">Enforcement</p>
+                    {blockStatus.inSync ? (
+                      <Badge variant="secondary">In sync</Badge>
+                    ) : (
+                      <Badge variant="destructive">Drifted</Badge>
+                    )}
+                  </div>
+                  <p className="text-xs text-muted-foreground">
+                    {blockStatus.blocked.length} of{" "}
+                    {blockStatus.required.length} required domain(s) blocked
+                  </p>
+                  {blockStatus.missing.length > 0 && (
+                    <p className="text-xs text-muted-foreground">
+                      Missing: {blockStatus.missing.join(", ")}
+                    </p>
+                  )}
+                  {blockStatus.extra.length > 0 && (
+                    <p className="text-xs text-muted-foreground">
+                      Blocked without a rule: {blockStatus.extra.join(", ")}
+                    </p>
+                  )}
+                  {blockStatus.external.map((entry) => (
+                    <p
+                      key={`${entry.line}-${entry.name}`}
+                      className="text-xs text-muted-foreground"
+                    >
+                      Hosts line {entry.line} maps {entry.name} to{" "}
+                      {entry.address}
+                      {entry.effective && !entry.sinkhole
+                        ? " and overrides the block"
+                        : ""}
+                    </p>
+                  ))}
+                </div>
+              )}
+              {blockStatus && (
+                <div className="space-y-1 text-sm border-t pt-3">
+                  <div className="flex items-center justify-between gap-4">
+                    <p className="font-medium">DNS-over-HTTPS</p>
+                    {blockStatus.doh.bypassPossible ? (
+                      <Badge variant="destructive">Bypass possible</Badge>
+                    ) : (
+                      <Badge variant="secondary">Disabled</Badge>
+                    )}
+                  </div>
+                  <p className="text-xs text-muted-foreground">
+                    {blockStatus.doh.providersBlocked
+                      ? "Known DoH providers are blocked"
+                      : "DoH providers are not blocked"}
+                  </p>
+                  {blockStatus.doh.browsers
+                    .filter((browser) => browser.installed)
+                    .map((browser) => (
+                      <p
+                        key={browser.browser}
+                        className="text-xs text-muted-foreground"
+                      >
+                        {browser.browser}:{" "}
+                        {browser.dohDisabled
+                          ? "DoH disabled by policy"
+                          : browser.supported
+                            ? "DoH allowed"
+                            : "DoH allowed (policy needs a configuration profile)"}
+                      </p>
+                    ))}
+                  {dohPolicyErrors.map((error) => (
+                    <p key={error} className="text-xs text-destructive">
+                      {error}
+                    </p>
+                  ))}
+                  <div className="flex gap-2 pt-2">
+                    <Button
+                      size="sm"
+                      variant="outline"
+                      disabled={updatingDohPolicies}
+                      onClick={() => handleSetDohPolicies(true)}
+                    >
+                      Disable DoH in browsers
+                    </Button>
+                    <Button
+                      size="sm"
+                      variant="ghost"
+                      disabled={updatingDohPolicies}
+                      onClick={() => handleSetDohPolicies(false)}
+                    >
+                      Remove policies
+                    </Button>
+                  </div>
+                </div>
+              )}
+            </div>
             <div className="rounded-lg p-4 bg-blue-500/10 border-2 border-blue-500/20">
               <p className="text-sm text-blue-700 dark:text-blue-300">
                 ℹ️ Website blocking works by terminating browser processes when
@@ -521,6 +983,23 @@ export default function Settings() {
                 className="scale-125 shadow-md"
               />
             </div>
+            <div className="flex items-center justify-between p-5 rounded-xl border-2 bg-gradient-to-br from-muted/20 to-background shadow-sm hover:shadow-md transition-all duration-200">
+              <div className="space-y-1">
+                <Label className="text-base font-semibold">
+                  Tamper Notifications
+                </Label>
+                <p className="text-sm text-muted-foreground">
+                  Get notified when blocked sites are removed from the hosts file
+                </p>
+              </div>
+              <Switch
+                checked={settings.sendTamperNotifications ?? true}
+                onCheckedChange={(checked) =>
+                  updateSettings({ sendTamperNotifications: checked })
+                }
+                className="scale-125 shadow-md"
+              />
+            </div>
           </CardContent>
         </Card>
 
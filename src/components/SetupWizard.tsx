@@ -259,9 +259,7 @@ export default function SetupWizard() {
                     </li>
                     <li className="flex items-start gap-2">
                       <span className="text-primary mt-0.5">✓</span>
-                      <span>
-                        Block websites (requires browser processes to be killed)
-                      </span>
+                      <span>Block websites for the whole system</span>
                     </li>
                     <li className="flex items-start gap-2">
                       <span className="text-primary mt-0.5">✓</span>
@@ -0,0 +1,11 @@
+// Allowlists are only enforced by the DNS sinkhole; without it they do nothing
+export default function SinkholeRequiredWarning() {
+  return (
+    <div className="mt-4 p-3 bg-yellow-500/10 border border-yellow-500/20 rounded-lg">
+      <p className="text-sm text-yellow-600 dark:text-yellow-500">
+        ⚠️ <strong>DNS sinkhole is off.</strong> Allowlists are enforced by the
+        DNS sinkhole and have no effect until you enable it in Settings.
+      </p>
+    </div>
+  );
+}
@@ -0,0 +1,157 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { useBlockerContext } from "@/contexts/BlockerContext";
+import { useSettings } from "@/hooks/useSettings";
+import { UrlBlockRule } from "@/types";
+import { generateId } from "@/lib/helpers";
+import RuleTimingTabs, {
+  DEFAULT_RULE_TIMING,
+  RuleTimingInput,
+  describeTimingForWebhook,
+  ruleTimingFields,
+} from "./RuleTimingTabs";
+
+interface UrlRuleDialogProps {
+  open: boolean;
+  onOpenChange: (open: boolean) => void;
+}
+
+// Same host check as the backend, so bad patterns are caught before saving
+function patternError(pattern: string): string | null {
+  if (!pattern) return null;
+
+  const schemeEnd = pattern.indexOf("://");
+  const withoutScheme =
+    schemeEnd >= 0 ? pattern.slice(schemeEnd + 3) : pattern;
+  const host = withoutScheme
+    .split("/")[0]
+    .replace(/^(\*\.)+/, "")
+    .replace(/\.+$/, "");
+  if (!/^[A-Za-z0-9._-]+$/.test(host)) {
+    return "Start the pattern with a domain, like youtube.com/shorts";
+  }
+  return null;
+}
+
+export default function UrlRuleDialog({
+  open,
+  onOpenChange,
+}: UrlRuleDialogProps) {
+  const { addUrlRule } = useBlockerContext();
+  const { settings } = useSettings();
+
+  const [pattern, setPattern] = useState("");
+  const [timing, setTiming] = useState<RuleTimingInput>(DEFAULT_RULE_TIMING);
+
+  const trimmed = pattern.trim();
+  const error = patternError(trimmed);
+
+  const handleClose = () => {
+    setPattern("");
+    setTiming(DEFAULT_RULE_TIMING);
+    onOpenChange(false);
+  };
+
+  const handleAddRule = async () => {
+    if (!trimmed || error) return;
+
+    const rule: UrlBlockRule = {
+      id: generateId(),
+      pattern: trimmed,
+      isActive: true,
+      createdAt: Date.now(),
+      ...ruleTimingFields(timing),
+    };
+    addUrlRule(rule);
+
+    // Send webhook notification if enabled
+    if (
+      settings.webhookEnabled &&
+      settings.webhookUrl &&
+      settings.sendBlockNotifications
+    ) {
+      try {
+        const message = `🔒 **URL Rule Added**\n\n**Pattern:** ${
+          rule.pattern
+        }${describeTimingForWebhook(timing)}`;
+        await invoke("send_discord_webhook", {
+          webhookUrl: settings.webhookUrl,
+          message,
+        });
+      } catch (error) {
+        console.error("Failed to send webhook:", error);
+      }
+    }
+
+    handleClose();
+  };
+
+  return (
+    <Dialog open={open} onOpenChange={handleClose}>
+      <DialogContent className="max-w-3xl max-h-[90vh] overflow-y-auto">
+        <DialogHeader>
+          <DialogTitle>Add URL Rule</DialogTitle>
+          <DialogDescription>
+            Block part of a website, such as youtube.com/shorts, in browsers
+            with the companion extension installed
+          </DialogDescription>
+        </DialogHeader>
+
+        <div className="space-y-6">
+          <div>
+            <Label className="text-base font-semibold mb-3 block">
+              URL Pattern
+            </Label>
+            <Input
+              placeholder="reddit.com/r/all"
+              value={pattern}
+              onChange={(e) => setPattern(e.target.value)}
+              onKeyDown={(e) => {
+                if (e.key === "Enter") {
+                  e.preventDefault();
+                  handleAddRule();
+                }
+              }}
+            />
+            {error ? (
+              <p className="text-sm text-destructive mt-2">{error}</p>
+            ) : (
+              <p className="text-sm text-muted-foreground mt-2">
+                Matches the domain and its subdomains. Use * to match any
+                part of the path, as in example.com/*/comments.
+              </p>
+            )}
+          </div>
+
+          <RuleTimingTabs
+            value={timing}
+            onChange={setTiming}
+            permanentDescription="Matching pages stay blocked until you remove the rule."
+            timerDescription={(minutes) =>
+              `Matching pages will be blocked for ${minutes} minutes starting now.`
+            }
+          />
+
+          <div className="flex justify-between pt-4">
+            <Button variant="outline" onClick={handleClose}>
+              Cancel
+            </Button>
+            <Button onClick={handleAddRule} disabled={!trimmed || !!error}>
+              Add URL Rule
+            </Button>
+          </div>
+        </div>
+      </DialogContent>
+    </Dialog>
+  );
+}
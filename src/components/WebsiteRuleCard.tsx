@@ -131,6 +131,11 @@ export default function WebsiteRuleCard({
           <div className="flex-1 min-w-0">
             <div className="flex items-center gap-2 mb-1">
               <h3 className="font-semibold truncate">{rule.domain}</h3>
+              {rule.domains && rule.domains.length > 0 && (
+                <Badge variant="outline">
+                  {rule.domains.length.toLocaleString()} domains
+                </Badge>
+              )}
               <Badge
                 variant={active ? "default" : "secondary"}
                 className="capitalize"
@@ -1,6 +1,6 @@
 import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Globe, Plus, X } from "lucide-react";
+import { FileUp, Globe, Plus, X } from "lucide-react";
 import {
   Dialog,
   DialogContent,
@@ -14,9 +14,10 @@ import { Label } from "@/components/ui/label";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
 import { Card, CardContent } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
+import { Switch } from "@/components/ui/switch";
 import { useBlockerContext } from "@/contexts/BlockerContext";
 import { useSettings } from "@/hooks/useSettings";
-import { WebsiteBlockRule } from "@/types";
+import { BlocklistImportSummary, WebsiteBlockRule } from "@/types";
 import { generateId, isOvernightSchedule } from "@/lib/helpers";
 import { WEBSITE_CATEGORIES, normalizeDomain } from "@/lib/websiteCategories";
 
@@ -29,13 +30,15 @@ export default function WebsiteRuleDialog({
   open,
   onOpenChange,
 }: WebsiteRuleDialogProps) {
-  const { addWebsiteRule, websiteRules } = useBlockerContext();
+  const { addWebsiteRule, reloadWebsiteRules, websiteRules } =
+    useBlockerContext();
   const { settings } = useSettings();
 
   const [step, setStep] = useState<"select" | "configure">("select");
   const [selectedCategories, setSelectedCategories] = useState<string[]>([]);
   const [customDomains, setCustomDomains] = useState<string[]>([]);
   const [customDomainInput, setCustomDomainInput] = useState("");
+  const [blocklistPath, setBlocklistPath] = useState<string | null>(null);
 
   // Rule configuration
   const [ruleType, setRuleType] = useState<"timer" | "schedule" | "permanent">(
@@ -47,6 +50,7 @@ export default function WebsiteRuleDialog({
   const [startMinute, setStartMinute] = useState("0");
   const [endHour, setEndHour] = useState("17");
   const [endMinute, setEndMinute] = useState("0");
+  const [includeSubdomains, setIncludeSubdomains] = useState(true);
 
   // Calculate if schedule is overnight
   const isOvernight =
@@ -63,6 +67,7 @@ export default function WebsiteRuleDialog({
     setSelectedCategories([]);
     setCustomDomains([]);
     setCustomDomainInput("");
+    setBlocklistPath(null);
     setRuleType("permanent");
     setDuration("30");
     setDays([]);
@@ -70,6 +75,7 @@ export default function WebsiteRuleDialog({
     setStartMinute("0");
     setEndHour("17");
     setEndMinute("0");
+    setIncludeSubdomains(true);
     onOpenChange(false);
   };
 
@@ -95,14 +101,82 @@ export default function WebsiteRuleDialog({
     setCustomDomains(customDomains.filter((d) => d !== domain));
   };
 
+  const browseBlocklist = async () => {
+    try {
+      const path = await invoke<string | null>("browse_for_blocklist");
+      if (path) setBlocklistPath(path);
+    } catch (error) {
+      console.error("Failed to select blocklist:", error);
+    }
+  };
+
+  const nothingSelected =
+    selectedCategories.length === 0 &&
+    customDomains.length === 0 &&
+    !blocklistPath;
+
   const handleNext = () => {
-    if (selectedCategories.length === 0 && customDomains.length === 0) {
+    if (nothingSelected) {
       return;
     }
     setStep("configure");
   };
 
+  // Timing shared by every rule created in this dialog
+  const ruleTiming = (): Partial<WebsiteBlockRule> => {
+    if (ruleType === "timer") {
+      return { duration: parseInt(duration), startTime: Date.now() };
+    }
+    if (ruleType === "schedule") {
+      return {
+        days,
+        startHour: parseInt(startHour),
+        startMinute: parseInt(startMinute),
+        endHour: parseInt(endHour),
+        endMinute: parseInt(endMinute),
+      };
+    }
+    return {};
+  };
+
+  // Imports run in Rust, which stores the whole list as a single rule; large
+  // lists would be far too slow to add one by one from here
+  const importBlocklist = async (path: string): Promise<number> => {
+    try {
+      const summary = await invoke<BlocklistImportSummary>("import_blocklist", {
+        path,
+        template: { type: ruleType, includeSubdomains, ...ruleTiming() },
+      });
+      await reloadWebsiteRules();
+
+      let message = `Imported ${summary.imported} website(s) from the blocklist as one rule.`;
+      if (summary.alreadyBlocked > 0) {
+        message += `\n${summary.alreadyBlocked} already had blocking rules.`;
+      }
+      if (summary.duplicates > 0) {
+        message += `\n${summary.duplicates} duplicate entries were skipped.`;
+      }
+      if (summary.unsupported > 0) {
+        message += `\n${summary.unsupported} unsupported entries were skipped.`;
+      }
+      if (summary.invalid > 0) {
+        message += `\n${summary.invalid} invalid entries were skipped (e.g. ${summary.invalidExamples
+          .slice(0, 3)
+          .join(", ")}).`;
+      }
+      alert(message);
+      return summary.imported;
+    } catch (error) {
+      alert(`Failed to import blocklist: ${error}`);
+      return 0;
+    }
+  };
+
   const handleAddRules = async () => {
+    const importedCount = blocklistPath
+      ? await importBlocklist(blocklistPath)
+      : 0;
+
     const domains: string[] = [];
 
     // Collect domains from selected categories
@@ -121,7 +195,9 @@ export default function WebsiteRuleDialog({
 
     // Check for existing rules and filter out duplicates
     const existingDomains = new Set(
-      websiteRules.map((r) => r.domain.toLowerCase())
+      websiteRules.flatMap((r) =>
+        (r.domains?.length ? r.domains : [r.domain]).map((d) => d.toLowerCase())
+      )
     );
     const newDomains = uniqueDomains.filter(
       (d) => !existingDomains.has(d.toLowerCase())
@@ -143,8 +219,10 @@ export default function WebsiteRuleDialog({
     }
 
     // If all domains are duplicates, don't proceed
-    if (newDomains.length === 0) {
-      alert("All selected websites already have blocking rules.");
+    if (newDomains.length === 0 && importedCount === 0) {
+      if (uniqueDomains.length > 0) {
+        alert("All selected websites already have blocking rules.");
+      }
       return;
     }
 
@@ -156,19 +234,10 @@ export default function WebsiteRuleDialog({
         type: ruleType,
         isActive: true,
         createdAt: Date.now(),
+        includeSubdomains,
+        ...ruleTiming(),
       };
 
-      if (ruleType === "timer") {
-        rule.duration = parseInt(duration);
-        rule.startTime = Date.now();
-      } else if (ruleType === "schedule") {
-        rule.days = days;
-        rule.startHour = parseInt(startHour);
-        rule.startMinute = parseInt(startMinute);
-        rule.endHour = parseInt(endHour);
-        rule.endMinute = parseInt(endMinute);
-      }
-
       addWebsiteRule(rule);
     }
 
@@ -184,7 +253,9 @@ export default function WebsiteRuleDialog({
           .filter(Boolean)
           .join(", ");
 
-        let message = `🌐 **Website Blocking Enabled**\n\n**Domains blocked:** ${uniqueDomains.length}\n**Type:** ${ruleType}`;
+        let message = `🌐 **Website Blocking Enabled**\n\n**Domains blocked:** ${
+          uniqueDomains.length + importedCount
+        }\n**Type:** ${ruleType}`;
 
         if (categoryNames) {
           message += `\n**Categories:** ${categoryNames}`;
@@ -194,6 +265,10 @@ export default function WebsiteRuleDialog({
           message += `\n**Custom domains:** ${customDomains.join(", ")}`;
         }
 
+        if (importedCount > 0) {
+          message += `\n**Imported from blocklist:** ${importedCount}`;
+        }
+
         if (ruleType === "timer") {
           message += `\n**Duration:** ${duration} minutes`;
         } else if (ruleType === "schedule") {
@@ -239,13 +314,11 @@ export default function WebsiteRuleDialog({
               : "Set when and how long these websites should be blocked"}
           </DialogDescription>
 
-          {/* Warning about browser killing */}
+          {/* Browsers keep connections and DNS answers for a while */}
           <div className="mt-4 p-3 bg-yellow-500/10 border border-yellow-500/20 rounded-lg">
             <p className="text-sm text-yellow-600 dark:text-yellow-500">
-              ⚠️ <strong>Note:</strong> Website blocking works by closing all
-              browser windows (Edge, Chrome, Firefox, etc.) when active. This is
-              necessary because modern browsers bypass traditional blocking
-              methods.
+              ⚠️ <strong>Note:</strong> Tabs that are already open may keep
+              loading a site until the browser is restarted.
             </p>
           </div>
         </DialogHeader>
@@ -330,16 +403,39 @@ export default function WebsiteRuleDialog({
               )}
             </div>
 
+            {/* Blocklist Import */}
+            <div>
+              <Label className="text-base font-semibold mb-3 block">
+                Import Blocklist
+              </Label>
+              <p className="text-sm text-muted-foreground mb-3">
+                Hosts files, one domain per line, or AdBlock{" "}
+                <code>||domain^</code> lists
+              </p>
+              {blocklistPath ? (
+                <Badge variant="secondary" className="px-3 py-1 max-w-full">
+                  <FileUp className="h-3 w-3 mr-1 shrink-0" />
+                  <span className="truncate">{blocklistPath}</span>
+                  <button
+                    onClick={() => setBlocklistPath(null)}
+                    className="ml-2 hover:text-destructive"
+                  >
+                    <X className="h-3 w-3" />
+                  </button>
+                </Badge>
+              ) : (
+                <Button variant="outline" onClick={browseBlocklist}>
+                  <FileUp className="h-4 w-4 mr-2" />
+                  Choose File
+                </Button>
+              )}
+            </div>
+
             <div className="flex justify-between pt-4">
               <Button variant="outline" onClick={handleClose}>
                 Cancel
               </Button>
-              <Button
-                onClick={handleNext}
-                disabled={
-                  selectedCategories.length === 0 && customDomains.length === 0
-                }
-              >
+              <Button onClick={handleNext} disabled={nothingSelected}>
                 Next: Configure Rule
               </Button>
             </div>
@@ -464,6 +560,20 @@ export default function WebsiteRuleDialog({
               </TabsContent>
             </Tabs>
 
+            <div className="flex items-center justify-between rounded-lg border p-4">
+              <div className="space-y-1">
+                <Label>Block subdomains</Label>
+                <p className="text-sm text-muted-foreground">
+                  With the DNS sinkhole, also block every subdomain (e.g.
+                  m.youtube.com for youtube.com)
+                </p>
+              </div>
+              <Switch
+                checked={includeSubdomains}
+                onCheckedChange={setIncludeSubdomains}
+              />
+            </div>
+
             <div className="flex justify-between pt-4">
               <Button variant="outline" onClick={() => setStep("select")}>
                 Back
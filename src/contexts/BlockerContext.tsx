@@ -1,7 +1,11 @@
 import { createContext, useContext, ReactNode } from "react";
 import { useBlocker } from "@/hooks/useBlocker";
-import { useWebsiteBlocker } from "@/hooks/useWebsiteBlocker";
-import { BlockRule, WebsiteBlockRule } from "@/types";
+import {
+  AllowlistRule,
+  BlockRule,
+  UrlBlockRule,
+  WebsiteBlockRule,
+} from "@/types";
 
 interface BlockerContextType {
   rules: BlockRule[];
@@ -20,6 +24,7 @@ interface BlockerContextType {
       | ((prev: WebsiteBlockRule[]) => WebsiteBlockRule[])
   ) => void;
   addWebsiteRule: (rule: WebsiteBlockRule) => void;
+  reloadWebsiteRules: () => Promise<void>;
   removeWebsiteRule: (ruleId: string) => void;
   updateWebsiteRule: (
     ruleId: string,
@@ -27,6 +32,19 @@ interface BlockerContextType {
   ) => void;
   cleanupExpiredWebsiteTimers: () => number;
 
+  urlRules: UrlBlockRule[];
+  addUrlRule: (rule: UrlBlockRule) => void;
+  removeUrlRule: (ruleId: string) => void;
+  updateUrlRule: (ruleId: string, updates: Partial<UrlBlockRule>) => void;
+
+  allowlistRules: AllowlistRule[];
+  addAllowlistRule: (rule: AllowlistRule) => void;
+  removeAllowlistRule: (ruleId: string) => void;
+  updateAllowlistRule: (
+    ruleId: string,
+    updates: Partial<AllowlistRule>
+  ) => void;
+
   isEnforcing: boolean;
   setIsEnforcing: (enforcing: boolean) => void;
 }
@@ -36,9 +54,6 @@ const BlockerContext = createContext<BlockerContextType | undefined>(undefined);
 export function BlockerProvider({ children }: { children: ReactNode }) {
   const blocker = useBlocker();
 
-  // Apply website blocking based on active rules
-  useWebsiteBlocker(blocker.websiteRules, blocker.isEnforcing);
-
   return (
     <BlockerContext.Provider value={blocker}>
       {children}
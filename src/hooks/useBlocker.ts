@@ -1,43 +1,30 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { BlockRule, WebsiteBlockRule } from "@/types";
+import { listen } from "@tauri-apps/api/event";
+import {
+  AllowlistRule,
+  BlockRule,
+  ProcessKilledEvent,
+  UrlBlockRule,
+  WebsiteBlockRule,
+} from "@/types";
 import { storage } from "@/lib/storage";
-import { isRuleActive } from "@/lib/helpers";
 
 export function useBlocker() {
   const [rules, setRulesState] = useState<BlockRule[]>([]);
   const [websiteRules, setWebsiteRulesState] = useState<WebsiteBlockRule[]>([]);
+  const [urlRules, setUrlRulesState] = useState<UrlBlockRule[]>([]);
+  const [allowlistRules, setAllowlistRulesState] = useState<AllowlistRule[]>(
+    []
+  );
   const [isEnforcing, setIsEnforcing] = useState(false);
 
-  // Load rules on mount and when localStorage changes
+  // Load rules on mount (the store is hydrated before the app renders)
   useEffect(() => {
-    const loadRules = () => {
-      const storedRules = storage.getBlockRules();
-      setRulesState(storedRules);
-
-      const storedWebsiteRules = storage.getWebsiteRules();
-      setWebsiteRulesState(storedWebsiteRules);
-    };
-
-    // Load initial rules
-    loadRules();
-
-    // Listen for storage changes from other tabs/windows
-    const handleStorageChange = (e: StorageEvent) => {
-      if (e.key === "neu_block_rules") {
-        const storedRules = storage.getBlockRules();
-        setRulesState(storedRules);
-      } else if (e.key === "neu_website_rules") {
-        const storedWebsiteRules = storage.getWebsiteRules();
-        setWebsiteRulesState(storedWebsiteRules);
-      }
-    };
-
-    window.addEventListener("storage", handleStorageChange);
-
-    return () => {
-      window.removeEventListener("storage", handleStorageChange);
-    };
+    setRulesState(storage.getBlockRules());
+    setWebsiteRulesState(storage.getWebsiteRules());
+    setUrlRulesState(storage.getUrlRules());
+    setAllowlistRulesState(storage.getAllowlistRules());
   }, []);
 
   const setRules = useCallback(
@@ -99,6 +86,10 @@ export function useBlocker() {
     [setWebsiteRules]
   );
 
+  const reloadWebsiteRules = useCallback(async () => {
+    setWebsiteRulesState(await storage.reloadWebsiteRules());
+  }, []);
+
   const removeWebsiteRule = useCallback(
     (ruleId: string) => {
       setWebsiteRules((prev) => prev.filter((r) => r.id !== ruleId));
@@ -115,6 +106,80 @@ export function useBlocker() {
     [setWebsiteRules]
   );
 
+  // URL rule management
+  const setUrlRules = useCallback(
+    (newRules: UrlBlockRule[] | ((prev: UrlBlockRule[]) => UrlBlockRule[])) => {
+      setUrlRulesState((prev) => {
+        const updated =
+          typeof newRules === "function" ? newRules(prev) : newRules;
+        storage.saveUrlRules(updated);
+        return updated;
+      });
+    },
+    []
+  );
+
+  const addUrlRule = useCallback(
+    (rule: UrlBlockRule) => {
+      setUrlRules((prev) => [...prev, rule]);
+    },
+    [setUrlRules]
+  );
+
+  const removeUrlRule = useCallback(
+    (ruleId: string) => {
+      setUrlRules((prev) => prev.filter((r) => r.id !== ruleId));
+    },
+    [setUrlRules]
+  );
+
+  const updateUrlRule = useCallback(
+    (ruleId: string, updates: Partial<UrlBlockRule>) => {
+      setUrlRules((prev) =>
+        prev.map((r) => (r.id === ruleId ? { ...r, ...updates } : r))
+      );
+    },
+    [setUrlRules]
+  );
+
+  // Allowlist rule management
+  const setAllowlistRules = useCallback(
+    (
+      newRules: AllowlistRule[] | ((prev: AllowlistRule[]) => AllowlistRule[])
+    ) => {
+      setAllowlistRulesState((prev) => {
+        const updated =
+          typeof newRules === "function" ? newRules(prev) : newRules;
+        storage.saveAllowlistRules(updated);
+        return updated;
+      });
+    },
+    []
+  );
+
+  const addAllowlistRule = useCallback(
+    (rule: AllowlistRule) => {
+      setAllowlistRules((prev) => [...prev, rule]);
+    },
+    [setAllowlistRules]
+  );
+
+  const removeAllowlistRule = useCallback(
+    (ruleId: string) => {
+      setAllowlistRules((prev) => prev.filter((r) => r.id !== ruleId));
+    },
+    [setAllowlistRules]
+  );
+
+  const updateAllowlistRule = useCallback(
+    (ruleId: string, updates: Partial<AllowlistRule>) => {
+      setAllowlistRules((prev) =>
+        prev.map((r) => (r.id === ruleId ? { ...r, ...updates } : r))
+      );
+    },
+    [setAllowlistRules]
+  );
+
   // Cleanup expired timer rules
   const cleanupExpiredTimers = useCallback(() => {
     const now = Date.now();
@@ -159,114 +224,26 @@ export function useBlocker() {
     return removedCount;
   }, [setWebsiteRules]);
 
-  // Enforcement loop
+  // Enforcement runs in the Rust engine, which reads rules from the store
   useEffect(() => {
-    if (!isEnforcing) return;
-
-    // Rate limiting: max processes to kill per interval
-    const MAX_KILLS_PER_INTERVAL = 50;
-    let killCountThisInterval = 0;
-
-    const interval = setInterval(async () => {
-      // Reset kill counter for new interval
-      killCountThisInterval = 0;
-
-      const activeRules = rules.filter(isRuleActive);
-
-      if (activeRules.length === 0) return;
-
-      try {
-        const processes = await invoke<any[]>("get_running_processes");
+    invoke("set_enforcement_enabled", { enabled: isEnforcing }).catch(
+      (error) => console.error("Failed to toggle enforcement:", error)
+    );
+  }, [isEnforcing]);
 
-        for (const rule of activeRules) {
-          // Check if we've hit the rate limit
-          if (killCountThisInterval >= MAX_KILLS_PER_INTERVAL) {
-            console.warn(
-              `Rate limit reached: ${MAX_KILLS_PER_INTERVAL} processes killed this interval. Skipping remaining processes.`
-            );
-            break;
-          }
-
-          // Improved matching logic:
-          // 1. Compare normalized executable paths directly for exact matches
-          // 2. As fallback, check if process name matches app name (for running processes without full path)
-          const rulePathNormalized = rule.appPath
-            .toLowerCase()
-            .replace(/\\/g, "/");
-          const ruleNameNormalized = rule.appName.toLowerCase();
-
-          const matchingProcesses = processes.filter((p) => {
-            const processPath = (p.path || "")
-              .toLowerCase()
-              .replace(/\\/g, "/");
-            const processName = (p.name || "").toLowerCase();
-
-            // Primary match: exact executable path match
-            if (
-              processPath &&
-              rulePathNormalized &&
-              processPath === rulePathNormalized
-            ) {
-              return true;
-            }
-
-            // Secondary match: executable filename match (last part of path)
-            // This handles cases where the full path might differ slightly
-            const ruleExeName = rulePathNormalized.split("/").pop() || "";
-            const processExeName = processPath.split("/").pop() || "";
-
-            if (
-              ruleExeName &&
-              processExeName &&
-              ruleExeName === processExeName
-            ) {
-              return true;
-            }
-
-            // Tertiary match: process name match (for processes without full path info)
-            // Only match if names are very similar (not just contains)
-            if (processName && ruleNameNormalized) {
-              // Remove .exe extension for comparison
-              const cleanProcessName = processName.replace(".exe", "");
-              const cleanRuleName = ruleNameNormalized.replace(".exe", "");
-
-              if (cleanProcessName === cleanRuleName) {
-                return true;
-              }
-            }
-
-            return false;
-          });
-
-          for (const process of matchingProcesses) {
-            // Check rate limit before each kill
-            if (killCountThisInterval >= MAX_KILLS_PER_INTERVAL) {
-              console.warn(
-                `Rate limit reached during rule processing. Stopping at ${killCountThisInterval} kills.`
-              );
-              break;
-            }
-
-            if (process.pid) {
-              try {
-                await invoke("kill_process", { pid: process.pid });
-                killCountThisInterval++;
-                console.log(
-                  `Blocked and killed: ${process.name} (PID: ${process.pid}) - matched rule: ${rule.appName} [${killCountThisInterval}/${MAX_KILLS_PER_INTERVAL}]`
-                );
-              } catch (error) {
-                console.error(`Failed to kill process ${process.pid}:`, error);
-              }
-            }
-          }
-        }
-      } catch (error) {
-        console.error("Enforcement error:", error);
-      }
-    }, 2000); // Check every 2 seconds
+  // Display kills reported by the enforcement engine
+  useEffect(() => {
+    const unlisten = listen<ProcessKilledEvent>("process-killed", (event) => {
+      const { processName, pid, appName } = event.payload;
+      console.log(
+        `Blocked and killed: ${processName} (PID: ${pid}) - matched rule: ${appName}`
+      );
+    });
 
-    return () => clearInterval(interval);
-  }, [isEnforcing, rules]);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
   return {
     rules,
@@ -278,9 +255,18 @@ export function useBlocker() {
     websiteRules,
     setWebsiteRules,
     addWebsiteRule,
+    reloadWebsiteRules,
     removeWebsiteRule,
     updateWebsiteRule,
     cleanupExpiredWebsiteTimers,
+    urlRules,
+    addUrlRule,
+    removeUrlRule,
+    updateUrlRule,
+    allowlistRules,
+    addAllowlistRule,
+    removeAllowlistRule,
+    updateAllowlistRule,
     isEnforcing,
     setIsEnforcing,
   };
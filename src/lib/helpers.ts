@@ -1,6 +1,13 @@
-import { BlockRule, WebsiteBlockRule } from "@/types";
+import {
+  AllowlistRule,
+  BlockRule,
+  UrlBlockRule,
+  WebsiteBlockRule,
+} from "@/types";
 
-export function isRuleActive(rule: BlockRule | WebsiteBlockRule): boolean {
+type TimedRule = BlockRule | WebsiteBlockRule | UrlBlockRule | AllowlistRule;
+
+export function isRuleActive(rule: TimedRule): boolean {
   if (!rule.isActive) return false;
 
   const now = Date.now();
@@ -15,14 +22,14 @@ export function isRuleActive(rule: BlockRule | WebsiteBlockRule): boolean {
     return now >= rule.startTime && now <= endTime;
   }
 
+  // Keep in sync with `RuleTiming::is_active_at` in src-tauri/src/rules.rs
   if (rule.type === "schedule") {
     const currentDate = new Date();
     const currentDay = currentDate.getDay();
+    const previousDay = (currentDay + 6) % 7;
     const currentMinutes =
       currentDate.getHours() * 60 + currentDate.getMinutes();
-
-    // Check if today is a blocked day
-    if (!rule.days?.includes(currentDay)) return false;
+    const days = rule.days ?? [];
 
     // Check if current time is within blocked hours
     if (
@@ -36,11 +43,18 @@ export function isRuleActive(rule: BlockRule | WebsiteBlockRule): boolean {
 
       // Handle overnight schedules (e.g., 10PM to 6AM)
       if (endMinutes < startMinutes) {
-        // Overnight: active if EITHER after start OR before end
-        return currentMinutes >= startMinutes || currentMinutes <= endMinutes;
+        // Overnight: the part after midnight belongs to the previous day
+        return (
+          (days.includes(currentDay) && currentMinutes >= startMinutes) ||
+          (days.includes(previousDay) && currentMinutes <= endMinutes)
+        );
       } else {
         // Normal same-day schedule: active if between start and end
-        return currentMinutes >= startMinutes && currentMinutes <= endMinutes;
+        return (
+          days.includes(currentDay) &&
+          currentMinutes >= startMinutes &&
+          currentMinutes <= endMinutes
+        );
       }
     }
   }
@@ -83,6 +97,31 @@ export function formatTimeRange(
     : `${startTime} - ${endTime}`;
 }
 
+// One-line summary of when a rule applies, for rule cards
+export function describeRuleTiming(rule: TimedRule): string {
+  if (rule.type === "permanent") {
+    return "Always";
+  }
+  if (rule.type === "timer") {
+    if (!rule.duration || !rule.startTime) return "Timer not configured";
+    const endTime = rule.startTime + rule.duration * 60 * 1000;
+    const remaining = Math.max(0, endTime - Date.now());
+    if (remaining > 0) {
+      return `${formatDuration(Math.floor(remaining / 60000))} remaining`;
+    }
+    return `Timer expired (${formatDuration(rule.duration)})`;
+  }
+  if (!rule.days || !rule.days.length) return "No days selected";
+  const dayNames = rule.days.map(getDayName).join(", ");
+  const timeRange = formatTimeRange(
+    rule.startHour || 0,
+    rule.startMinute || 0,
+    rule.endHour || 0,
+    rule.endMinute || 0
+  );
+  return `${dayNames} • ${timeRange}`;
+}
+
 export function isOvernightSchedule(
   startHour: number,
   startMinute: number,
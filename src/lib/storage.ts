@@ -1,4 +1,12 @@
-import { AppSettings, BlockRule, WebsiteBlockRule, BlockEvent } from "@/types";
+import { invoke } from "@tauri-apps/api/core";
+import {
+  AppSettings,
+  BlockRule,
+  WebsiteBlockRule,
+  UrlBlockRule,
+  AllowlistRule,
+  BlockEvent,
+} from "@/types";
 
 const STORAGE_KEYS = {
   SETTINGS: "neu_settings",
@@ -8,153 +16,244 @@ const STORAGE_KEYS = {
   PIN_SESSION: "neu_pin_session",
 } as const;
 
-// Helper to safely write to localStorage with quota handling
-function safeSetItem(key: string, value: string): void {
+const DEFAULT_SETTINGS: AppSettings = {
+  webhookEnabled: false,
+  sendBlockNotifications: true,
+  sendUnblockNotifications: true,
+  sendKillswitchNotifications: true,
+  isSetupComplete: false,
+  blockingEnabled: false,
+  websiteBlockingEnabled: true, // Enabled by default
+  killGracePeriodMs: 3000,
+  sendTamperNotifications: true,
+  dnsSinkholeEnabled: false,
+  dnsSinkholeListen: "127.0.0.1:53",
+  dnsUpstream: "1.1.1.1:53",
+  websiteBlockBackend: "hosts",
+  blockPageEnabled: false,
+  blockPageListen: "127.0.0.1:80",
+  blockDohProviders: true,
+  browserPoliciesEnabled: false,
+  sniProxyEnabled: false,
+  sniProxyListen: "127.0.0.1:3128",
+};
+
+// In-memory copy of the Rust-owned store. Hydrated once by `storage.init()`
+// before the app renders; every write goes through to the Rust store.
+const cache = {
+  settings: { ...DEFAULT_SETTINGS } as AppSettings,
+  blockRules: [] as BlockRule[],
+  websiteRules: [] as WebsiteBlockRule[],
+  urlRules: [] as UrlBlockRule[],
+  allowlistRules: [] as AllowlistRule[],
+};
+
+function persist(command: string, args: Record<string, unknown>): void {
+  invoke(command, args).catch((error) =>
+    console.error(`Failed to persist (${command}):`, error)
+  );
+}
+
+function readLegacy<T>(key: string): T | undefined {
+  const stored = localStorage.getItem(key);
+  if (!stored) return undefined;
   try {
-    localStorage.setItem(key, value);
-  } catch (error) {
-    if (error instanceof DOMException && 
-        (error.name === 'QuotaExceededError' || 
-         error.name === 'NS_ERROR_DOM_QUOTA_REACHED')) {
-      // Storage quota exceeded
-      console.error('LocalStorage quota exceeded for key:', key);
-      
-      // If it's events, automatically clean up old events
-      if (key === STORAGE_KEYS.EVENTS) {
-        console.log('Auto-cleaning old events due to quota limit...');
-        const events: BlockEvent[] = JSON.parse(value);
-        // Keep only last 100 events
-        const trimmedEvents = events.slice(-100);
-        try {
-          localStorage.setItem(key, JSON.stringify(trimmedEvents));
-          console.log(`Trimmed events from ${events.length} to ${trimmedEvents.length}`);
-          return;
-        } catch (retryError) {
-          console.error('Failed to save even after trimming:', retryError);
-        }
-      }
-      
-      // Throw a user-friendly error
-      throw new Error(
-        'Storage limit exceeded. Please export your data and clear old events in the Events tab.'
-      );
+    return JSON.parse(stored) as T;
+  } catch {
+    return undefined;
+  }
+}
+
+// Persist only the rules that changed, and delete the ones that were removed
+function syncRules<T extends { id: string }>(
+  previous: T[],
+  next: T[],
+  saveCommand: string,
+  deleteCommand: string
+): void {
+  const previousById = new Map(previous.map((r) => [r.id, r]));
+  const nextIds = new Set(next.map((r) => r.id));
+
+  for (const rule of next) {
+    const old = previousById.get(rule.id);
+    if (!old || JSON.stringify(old) !== JSON.stringify(rule)) {
+      persist(saveCommand, { rule });
+    }
+  }
+  for (const rule of previous) {
+    if (!nextIds.has(rule.id)) {
+      persist(deleteCommand, { ruleId: rule.id });
     }
-    // Re-throw other errors
-    throw error;
   }
 }
 
 export const storage = {
+  // Migrate legacy localStorage data (first launch only) and load the store
+  async init(): Promise<void> {
+    const legacyKeys = [
+      STORAGE_KEYS.SETTINGS,
+      STORAGE_KEYS.BLOCK_RULES,
+      STORAGE_KEYS.WEBSITE_RULES,
+      STORAGE_KEYS.EVENTS,
+    ];
+
+    const migrated = await invoke<boolean>("migrate_local_storage", {
+      legacy: {
+        settings: readLegacy<AppSettings>(STORAGE_KEYS.SETTINGS),
+        blockRules: readLegacy<BlockRule[]>(STORAGE_KEYS.BLOCK_RULES) ?? [],
+        websiteRules:
+          readLegacy<WebsiteBlockRule[]>(STORAGE_KEYS.WEBSITE_RULES) ?? [],
+        events: readLegacy<BlockEvent[]>(STORAGE_KEYS.EVENTS) ?? [],
+      },
+    });
+    if (migrated) {
+      legacyKeys.forEach((key) => localStorage.removeItem(key));
+    }
+
+    const [settings, blockRules, websiteRules, urlRules, allowlistRules] =
+      await Promise.all([
+        invoke<AppSettings>("get_settings"),
+        invoke<BlockRule[]>("get_rules"),
+        invoke<WebsiteBlockRule[]>("get_website_rules"),
+        invoke<UrlBlockRule[]>("get_url_rules"),
+        invoke<AllowlistRule[]>("get_allowlist_rules"),
+      ]);
+    cache.settings = settings;
+    cache.blockRules = blockRules;
+    cache.websiteRules = websiteRules;
+    cache.urlRules = urlRules;
+    cache.allowlistRules = allowlistRules;
+  },
+
   // Settings
   getSettings(): AppSettings {
-    const stored = localStorage.getItem(STORAGE_KEYS.SETTINGS);
-    if (!stored) {
-      return {
-        webhookEnabled: false,
-        sendBlockNotifications: true,
-        sendUnblockNotifications: true,
-        sendKillswitchNotifications: true,
-        isSetupComplete: false,
-        blockingEnabled: false,
-        websiteBlockingEnabled: true, // Enabled by default
-      };
-    }
-    const parsed = JSON.parse(stored);
-    // Add websiteBlockingEnabled default for existing users
-    if (parsed.websiteBlockingEnabled === undefined) {
-      parsed.websiteBlockingEnabled = true;
-    }
-    return parsed;
+    return cache.settings;
   },
 
   saveSettings(settings: AppSettings): void {
-    safeSetItem(STORAGE_KEYS.SETTINGS, JSON.stringify(settings));
+    cache.settings = settings;
+    persist("save_settings", { settings });
   },
 
   // Block Rules
   getBlockRules(): BlockRule[] {
-    const stored = localStorage.getItem(STORAGE_KEYS.BLOCK_RULES);
-    return stored ? JSON.parse(stored) : [];
+    return cache.blockRules;
   },
 
   saveBlockRules(rules: BlockRule[]): void {
-    safeSetItem(STORAGE_KEYS.BLOCK_RULES, JSON.stringify(rules));
+    syncRules(cache.blockRules, rules, "save_rule", "delete_rule");
+    cache.blockRules = rules;
   },
 
   addBlockRule(rule: BlockRule): void {
-    const rules = this.getBlockRules();
-    rules.push(rule);
-    this.saveBlockRules(rules);
+    this.saveBlockRules([...this.getBlockRules(), rule]);
   },
 
   removeBlockRule(ruleId: string): void {
-    const rules = this.getBlockRules().filter((r) => r.id !== ruleId);
-    this.saveBlockRules(rules);
+    this.saveBlockRules(this.getBlockRules().filter((r) => r.id !== ruleId));
   },
 
   updateBlockRule(ruleId: string, updates: Partial<BlockRule>): void {
-    const rules = this.getBlockRules().map((r) =>
-      r.id === ruleId ? { ...r, ...updates } : r
+    this.saveBlockRules(
+      this.getBlockRules().map((r) =>
+        r.id === ruleId ? { ...r, ...updates } : r
+      )
     );
-    this.saveBlockRules(rules);
   },
 
   // Website Rules
   getWebsiteRules(): WebsiteBlockRule[] {
-    const stored = localStorage.getItem(STORAGE_KEYS.WEBSITE_RULES);
-    return stored ? JSON.parse(stored) : [];
+    return cache.websiteRules;
   },
 
   saveWebsiteRules(rules: WebsiteBlockRule[]): void {
-    safeSetItem(STORAGE_KEYS.WEBSITE_RULES, JSON.stringify(rules));
+    syncRules(
+      cache.websiteRules,
+      rules,
+      "save_website_rule",
+      "delete_website_rule"
+    );
+    cache.websiteRules = rules;
+  },
+
+  // Re-read rules the Rust side added on its own (e.g. a blocklist import)
+  async reloadWebsiteRules(): Promise<WebsiteBlockRule[]> {
+    cache.websiteRules = await invoke<WebsiteBlockRule[]>("get_website_rules");
+    return cache.websiteRules;
   },
 
   addWebsiteRule(rule: WebsiteBlockRule): void {
-    const rules = this.getWebsiteRules();
-    rules.push(rule);
-    this.saveWebsiteRules(rules);
+    this.saveWebsiteRules([...this.getWebsiteRules(), rule]);
   },
 
   removeWebsiteRule(ruleId: string): void {
-    const rules = this.getWebsiteRules().filter((r) => r.id !== ruleId);
-    this.saveWebsiteRules(rules);
+    this.saveWebsiteRules(
+      this.getWebsiteRules().filter((r) => r.id !== ruleId)
+    );
   },
 
   updateWebsiteRule(ruleId: string, updates: Partial<WebsiteBlockRule>): void {
-    const rules = this.getWebsiteRules().map((r) =>
-      r.id === ruleId ? { ...r, ...updates } : r
+    this.saveWebsiteRules(
+      this.getWebsiteRules().map((r) =>
+        r.id === ruleId ? { ...r, ...updates } : r
+      )
     );
-    this.saveWebsiteRules(rules);
   },
 
-  // Events
-  getEvents(): BlockEvent[] {
-    const stored = localStorage.getItem(STORAGE_KEYS.EVENTS);
-    return stored ? JSON.parse(stored) : [];
+  // URL Rules (path-level, enforced by the browser extension)
+  getUrlRules(): UrlBlockRule[] {
+    return cache.urlRules;
   },
 
-  saveEvents(events: BlockEvent[]): void {
-    safeSetItem(STORAGE_KEYS.EVENTS, JSON.stringify(events));
+  saveUrlRules(rules: UrlBlockRule[]): void {
+    syncRules(cache.urlRules, rules, "save_url_rule", "delete_url_rule");
+    cache.urlRules = rules;
   },
 
-  addEvent(event: BlockEvent): void {
-    const events = this.getEvents();
-    events.unshift(event); // Add to beginning
-    // Keep only last 100 events
-    if (events.length > 100) {
-      events.splice(100);
-    }
-    this.saveEvents(events);
+  addUrlRule(rule: UrlBlockRule): void {
+    this.saveUrlRules([...this.getUrlRules(), rule]);
   },
 
-  clearEvents(): void {
-    localStorage.removeItem(STORAGE_KEYS.EVENTS);
+  removeUrlRule(ruleId: string): void {
+    this.saveUrlRules(this.getUrlRules().filter((r) => r.id !== ruleId));
   },
 
-  clearAllData(): void {
-    Object.values(STORAGE_KEYS).forEach((key) => {
-      localStorage.removeItem(key);
-    });
+  // Allowlist rules (allowlist-only mode, enforced by the DNS sinkhole)
+  getAllowlistRules(): AllowlistRule[] {
+    return cache.allowlistRules;
+  },
+
+  saveAllowlistRules(rules: AllowlistRule[]): void {
+    syncRules(
+      cache.allowlistRules,
+      rules,
+      "save_allowlist_rule",
+      "delete_allowlist_rule"
+    );
+    cache.allowlistRules = rules;
+  },
+
+  addAllowlistRule(rule: AllowlistRule): void {
+    this.saveAllowlistRules([...this.getAllowlistRules(), rule]);
+  },
+
+  removeAllowlistRule(ruleId: string): void {
+    this.saveAllowlistRules(
+      this.getAllowlistRules().filter((r) => r.id !== ruleId)
+    );
+  },
+
+  // Events (newest first, capped on the Rust side)
+  getEvents(): Promise<BlockEvent[]> {
+    return invoke<BlockEvent[]>("get_events");
+  },
+
+  addEvent(event: BlockEvent): void {
+    persist("append_event", { event });
+  },
+
+  clearEvents(): Promise<void> {
+    return invoke("clear_events");
   },
 
   // PIN Session Management
@@ -2,6 +2,7 @@ export interface AppInfo {
   name: string;
   path: string;
   pid?: number;
+  icon?: string; // data URL, currently only provided on Linux
 }
 
 export interface BlockRule {
@@ -22,14 +23,79 @@ export interface BlockRule {
   startMinute?: number;
   endHour?: number;
   endMinute?: number;
+
+  // Process matching (empty = exact path, file name, process name)
+  matchCriteria?: MatchCriterion[];
+  exeSha256?: string;
 }
 
+export type MatchCriterion =
+  | { kind: "exactPath"; path: string }
+  | { kind: "nameGlob"; pattern: string }
+  | { kind: "commandLineRegex"; pattern: string }
+  | { kind: "parentName"; name: string }
+  | { kind: "sha256"; hash: string };
+
+export type MatchReason =
+  | { kind: "exactPath"; path: string }
+  | { kind: "nameGlob"; pattern: string; name: string }
+  | { kind: "commandLineRegex"; pattern: string }
+  | { kind: "parentName"; name: string }
+  | { kind: "sha256"; hash: string };
+
 export interface WebsiteBlockRule {
   id: string;
   domain: string;
   type: "timer" | "schedule" | "permanent";
   isActive: boolean;
   createdAt: number;
+  // DNS sinkhole only: also block every subdomain (defaults to true)
+  includeSubdomains?: boolean;
+  // Imported blocklists: every domain of the list; `domain` is then the
+  // list's name
+  domains?: string[];
+
+  // Timer specific
+  duration?: number;
+  startTime?: number;
+
+  // Schedule specific
+  days?: number[];
+  startHour?: number;
+  startMinute?: number;
+  endHour?: number;
+  endMinute?: number;
+}
+
+// Part of a site, e.g. "youtube.com/shorts"; enforced by the browser extension
+export interface UrlBlockRule {
+  id: string;
+  pattern: string;
+  type: "timer" | "schedule" | "permanent";
+  isActive: boolean;
+  createdAt: number;
+
+  // Timer specific
+  duration?: number;
+  startTime?: number;
+
+  // Schedule specific
+  days?: number[];
+  startHour?: number;
+  startMinute?: number;
+  endHour?: number;
+  endMinute?: number;
+}
+
+// Allowlist-only mode: while active, the DNS sinkhole blocks every domain
+// except these and their subdomains
+export interface AllowlistRule {
+  id: string;
+  name: string;
+  domains: string[];
+  type: "timer" | "schedule" | "permanent";
+  isActive: boolean;
+  createdAt: number;
 
   // Timer specific
   duration?: number;
@@ -43,6 +109,18 @@ export interface WebsiteBlockRule {
   endMinute?: number;
 }
 
+export interface BlocklistImportSummary {
+  hostsEntries: number;
+  plainEntries: number;
+  adblockEntries: number;
+  duplicates: number;
+  invalid: number;
+  invalidExamples: string[];
+  unsupported: number;
+  alreadyBlocked: number;
+  imported: number;
+}
+
 export interface AppSettings {
   pinHash?: string;
   webhookUrl?: string;
@@ -53,6 +131,132 @@ export interface AppSettings {
   isSetupComplete: boolean;
   blockingEnabled: boolean;
   websiteBlockingEnabled: boolean;
+  killGracePeriodMs?: number;
+  sendTamperNotifications?: boolean;
+  dnsSinkholeEnabled?: boolean;
+  dnsSinkholeListen?: string;
+  dnsUpstream?: string;
+  websiteBlockBackend?: WebsiteBlockBackendKind;
+  blockPageEnabled?: boolean;
+  blockPageListen?: string;
+  blockDohProviders?: boolean;
+  browserPoliciesEnabled?: boolean;
+  // Companion extension the browser policies force-install
+  chromiumExtensionId?: string;
+  firefoxExtensionId?: string;
+  sniProxyEnabled?: boolean;
+  sniProxyListen?: string;
+}
+
+export type WebsiteBlockBackendKind =
+  | "hosts"
+  | "dnsmasq"
+  | "systemdResolved"
+  | "nftables";
+
+export interface WebsiteBlockBackendStatus {
+  kind: WebsiteBlockBackendKind;
+  active: boolean;
+  available: boolean;
+  unavailableReason?: string;
+  healthy: boolean;
+  healthError?: string;
+  blockedDomains: string[];
+}
+
+export type DomainErrorReason =
+  | { kind: "empty" }
+  | { kind: "ipAddress" }
+  | { kind: "whitespace" }
+  | { kind: "invalidIdn" }
+  | { kind: "tooLong" }
+  | { kind: "emptyLabel" }
+  | { kind: "labelTooLong"; label: string }
+  | { kind: "invalidCharacter"; label: string }
+  | { kind: "hyphenAtLabelEdge"; label: string }
+  | { kind: "singleLabel" }
+  | { kind: "numericTopLevel" };
+
+export interface DomainError {
+  input: string;
+  reason: DomainErrorReason;
+  message: string;
+}
+
+export interface DomainVerification {
+  domain: string;
+  status: "blocked" | "unresolved" | "notBlocked";
+  addresses: string[];
+}
+
+export interface WebsiteBlockApplyResult {
+  applied: string[];
+  invalid: DomainError[];
+  flushedCaches: string[];
+  verification: DomainVerification[];
+  unverified: number;
+}
+
+export interface ExternalHostsEntry {
+  domain: string;
+  name: string;
+  address: string;
+  line: number;
+  effective: boolean;
+  sinkhole: boolean;
+}
+
+export interface WebsiteBlockStatus {
+  backend: WebsiteBlockBackendKind;
+  websiteBlockingEnabled: boolean;
+  required: string[];
+  blocked: string[];
+  missing: string[];
+  extra: string[];
+  external: ExternalHostsEntry[];
+  inSync: boolean;
+  doh: DohStatus;
+}
+
+export interface BrowserDohPolicy {
+  browser: string;
+  installed: boolean;
+  supported: boolean;
+  dohDisabled: boolean;
+  location?: string;
+  error?: string;
+}
+
+export interface BrowserUrlPolicy {
+  browser: string;
+  installed: boolean;
+  supported: boolean;
+  entries: number;
+  conflict: boolean;
+  extensionForced: boolean;
+  location?: string;
+  error?: string;
+}
+
+export interface BrowserPolicyStatus {
+  enabled: boolean;
+  browsers: BrowserUrlPolicy[];
+  chromiumUnsupported: string[];
+  error?: string;
+}
+
+export interface SniProxyStatus {
+  enabled: boolean;
+  running: boolean;
+  listen: string;
+  systemProxy?: boolean;
+  error?: string;
+}
+
+export interface DohStatus {
+  providersBlocked: boolean;
+  browsers: BrowserDohPolicy[];
+  bypassPossible: boolean;
 }
 
 export interface BlockEvent {
@@ -62,3 +266,26 @@ export interface BlockEvent {
   timestamp: number;
   message: string;
 }
+
+export interface ProcessKilledEvent {
+  ruleId: string;
+  appName: string;
+  processName: string;
+  processPath: string;
+  pid: number;
+  reason: MatchReason;
+  terminatedPids: number[];
+  forced: boolean;
+  timestamp: number;
+}
+
+export interface TerminationResult {
+  pid: number;
+  outcome:
+    | "terminated"
+    | "alreadyExited"
+    | "permissionDenied"
+    | "protectedProcess"
+    | "killPending";
+  forced: boolean;
+}